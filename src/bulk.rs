@@ -0,0 +1,182 @@
+use std::fmt::{self, Display};
+
+use iced::{Align, Button, button, Column, Element, Length, pick_list, PickList, Row, Text};
+
+use crate::disposition::Disposition;
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap, TextInputState};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Toggle,
+    HealParty,
+    Amount(String),
+    Target(Target),
+    /// asks first, see `Bulk::confirming`
+    Damage,
+    ConfirmDamage,
+    Cancel,
+}
+
+/// Who "damage all" hits
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Target {
+    HiddenEnemies,
+    Enemies,
+    /// whoever the table's filter is showing
+    Shown,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self::HiddenEnemies
+    }
+}
+
+impl Target {
+    pub const ALL: [Self; 3] = [
+        Self::HiddenEnemies,
+        Self::Enemies,
+        Self::Shown,
+    ];
+
+    pub fn matches(self, disposition: Disposition, hidden: bool, shown: bool) -> bool {
+        match self {
+            Self::HiddenEnemies => disposition == Disposition::Enemy && hidden,
+            Self::Enemies => disposition == Disposition::Enemy,
+            Self::Shown => shown,
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::HiddenEnemies => "Hidden enemies",
+            Self::Enemies => "All enemies",
+            Self::Shown => "Everyone shown",
+        })
+    }
+}
+
+/// The bulk actions menu under the turn buttons, the HP changes themselves happen in `update`
+#[derive(Debug, Default)]
+pub struct Bulk {
+    pub open: bool,
+    toggle: button::State,
+    heal_party: button::State,
+    reset_reactions: button::State,
+    pub amount: TextInputState,
+    pub target: Target,
+    target_list: pick_list::State<Target>,
+    damage: button::State,
+    /// how many entities the damage will hit, shown before anyone is hurt
+    pub confirming: Option<usize>,
+    confirm: button::State,
+    cancel: button::State,
+}
+
+impl Bulk {
+    pub fn amount(&self) -> Option<i64> {
+        self.amount.content.parse().ok().filter(|&amount| amount > 0)
+    }
+
+    /// `dm_view` because resetting reactions isn't something the players should see offered
+    pub fn view(&mut self, style: Style, dm_view: bool) -> Element<crate::Message> {
+        let msg = crate::Message::Bulk;
+        let amount = self.amount();
+        let Self { open, toggle, heal_party, reset_reactions, amount: amount_input, target, target_list, damage, confirming, confirm, cancel } = self;
+
+        let toggle = Button::new(toggle, Text::new(if *open { "Hide bulk actions" } else { "Bulk actions" }).size(12))
+            .style(style)
+            .on_press(msg(Message::Toggle));
+        if !*open {
+            return toggle.into();
+        }
+
+        let heal_party = Row::new()
+            .align_items(Align::Center)
+            .push(Button::new(heal_party, Text::new("Heal allies to full").size(14))
+                .style(style)
+                .on_press(msg(Message::HealParty)))
+            .tap_if(dm_view, |row| row
+                .push_space(6)
+                .push(Button::new(reset_reactions, Text::new("Reset all reactions").size(14))
+                    .style(style)
+                    .on_press(crate::Message::ResetAllReactions)));
+        let damage_row = Row::new()
+            .align_items(Align::Center)
+            .push(amount_input.text_input("damage", move |amount| msg(Message::Amount(amount)))
+                .style(style)
+                .size(14)
+                .width(Length::Units(60))
+                .tap_if(amount.is_some(), |txt| txt.on_submit(msg(Message::Damage))))
+            .push_space(6)
+            .push(PickList::new(
+                target_list,
+                Target::ALL.to_vec(),
+                Some(*target),
+                move |target| msg(Message::Target(target)),
+            ).style(style)
+                .text_size(14))
+            .push_space(6)
+            .push(Button::new(damage, Text::new("Damage all").size(14))
+                .style(style)
+                .tap_if(amount.is_some(), |btn| btn.on_press(msg(Message::Damage))));
+
+        Column::new()
+            .align_items(Align::Center)
+            .push(toggle)
+            .push_space(6)
+            .push(heal_party)
+            .push_space(6)
+            .push(damage_row)
+            .tap_if_some(*confirming, |col, n| col
+                .push_space(6)
+                .push(Row::new()
+                    .align_items(Align::Center)
+                    .push(Text::new(format!(
+                        "Deal {} damage to {n} {}?",
+                        amount.unwrap_or(0),
+                        if n == 1 { "entity" } else { "entities" },
+                    )).size(14))
+                    .push_space(6)
+                    .push(Button::new(confirm, Text::new("Confirm").size(14))
+                        .style(style)
+                        .tap_if(n != 0, |btn| btn.on_press(msg(Message::ConfirmDamage))))
+                    .push_space(4)
+                    .push(Button::new(cancel, Text::new("Cancel").size(14))
+                        .style(style)
+                        .on_press(msg(Message::Cancel)))))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        use Disposition::{Ally, Enemy, Neutral};
+        let cases = [
+            // (disposition, hidden, shown) -> [hidden enemies, enemies, shown]
+            ((Enemy, true, false), [true, true, false]),
+            ((Enemy, false, true), [false, true, true]),
+            ((Ally, true, true), [false, false, true]),
+            ((Neutral, false, false), [false, false, false]),
+        ];
+        for ((disposition, hidden, shown), expected) in cases {
+            assert_eq!(Target::ALL.map(|target| target.matches(disposition, hidden, shown)), expected, "{disposition:?}");
+        }
+    }
+
+    #[test]
+    fn amount_has_to_be_positive() {
+        let mut bulk = Bulk::default();
+        for (text, amount) in [("12", Some(12)), ("0", None), ("", None), ("-3", None), ("x", None)] {
+            bulk.amount.content = text.to_string();
+            assert_eq!(bulk.amount(), amount, "{text:?}");
+        }
+    }
+}
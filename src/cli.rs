@@ -0,0 +1,257 @@
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::{Enemy, ENCOUNTER_DIR, ENCOUNTER_VERSION, EncounterFile, PARTY_DIR, Pc, PC_DIR, roll_hp};
+use crate::utils::Hidden;
+
+/// The commands that run without opening a window, for scripting prep. Returns the exit code.
+///
+/// ```text
+/// initiative_manager export "Goblin Ambush" --format md|csv|json
+/// initiative_manager validate
+/// initiative_manager roll "8d8+16"
+/// ```
+pub fn run(command: &str, args: impl Iterator<Item=String>) -> i32 {
+    let result = match command {
+        "export" => export(args),
+        "validate" => validate(),
+        "roll" => roll(args),
+        _ => Err(format!("unknown command `{command}`")),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Format {
+    Markdown,
+    Csv,
+    Json,
+}
+
+fn export(mut args: impl Iterator<Item=String>) -> Result<(), String> {
+    let mut name = None;
+    let mut format = Format::Markdown;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = match args.next().as_deref() {
+                Some("md") => Format::Markdown,
+                Some("csv") => Format::Csv,
+                Some("json") => Format::Json,
+                Some(other) => return Err(format!("unknown format `{other}`, expected md, csv, or json")),
+                None => return Err(String::from("--format needs one of md, csv, or json")),
+            },
+            _ => name = Some(arg),
+        }
+    }
+    let name = name.ok_or_else(|| String::from("usage: initiative_manager export <encounter> [--format md|csv|json]"))?;
+
+    let path = ENCOUNTER_DIR.join(format!("{name}.json"));
+    let file = File::open(&path)
+        .map_err(|e| format!("couldn't open {}: {e}", path.display()))?;
+    let encounter = EncounterFile::from_reader(file)
+        .map_err(|e| format!("{} doesn't parse: {e}", path.display()))?;
+
+    match format {
+        // always written at the current version, so this also upgrades old files
+        Format::Json => serde_json::to_writer_pretty(io::stdout(), &EncounterFile { version: ENCOUNTER_VERSION, ..encounter })
+            .map_err(|e| e.to_string())?,
+        Format::Markdown => print!("{}", markdown(&name, &encounter)),
+        Format::Csv => print!("{}", csv(&encounter.enemies)),
+    }
+    Ok(())
+}
+
+/// `(hidden)` after anything the players wouldn't see
+fn cell<T: ToString>(Hidden(value, hidden): &Hidden<T>) -> String {
+    if *hidden {
+        format!("{} (hidden)", value.to_string())
+    } else {
+        value.to_string()
+    }
+}
+
+fn markdown(name: &str, encounter: &EncounterFile) -> String {
+    let mut md = format!("# {name}\n\n");
+    if encounter.template {
+        md.push_str("A template, HP and initiative are rolled on load.\n\n");
+    }
+    if !encounter.notes.is_empty() {
+        let _ = writeln!(md, "{}\n", encounter.notes);
+    }
+    if !encounter.lair_actions.is_empty() {
+        let _ = writeln!(md, "Lair actions (initiative 20): {}\n", encounter.lair_actions);
+    }
+    md.push_str("| Name | HP | AC | Legendary Actions | Initiative |\n");
+    md.push_str("| --- | --- | --- | --- | --- |\n");
+    for enemy in &encounter.enemies {
+        let hp = match &enemy.hp_formula {
+            Some(formula) => format!("{} ({formula})", cell(&enemy.hp)),
+            None => cell(&enemy.hp),
+        };
+        let _ = writeln!(
+            md,
+            "| {} | {hp} | {} | {} | {} |",
+            cell(&enemy.name).replace('|', "\\|"),
+            enemy.ac.map_or_else(String::new, |ac| ac.to_string()),
+            enemy.legendary_actions.as_ref().map_or_else(String::new, cell),
+            cell(&enemy.initiative),
+        );
+    }
+    md
+}
+
+fn csv(enemies: &[Enemy]) -> String {
+    fn field(str: &str) -> String {
+        if str.contains(&[',', '"', '\n'][..]) {
+            format!("\"{}\"", str.replace('"', "\"\""))
+        } else {
+            str.to_string()
+        }
+    }
+
+    let mut csv = String::from("name,name_hidden,hp,max_hp,hp_formula,hp_hidden,ac,legendary_actions,initiative,initiative_hidden\n");
+    for enemy in enemies {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{}",
+            field(&enemy.name.0),
+            enemy.name.1,
+            enemy.hp.0,
+            enemy.max_hp.unwrap_or(enemy.hp.0),
+            field(enemy.hp_formula.as_deref().unwrap_or_default()),
+            enemy.hp.1,
+            enemy.ac.map_or_else(String::new, |ac| ac.to_string()),
+            enemy.legendary_actions.map_or_else(String::new, |Hidden(la, _)| la.to_string()),
+            enemy.initiative.0,
+            enemy.initiative.1,
+        );
+    }
+    csv
+}
+
+/// Every `.json` file in `dir`, sorted by name, which leaves out `PC_DIR` inside of `PARTY_DIR`
+fn json_files(dir: &Path) -> Result<Vec<std::path::PathBuf>, String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("couldn't read {}: {e}", dir.display()))?;
+    Ok(entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .sorted()
+        .collect())
+}
+
+fn validate() -> Result<(), String> {
+    let mut problems = Vec::new();
+    let mut checked = 0;
+
+    for path in json_files(&ENCOUNTER_DIR)? {
+        checked += 1;
+        let parsed = File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| EncounterFile::from_reader(file).map_err(|e| e.to_string()));
+        match parsed {
+            Err(e) => problems.push(format!("{}: {e}", path.display())),
+            Ok(encounter) => {
+                if encounter.version > ENCOUNTER_VERSION {
+                    problems.push(format!(
+                        "{}: version {} is newer than this app understands ({ENCOUNTER_VERSION})",
+                        path.display(),
+                        encounter.version,
+                    ));
+                }
+                for enemy in &encounter.enemies {
+                    if let Some(formula) = &enemy.hp_formula {
+                        if roll_hp(formula).is_none() {
+                            problems.push(format!("{}: {}'s HP formula `{formula}` doesn't roll", path.display(), enemy.name.0));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for path in json_files(&PARTY_DIR)? {
+        checked += 1;
+        let parsed = File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| serde_json::from_reader::<_, Vec<Pc>>(file).map_err(|e| e.to_string()));
+        if let Err(e) = parsed {
+            problems.push(format!("{}: {e}", path.display()));
+        }
+    }
+
+    for path in json_files(&PC_DIR)? {
+        checked += 1;
+        let parsed = File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| serde_json::from_reader::<_, Pc>(file).map_err(|e| e.to_string()));
+        if let Err(e) = parsed {
+            problems.push(format!("{}: {e}", path.display()));
+        }
+    }
+
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if problems.is_empty() {
+        println!("{checked} files, all fine");
+        Ok(())
+    } else {
+        Err(format!("{} {} in {checked} files", problems.len(), if problems.len() == 1 { "problem" } else { "problems" }))
+    }
+}
+
+fn roll(mut args: impl Iterator<Item=String>) -> Result<(), String> {
+    let formula = args.next()
+        .ok_or_else(|| String::from("usage: initiative_manager roll <dice>, ex. \"8d8+16\""))?;
+    let total = roll_hp(&formula)
+        .ok_or_else(|| format!("`{formula}` isn't something like 8d8+16"))?;
+    println!("{total}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enemy(json: serde_json::Value) -> Enemy {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let enemies = [
+            enemy(serde_json::json!({ "name": ["Goblin, \"Boss\"", false], "hp": [15, true], "hp_formula": "2d8 + 6", "legendary_actions": null, "initiative": [12, false] })),
+            enemy(serde_json::json!({ "name": ["Goblin 1", true], "hp": [7, false], "max_hp": 9, "ac": 15, "legendary_actions": [3, false], "initiative": [8, true] })),
+        ];
+        let csv = csv(&enemies);
+        let mut lines = csv.lines().skip(1);
+        assert_eq!(lines.next(), Some("\"Goblin, \"\"Boss\"\"\",false,15,15,2d8 + 6,true,,,12,false"));
+        assert_eq!(lines.next(), Some("Goblin 1,true,7,9,,false,15,3,8,true"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn markdown_marks_hidden_cells_and_escapes_pipes() {
+        let encounter = EncounterFile {
+            version: ENCOUNTER_VERSION,
+            enemies: vec![enemy(serde_json::json!({ "name": ["Orc | Chief", true], "hp": [30, false], "legendary_actions": [2, true], "initiative": [11, false] }))],
+            notes: String::new(),
+            template: false,
+            lair_actions: String::new(),
+        };
+        let md = markdown("Orcs", &encounter);
+        assert!(md.starts_with("# Orcs\n\n| Name |"));
+        assert!(md.ends_with("| Orc \\| Chief (hidden) | 30 |  | 2 (hidden) | 11 |\n"));
+    }
+}
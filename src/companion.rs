@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// A familiar, animal companion, wild shape... that a player brings in and out of the fight, saved
+/// with them in their party's file
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Companion {
+    pub name: String,
+    /// ex. `2d8 + 2` or `13`, rolled each time they're added
+    pub hp: String,
+    /// held in a group with their owner so they go right after them, instead of rolling their own
+    /// initiative
+    #[serde(default)]
+    pub shares_turn: bool,
+    /// added to their own roll when they don't share their owner's turn
+    #[serde(default)]
+    pub initiative_bonus: i32,
+}
+
+/// What a row's companion menu offers, one for each of `companions`. `present` is whether each is
+/// in the fight already.
+pub fn choices(companions: &[Companion], present: impl Fn(usize) -> bool) -> Vec<String> {
+    companions.iter()
+        .enumerate()
+        .map(|(i, companion)| if present(i) {
+            format!("Dismiss {}", companion.name)
+        } else {
+            format!("Add {}", companion.name)
+        })
+        .collect()
+}
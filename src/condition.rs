@@ -0,0 +1,122 @@
+use iced::button;
+use rand::Rng;
+
+/// Something like "Poisoned", either for some number of rounds or until it's removed
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub name: String,
+    /// `None` lasts until removed
+    pub rounds: Option<u32>,
+    /// the round it was applied in, which doesn't count towards `rounds`
+    pub applied: u32,
+    pub remove_state: button::State,
+}
+
+impl Condition {
+    /// parses `"Poisoned 3"` as 3 rounds of poisoned, and `"Prone"` as prone until removed
+    pub fn parse(text: &str, round: u32) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let (name, rounds) = match text.rsplit_once(' ') {
+            Some((name, rounds)) => match rounds.parse() {
+                Ok(rounds) => (name.trim(), Some(rounds)),
+                Err(_) => (text, None),
+            },
+            None => (text, None),
+        };
+        Some(Self {
+            name: name.to_string(),
+            rounds,
+            applied: round,
+            remove_state: Default::default(),
+        })
+    }
+
+    fn expired(&self) -> bool {
+        self.rounds == Some(0)
+    }
+
+    fn tick(&mut self, round: u32) {
+        if round > self.applied {
+            if let Some(rounds) = &mut self.rounds {
+                *rounds = rounds.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// An ability like a dragon's breath weapon, which comes back on a d6 roll of `min` or higher
+#[derive(Debug)]
+pub struct Recharge {
+    pub name: String,
+    pub min: u32,
+    pub ready: bool,
+    pub use_state: button::State,
+}
+
+impl Recharge {
+    pub fn new(name: String, min: u32) -> Self {
+        Self {
+            name,
+            min,
+            ready: true,
+            use_state: Default::default(),
+        }
+    }
+
+    fn roll(&mut self) {
+        if !self.ready {
+            self.ready = rand::thread_rng().gen_range(1..=6) >= self.min;
+        }
+    }
+}
+
+pub fn tick(conditions: &mut Vec<Condition>, recharges: &mut [Recharge], round: u32) {
+    conditions.iter_mut().for_each(|condition| condition.tick(round));
+    conditions.retain(|condition| !condition.expired());
+    recharges.iter_mut().for_each(Recharge::roll);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_trailing_rounds() {
+        let poisoned = Condition::parse("  Poisoned 3 ", 2).unwrap();
+        assert_eq!((poisoned.name.as_str(), poisoned.rounds, poisoned.applied), ("Poisoned", Some(3), 2));
+        let prone = Condition::parse("Prone", 2).unwrap();
+        assert_eq!((prone.name.as_str(), prone.rounds), ("Prone", None));
+        // not a number, so it's all the name
+        let hex = Condition::parse("Hex of Doom", 1).unwrap();
+        assert_eq!((hex.name.as_str(), hex.rounds), ("Hex of Doom", None));
+        assert!(Condition::parse("   ", 1).is_none());
+    }
+
+    #[test]
+    fn tick_counts_down_after_the_round_it_was_applied_in() {
+        let mut conditions = vec![
+            Condition::parse("Poisoned 2", 1).unwrap(),
+            Condition::parse("Prone", 1).unwrap(),
+        ];
+        // the round it was applied in doesn't count
+        tick(&mut conditions, &mut [], 1);
+        assert_eq!(conditions[0].rounds, Some(2));
+        tick(&mut conditions, &mut [], 2);
+        assert_eq!(conditions[0].rounds, Some(1));
+        tick(&mut conditions, &mut [], 3);
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].name, "Prone");
+    }
+
+    #[test]
+    fn tick_rolls_recharges_that_are_used() {
+        let mut always = Recharge::new(String::from("Breath"), 1);
+        always.ready = false;
+        let mut recharges = [always];
+        tick(&mut Vec::new(), &mut recharges, 1);
+        assert!(recharges[0].ready);
+    }
+}
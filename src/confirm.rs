@@ -0,0 +1,70 @@
+use iced::{Align, Button, button, Color, Column, Element, Row, Text};
+
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap, TextInputState};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Edit(String),
+}
+
+/// Type the name of the thing being deleted before the button does anything
+#[derive(Debug)]
+pub struct ConfirmDelete {
+    pub name: String,
+    on_confirm: crate::Message,
+    /// shown above the prompt, ex. if it's in use
+    warning: Option<String>,
+    pub text: TextInputState,
+    button: button::State,
+}
+
+impl ConfirmDelete {
+    pub fn new(name: String, on_confirm: crate::Message) -> Self {
+        Self {
+            name,
+            on_confirm,
+            warning: None,
+            text: TextInputState::focused(),
+            button: Default::default(),
+        }
+    }
+
+    pub fn warning(mut self, warning: impl Into<String>) -> Self {
+        self.warning = Some(warning.into());
+        self
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Edit(text) => self.text.content = text,
+        }
+    }
+
+    pub fn view(&mut self, style: Style) -> Element<crate::Message> {
+        const WARNING_COLOR: Color = Color::from_rgb(0.9, 0.6, 0.1);
+
+        let Self { name, on_confirm, warning, text, button } = self;
+        let matches = text.content == *name;
+        let input = text.text_input("Delete", |text| crate::Message::Confirm(Message::Edit(text)))
+            .style(style)
+            .tap_if(matches, |txt| txt.on_submit(on_confirm.clone()));
+        let submit = Button::new(
+            button,
+            Text::new(format!("Type '{name}' to confirm")).size(16),
+        ).style(style)
+            .tap_if(matches, |btn| btn.on_press(on_confirm.clone()));
+        let prompt = Row::new()
+            .align_items(Align::Center)
+            .push(input)
+            .push_space(8)
+            .push(submit);
+        Column::new()
+            .align_items(Align::Center)
+            .tap_if_some(warning.as_ref(), |col, warning| col
+                .push(Text::new(warning.as_str()).size(14).color(WARNING_COLOR))
+                .push_space(6))
+            .push(prompt)
+            .into()
+    }
+}
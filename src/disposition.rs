@@ -0,0 +1,81 @@
+use std::fmt::{self, Display};
+
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+/// Which side an entity is on, separate from whether they came from a party or encounter
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Disposition {
+    Ally,
+    Neutral,
+    Enemy,
+}
+
+impl Default for Disposition {
+    fn default() -> Self {
+        Self::Enemy
+    }
+}
+
+impl Disposition {
+    pub const ALL: [Self; 3] = [
+        Self::Ally,
+        Self::Neutral,
+        Self::Enemy,
+    ];
+
+    /// what clicking the accent changes it to
+    pub fn next(self) -> Self {
+        match self {
+            Self::Ally => Self::Neutral,
+            Self::Neutral => Self::Enemy,
+            Self::Enemy => Self::Ally,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Ally => Color::from_rgb(0.3, 0.75, 0.4),
+            Self::Neutral => Color::from_rgb(0.85, 0.75, 0.3),
+            Self::Enemy => Color::from_rgb(0.85, 0.3, 0.3),
+        }
+    }
+}
+
+impl Display for Disposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ally => "Ally",
+            Self::Neutral => "Neutral",
+            Self::Enemy => "Enemy",
+        })
+    }
+}
+
+/// Which entities the table shows, `None` is all of them
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Filter(pub Option<Disposition>);
+
+impl Filter {
+    pub const ALL: [Self; 4] = [
+        Self(None),
+        Self(Some(Disposition::Ally)),
+        Self(Some(Disposition::Neutral)),
+        Self(Some(Disposition::Enemy)),
+    ];
+
+    pub fn shows(self, disposition: Disposition) -> bool {
+        self.0.map_or(true, |only| only == disposition)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.0 {
+            None => "Show everyone",
+            Some(Disposition::Ally) => "Show allies",
+            Some(Disposition::Neutral) => "Show neutrals",
+            Some(Disposition::Enemy) => "Show enemies",
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::SAVE_DIR;
+use crate::style::TagColor;
+
+/// An entity as it should appear to the players
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Row {
+    pub name: String,
+    /// `None` if hidden
+    pub initiative: Option<u32>,
+    pub is_turn: bool,
+    /// tags are meant for the whole table
+    #[serde(default)]
+    pub tag: Option<TagColor>,
+}
+
+/// Writes the turn order to a printable html file in `SAVE_DIR`, returning its path
+pub async fn write_html(rows: Vec<Row>, round: u32) -> Result<PathBuf, String> {
+    let path = SAVE_DIR.join("initiative_order.html");
+    fs::write(&path, html(&rows, round))
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+fn html(rows: &[Row], round: u32) -> String {
+    let mut html = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Initiative Order</title>\n",
+        "<style>body { font-family: Arial, sans-serif; } table { border-collapse: collapse; } ",
+        "td, th { border: 1px solid black; padding: 4px 12px; } .turn { font-weight: bold; }</style>\n",
+        "</head>\n<body>\n",
+    ));
+    let _ = writeln!(html, "<h1>Round {round}</h1>");
+    html.push_str("<table>\n<tr><th>Name</th><th>Initiative</th></tr>\n");
+    for Row { name, initiative, is_turn, .. } in rows {
+        let _ = writeln!(
+            html,
+            "<tr{}><td>{}</td><td>{}</td></tr>",
+            if *is_turn { " class=\"turn\"" } else { "" },
+            escape(name),
+            initiative.map_or_else(|| "?".to_string(), |init| init.to_string()),
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escape(str: &str) -> String {
+    str.chars()
+        .fold(String::with_capacity(str.len()), |mut acc, c| {
+            match c {
+                '&' => acc.push_str("&amp;"),
+                '<' => acc.push_str("&lt;"),
+                '>' => acc.push_str("&gt;"),
+                '"' => acc.push_str("&quot;"),
+                c => acc.push(c),
+            }
+            acc
+        })
+}
+
+/// An entity going into a Roll20 turn tracker
+#[derive(Debug, Clone)]
+pub struct Roll20Entry {
+    /// `None` if hidden
+    pub name: Option<String>,
+    pub censored_name: String,
+    pub initiative: u32,
+}
+
+#[derive(Serialize)]
+struct Roll20Turn {
+    /// "-1" is what Roll20 uses for custom entries that aren't tied to a token
+    id: &'static str,
+    pr: String,
+    custom: String,
+}
+
+/// The json array the Roll20 turn tracker imports, ex. `[{"id":"-1","pr":"17","custom":"Goblin 1"}]`
+pub fn roll20(entries: &[Roll20Entry], skip_hidden: bool) -> String {
+    let turns = entries.iter()
+        .filter(|entry| !skip_hidden || entry.name.is_some())
+        .map(|entry| Roll20Turn {
+            id: "-1",
+            pr: entry.initiative.to_string(),
+            custom: entry.name.clone().unwrap_or_else(|| entry.censored_name.clone()),
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string(&turns).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Roll20Entry> {
+        vec![
+            Roll20Entry { name: Some(String::from("Goblin 1")), censored_name: String::from("Xodlqd z"), initiative: 17 },
+            Roll20Entry { name: None, censored_name: String::from("Bxlwqe"), initiative: 15 },
+            Roll20Entry { name: Some(String::from("\"Lucky\" Pete")), censored_name: String::from("\"Qmzra\" Xofh"), initiative: 12 },
+        ]
+    }
+
+    #[test]
+    fn roll20_matches_golden_file() {
+        let golden = include_str!("../tests/fixtures/roll20_turn_order.json");
+        assert_eq!(roll20(&entries(), false), golden.trim_end());
+    }
+
+    #[test]
+    fn roll20_skipping_hidden_matches_golden_file() {
+        let golden = include_str!("../tests/fixtures/roll20_turn_order_skip_hidden.json");
+        assert_eq!(roll20(&entries(), true), golden.trim_end());
+    }
+}
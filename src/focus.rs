@@ -0,0 +1,40 @@
+use iced::text_input;
+
+/// A button or pick list that Tab can land on. Text inputs keep track of their own focus, these
+/// are remembered in `InitiativeManager::keyboard_focus` and drawn with `style::Focusable`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Control {
+    SubmitNewEntity,
+    NewDisposition,
+    NextTurn,
+    PrevTurn,
+    Filter,
+    /// on whoever's turn it is
+    Row(RowControl),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RowControl {
+    Remove,
+    Pin,
+    Sideline,
+    Reaction,
+    Concentrate,
+    LegendaryMinus,
+    LegendaryPlus,
+}
+
+/// One step of the Tab order
+pub enum Stop<'a> {
+    Input(&'a mut text_input::State),
+    Control(Control),
+}
+
+impl<'a> Stop<'a> {
+    pub fn is_focused(&self, focus: Option<Control>) -> bool {
+        match self {
+            Self::Input(state) => state.is_focused(),
+            Self::Control(control) => focus == Some(*control),
+        }
+    }
+}
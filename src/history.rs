@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::header::{self, HeaderValue};
+use serde::Serialize;
+
+use crate::report::Totals;
+use crate::SAVE_DIR;
+use crate::utils::append_atomic;
+
+static HISTORY_FILE: Lazy<PathBuf> = Lazy::new(|| SAVE_DIR.join("combat_history.jsonl"));
+
+/// bump this whenever a field is renamed, removed, or changes meaning, adding fields doesn't need it
+pub const VERSION: u32 = 1;
+
+/// a failed post waits this long then tries one more time
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// What's posted to `Settings::summary_url` and written as one line of `combat_history.jsonl` when
+/// an encounter is cleared. Names are the real ones, this is for the DM's own records.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "timestamp": "2022-03-14T19:30:00-04:00",
+///   "encounter": "Goblin Ambush",
+///   "rounds": 4,
+///   "damage": [{ "name": "Goblin 1", "damage_taken": 12, "healing": 0 }],
+///   "survivors": ["Aragorn"]
+/// }
+/// ```
+///
+/// `encounter` is `null` if no encounter file was loaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub version: u32,
+    /// RFC 3339, in local time
+    pub timestamp: String,
+    pub encounter: Option<String>,
+    pub rounds: u32,
+    pub damage: Vec<Totals>,
+    /// everyone above 0 HP at the end
+    pub survivors: Vec<String>,
+}
+
+/// Adds `summary` to `SAVE_DIR/combat_history.jsonl`
+pub async fn append(summary: Summary) -> Result<(), String> {
+    let line = serde_json::to_string(&summary).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || append_atomic(&HISTORY_FILE, &line))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{}: {e}", HISTORY_FILE.display()))
+}
+
+/// Posts `summary` as json to `url`, trying again once if it doesn't work
+pub async fn post(url: String, summary: Summary) -> Result<(), String> {
+    let body = serde_json::to_string(&summary).map_err(|e| e.to_string())?;
+    match post_once(&url, body.clone()).await {
+        Ok(()) => Ok(()),
+        Err(_) => {
+            tokio::time::sleep(RETRY_DELAY).await;
+            post_once(&url, body).await
+        }
+    }
+}
+
+async fn post_once(url: &str, body: String) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("the server responded with {}", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the fields are what the doc comment on `Summary` promises whoever reads the history
+    #[test]
+    fn summary_serializes_as_documented() {
+        let summary = Summary {
+            version: VERSION,
+            timestamp: String::from("2022-03-14T19:30:00-04:00"),
+            encounter: None,
+            rounds: 4,
+            damage: vec![Totals { name: String::from("Goblin 1"), damage_taken: 12, healing: 0 }],
+            survivors: vec![String::from("Aragorn")],
+        };
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "version": 1,
+            "timestamp": "2022-03-14T19:30:00-04:00",
+            "encounter": null,
+            "rounds": 4,
+            "damage": [{ "name": "Goblin 1", "damage_taken": 12, "healing": 0 }],
+            "survivors": ["Aragorn"]
+        }));
+    }
+}
@@ -0,0 +1,82 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+/// a command still running after this long is killed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the command is being run for, `{event}` in the template
+#[derive(Debug, Copy, Clone)]
+pub enum Event {
+    Turn,
+    Round,
+    CombatEnd,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Turn => "turn",
+            Self::Round => "round",
+            Self::CombatEnd => "combat_end",
+        }
+    }
+}
+
+/// Runs the user's `template` with `{round}`, `{name}`, and `{event}` filled in, `name` has to be
+/// player-safe since who knows where the script sends it.
+///
+/// There's no shell, the template is split into words (`"` groups words together) before the
+/// placeholders are filled in, so a name can never turn into more arguments.
+pub async fn run(template: String, event: Event, round: u32, name: String) -> Result<(), String> {
+    let round = round.to_string();
+    let mut args = words(&template)?.into_iter()
+        .map(|word| word
+            .replace("{round}", &round)
+            .replace("{name}", &name)
+            .replace("{event}", event.name()));
+    let program = args.next().ok_or_else(|| String::from("the command is empty"))?;
+    let mut child = tokio::process::Command::new(&program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("couldn't run {program}: {e}"))?;
+    match tokio::time::timeout(TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("{program} exited with {status}")),
+        Ok(Err(e)) => Err(format!("{program}: {e}")),
+        Err(_) => Err(format!("{program} took longer than {} seconds", TIMEOUT.as_secs())),
+    }
+}
+
+fn words(template: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    for c in template.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => if in_word {
+                words.push(std::mem::take(&mut word));
+                in_word = false;
+            },
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(String::from("unclosed \" in the command"));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
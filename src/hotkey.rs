@@ -5,11 +5,23 @@ use iced::keyboard::{Event, KeyCode};
 pub enum Message {
     /// true -> forwards, false -> backwards
     NextField(bool),
+    /// presses whatever button Tab landed on, see `focus::Control`
+    Activate,
+    /// any click takes keyboard focus off of buttons
+    Unfocus,
+    /// heals (or damages, if negative) whoever's turn it is
+    NudgeHp(i64),
+    /// the only way back from streaming mode, since it hides the controls
+    ToggleStreaming,
+    /// backs out of picking who to swap initiative with
+    Cancel,
+    /// a few seconds of the secret stats, see `crate::Message::Peek`
+    Peek,
 }
 
 pub fn handle(event: keyboard::Event) -> Option<crate::Message> {
     type Modifiers = (bool, bool, bool);
-    // const CTRL: Modifiers = (true, false, false);
+    const CTRL: Modifiers = (true, false, false);
     const SHIFT: Modifiers = (false, false, true);
     // const CTRL_ALT: Modifiers = (true, true, false);
     // const CTRL_SHIFT: Modifiers = (true, false, true);
@@ -27,6 +39,33 @@ pub fn handle(event: keyboard::Event) -> Option<crate::Message> {
                     SHIFT => Some(Message::NextField(false)),
                     _ => None,
                 }
+                // `+` is shift-`=` on most keyboards, so both `=` and `+` heal
+                KeyCode::Equals | KeyCode::Plus => match modifiers {
+                    NONE => Some(Message::NudgeHp(1)),
+                    SHIFT => Some(Message::NudgeHp(5)),
+                    _ => None,
+                }
+                KeyCode::Enter | KeyCode::Space => match modifiers {
+                    NONE => Some(Message::Activate),
+                    _ => None,
+                }
+                KeyCode::Escape => match modifiers {
+                    NONE => Some(Message::Cancel),
+                    _ => None,
+                }
+                KeyCode::F12 => match modifiers {
+                    NONE => Some(Message::ToggleStreaming),
+                    _ => None,
+                }
+                KeyCode::P => match modifiers {
+                    CTRL => Some(Message::Peek),
+                    _ => None,
+                }
+                KeyCode::Minus => match modifiers {
+                    NONE => Some(Message::NudgeHp(-1)),
+                    SHIFT => Some(Message::NudgeHp(-5)),
+                    _ => None,
+                }
                 _ => None,
             };
             message.map(crate::Message::HotKey)
@@ -0,0 +1,122 @@
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use iced::Text;
+use iced_aw::{Icon, ICON_FONT};
+use serde::{Deserialize, Serialize};
+
+/// A small icon shown before an entity's name
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityIcon {
+    Glyph(Glyph),
+    /// The glow renderer can't draw images, so these show as a generic picture glyph (or
+    /// `MISSING` if the file wasn't there) while still being saved with the encounter.
+    Image(ImagePath),
+}
+
+/// A picture's path, saved as just the path. Whether the file is there is checked once, when it's
+/// typed in or loaded, instead of on every redraw.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(from = "PathBuf", into = "PathBuf")]
+pub struct ImagePath {
+    pub path: PathBuf,
+    found: bool,
+}
+
+impl From<PathBuf> for ImagePath {
+    fn from(path: PathBuf) -> Self {
+        let found = path.is_file();
+        Self { path, found }
+    }
+}
+
+impl From<ImagePath> for PathBuf {
+    fn from(image: ImagePath) -> Self {
+        image.path
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Glyph {
+    Person,
+    Shield,
+    Bug,
+    Star,
+    Heart,
+    Lightning,
+    Moon,
+    Droplet,
+}
+
+impl Glyph {
+    pub const ALL: [Self; 8] = [
+        Self::Person,
+        Self::Shield,
+        Self::Bug,
+        Self::Star,
+        Self::Heart,
+        Self::Lightning,
+        Self::Moon,
+        Self::Droplet,
+    ];
+
+    fn icon(self) -> Icon {
+        match self {
+            Self::Person => Icon::PersonFill,
+            Self::Shield => Icon::ShieldFill,
+            Self::Bug => Icon::Bug,
+            Self::Star => Icon::StarFill,
+            Self::Heart => Icon::HeartFill,
+            Self::Lightning => Icon::Lightning,
+            Self::Moon => Icon::Moon,
+            Self::Droplet => Icon::Droplet,
+        }
+    }
+}
+
+impl Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Person => "Person",
+            Self::Shield => "Shield",
+            Self::Bug => "Bug",
+            Self::Star => "Star",
+            Self::Heart => "Heart",
+            Self::Lightning => "Lightning",
+            Self::Moon => "Moon",
+            Self::Droplet => "Droplet",
+        })
+    }
+}
+
+impl EntityIcon {
+    const MISSING: Icon = Icon::QuestionCircle;
+
+    pub fn text(&self, size: u16) -> Text {
+        let icon = match self {
+            Self::Glyph(glyph) => glyph.icon(),
+            Self::Image(ImagePath { found: true, .. }) => Icon::Image,
+            Self::Image(_) => Self::MISSING,
+        };
+        Text::new(icon)
+            .font(ICON_FONT)
+            .size(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_are_saved_as_just_their_path() {
+        let icon = EntityIcon::Image(PathBuf::from("no/such/goblin.png").into());
+        let json = serde_json::to_string(&icon).unwrap();
+        assert_eq!(json, r#"{"image":"no/such/goblin.png"}"#);
+        let read: EntityIcon = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, icon);
+        assert!(matches!(read, EntityIcon::Image(ImagePath { found: false, .. })));
+    }
+}
@@ -16,14 +16,14 @@ clippy::cast_possible_wrap,
 )]
 // @formatter:on
 
-#![feature(array_windows)]
-#![feature(array_chunks)]
-
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
 use std::fs;
 use std::fs::{FileType, OpenOptions};
-use std::path::PathBuf;
-use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 use iced::*;
 use iced::tooltip::Position;
@@ -34,15 +34,47 @@ use once_cell::sync::Lazy;
 use rand::Rng;
 use self_update::cargo_crate_version;
 use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
 
-use crate::style::{SettingsBarStyle, Style};
-use crate::utils::{censor_name, checkbox, Hidden, Hp, MakeHidden, SpacingExt, Tap, TextInputState, ToggleButtonState, TooltipExt};
+use crate::companion::Companion;
+use crate::condition::{Condition, Recharge};
+use crate::confirm::ConfirmDelete;
+use crate::disposition::Disposition;
+use crate::focus::{Control, RowControl, Stop};
+use crate::icon::{EntityIcon, Glyph};
+use crate::settings::{AtZero, LegendaryDisplay, LegendaryReset, Setting, Settings, TieRule};
+use crate::settings_panel::SettingsPanel;
+use crate::style::{Focusable, Invalid, SettingsBarStyle, Style, TagColor};
+use crate::utils::{AttackRoll, censor_name, checkbox, CurrentMaxHp, Hidden, Hp, MakeHidden, parse_hex_color, SpacingExt, Tap, TextInputState, ToggleButtonState, TooltipExt};
 
 #[macro_use]
 mod utils;
 mod style;
 mod hotkey;
 mod update;
+mod settings;
+mod export;
+mod srd;
+mod icon;
+mod confirm;
+mod condition;
+mod disposition;
+mod server;
+mod sync;
+mod webhook;
+mod overlay;
+mod hook;
+mod report;
+mod history;
+mod recap;
+mod bulk;
+mod focus;
+mod settings_panel;
+mod tie;
+mod companion;
+mod scratchpad;
+mod reminder;
+mod cli;
 
 static SAVE_DIR: Lazy<PathBuf> = Lazy::new(|| {
     let path = dirs::data_local_dir().unwrap_or_default()
@@ -56,6 +88,13 @@ static PARTY_DIR: Lazy<PathBuf> = Lazy::new(|| {
     std::fs::create_dir_all(&path).unwrap();
     path
 });
+/// single players saved on their own, see `SaveMode::AssembleParty`
+static PC_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let path = PARTY_DIR.clone()
+        .join("pcs");
+    std::fs::create_dir_all(&path).unwrap();
+    path
+});
 static ENCOUNTER_DIR: Lazy<PathBuf> = Lazy::new(|| {
     let path = SAVE_DIR.clone()
         .join("encounters");
@@ -63,41 +102,288 @@ static ENCOUNTER_DIR: Lazy<PathBuf> = Lazy::new(|| {
     path
 });
 
+/// first in the load players list, see `SaveMode::AssembleParty`
+const ASSEMBLE_PARTY: &str = "Pick players...";
+/// what an assembled party that wasn't saved is called
+const ADHOC_PARTY: &str = "Tonight's party";
+
+/// Everyone that could be picked for `SaveMode::AssembleParty`, saved players first then each party
+fn assemble_rows() -> Vec<(String, Pc, bool)> {
+    fn json_files(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir).into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "json"))
+            .sorted()
+            .collect()
+    }
+
+    let pcs = json_files(&PC_DIR).into_iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|json| serde_json::from_str::<Pc>(&json).ok())
+        .map(|pc| (String::from("player"), pc, false));
+    let parties = json_files(&PARTY_DIR).into_iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let pcs = serde_json::from_str::<Vec<Pc>>(&fs::read_to_string(path).ok()?).ok()?;
+            Some(pcs.into_iter().map(move |pc| (name.clone(), pc, false)))
+        })
+        .flatten();
+    pcs.chain(parties).collect()
+}
+
+/// how many `Snapshot`s are kept to rewind to
+const SNAPSHOT_LEN: usize = 20;
+
+/// when lair actions happen, see `InitiativeManager::lair_actions`
+const LAIR_INITIATIVE: u32 = 20;
+
+/// how long an auto-removed monster can be brought back for
+const UNDO_REMOVE_WINDOW: Duration = Duration::from_secs(10);
+/// the same for a click on someone's name, which is more often a misclick than a meant removal
+const UNDO_DELETE_WINDOW: Duration = Duration::from_secs(5);
+
+/// older rounds fall off of `InitiativeManager::round_history`
+const ROUND_HISTORY_LEN: usize = 20;
+
+/// Identifies an entity regardless of where it is in the order or what it's named.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct EntityId(u64);
+
+static NEXT_ENTITY_ID: AtomicU64 = AtomicU64::new(0);
+
+impl EntityId {
+    fn next() -> Self {
+        Self(NEXT_ENTITY_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// make sure `next` never hands out an id that was loaded from a file
+    fn reserve(self) -> Self {
+        NEXT_ENTITY_ID.fetch_max(self.0 + 1, Ordering::Relaxed);
+        self
+    }
+}
+
+/// What kind of file an entity was loaded from, see `InitiativeManager::loaded_encounter`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Origin {
+    Encounter,
+    Party,
+}
+
 #[derive(Debug)]
-struct Entity {
+pub struct Entity {
+    id: EntityId,
+    /// `None` if added by hand
+    origin: Option<Origin>,
+    disposition: Disposition,
+    disposition_state: button::State,
+    /// a marker for the whole table, see `Settings::tag_legend`
+    tag: Option<TagColor>,
+    tag_state: button::State,
+    /// the name of who summoned them, which might not be in the fight anymore
+    owner: Option<String>,
+    /// held together with others at the same initiative, see `InitiativeManager::regroup`. The id is
+    /// whoever started the group.
+    group: Option<EntityId>,
+    group_state: button::State,
     name: Hidden<String>,
+    /// scrambled on creation and when re-hidden, not every frame
+    censored_name: String,
     remove_state: button::State,
     hp: Hidden<u32>,
+    max_hp: u32,
+    /// what `hp` was rolled from, if it was
+    hp_formula: Option<String>,
+    ac: Option<u32>,
+    icon: Option<EntityIcon>,
     damage: TextInputState,
     heal: TextInputState,
-    reaction_free: ToggleButtonState,
+    attack: TextInputState,
+    /// (left, per round), most things only get one
+    reactions: (u32, u32),
+    reaction_state: button::State,
+    /// only shown when they get more than one
+    reaction_plus: button::State,
     concentrating: ToggleButtonState,
     legendary_actions: Option<Hidden<(u32, u32)>>,
     la_minus: button::State,
     la_plus: button::State,
     initiative: Hidden<u32>,
+    /// usually their DEX modifier, breaks ties in `insert_entity`. From `Enemy::initiative_bonus`,
+    /// nothing else has one.
+    initiative_bonus: Option<i32>,
     init_up: button::State,
+    roll_off_state: button::State,
     init_down: button::State,
+    conditions: Vec<Condition>,
+    recharges: Vec<Recharge>,
+    new_condition: TextInputState,
+    /// one for each of `Settings::quick_conditions`, kept the same length by `view`
+    quick_states: Vec<button::State>,
+    /// whether their turn has come and gone this round, shown as a check at the end of their row
+    /// that can be clicked to fix it up (a readied action used up their turn...)
+    acted: bool,
+    acted_state: button::State,
+    /// a monster left at 0 HP, see `Settings::at_zero`. Skipped in the order until healed.
+    dead: bool,
+    /// `AtZero::Prompt` is waiting on a `ZeroChoice` for them
+    zero_prompt: bool,
+    zero_choices: [button::State; 3],
+    /// when their last turn ended, shown to the DM as "2m ago" to spot who's been quiet a while
+    last_turn: Option<Instant>,
+    /// running totals for the HP tooltip and the `recap::Recap`, updated by `change_hp` and `Message::Damage`
+    damage_dealt: u64,
+    damage_taken: u64,
+    healing_received: u64,
+    /// in the list but not yet in the fight, skipped by `NextTurn`/`PrevTurn` until activated
+    staged: bool,
+    activate_state: button::State,
+    /// skips their first turn, only matters in round 1
+    surprised: bool,
+    surprised_state: button::State,
+    /// kept (HP and all) when the encounter is cleared
+    pinned: ToggleButtonState,
+    sideline_state: button::State,
+    swap_state: button::State,
+    /// a player's, from their party file, brought in and out with `companion_list`
+    companions: Vec<Companion>,
+    companion_list: pick_list::State<String>,
+    /// the owner and which of their `companions` this is
+    companion_of: Option<(EntityId, usize)>,
 }
 
 impl Entity {
-    fn new(name: Hidden<String>, hp: Hidden<u32>, initiative: Hidden<u32>) -> Self {
+    pub fn new(name: Hidden<String>, hp: Hidden<u32>, initiative: Hidden<u32>) -> Self {
+        let censored_name = censor_name(&name.0);
         Self {
+            id: EntityId::next(),
+            origin: None,
+            disposition: Default::default(),
+            disposition_state: Default::default(),
+            tag: None,
+            tag_state: Default::default(),
+            owner: None,
+            group: None,
+            group_state: Default::default(),
             name,
+            censored_name,
             remove_state: Default::default(),
+            max_hp: hp.0,
             hp,
+            hp_formula: None,
+            ac: None,
+            icon: None,
             damage: Default::default(),
             heal: Default::default(),
-            reaction_free: ToggleButtonState::new(true),
+            attack: Default::default(),
+            reactions: (1, 1),
+            reaction_state: Default::default(),
+            reaction_plus: Default::default(),
             concentrating: ToggleButtonState::new(false),
+            pinned: ToggleButtonState::new_with(false, [Icon::Pin, Icon::PinFill]),
+            sideline_state: Default::default(),
+            swap_state: Default::default(),
+            companions: Vec::new(),
+            companion_list: Default::default(),
+            companion_of: None,
             legendary_actions: Default::default(),
             la_minus: Default::default(),
             la_plus: Default::default(),
             initiative,
+            initiative_bonus: None,
             init_up: Default::default(),
+            roll_off_state: Default::default(),
             init_down: Default::default(),
+            conditions: Vec::new(),
+            recharges: Vec::new(),
+            new_condition: Default::default(),
+            quick_states: Vec::new(),
+            acted: false,
+            acted_state: Default::default(),
+            dead: false,
+            zero_prompt: false,
+            zero_choices: Default::default(),
+            last_turn: None,
+            damage_dealt: 0,
+            damage_taken: 0,
+            healing_received: 0,
+            staged: false,
+            activate_state: Default::default(),
+            surprised: false,
+            surprised_state: Default::default(),
+        }
+    }
+
+    fn rescramble(&mut self) {
+        self.censored_name = censor_name(&self.name.0);
+    }
+
+    /// heals if `by` is positive, damages if it's negative, so a negative in either box does the
+    /// opposite of what the box says
+    fn change_hp(&mut self, by: i64) {
+        let amount = by.unsigned_abs().min(u32::MAX as u64) as u32;
+        self.hp.0 = if by < 0 {
+            self.damage_taken += amount as u64;
+            self.hp.0.saturating_sub(amount)
+        } else {
+            self.healing_received += amount as u64;
+            self.hp.0.saturating_add(amount)
+        };
+        if self.hp.0 != 0 {
+            self.dead = false;
+            self.zero_prompt = false;
+        }
+    }
+
+    fn combatant(&self) -> recap::Combatant {
+        recap::Combatant {
+            name: self.name.0.clone(),
+            damage_dealt: self.damage_dealt,
+            damage_taken: self.damage_taken,
+            healing: self.healing_received,
+            hp: self.hp.0,
         }
     }
+
+    /// A copy of everything about them but what's being typed and clicked, see `Snapshot`
+    fn snapshot(&self) -> Self {
+        let mut entity = Self::new(self.name.clone(), self.hp, self.initiative);
+        entity.id = self.id;
+        entity.origin = self.origin;
+        entity.disposition = self.disposition;
+        entity.tag = self.tag;
+        entity.owner = self.owner.clone();
+        entity.group = self.group;
+        entity.censored_name = self.censored_name.clone();
+        entity.max_hp = self.max_hp;
+        entity.hp_formula = self.hp_formula.clone();
+        entity.ac = self.ac;
+        entity.icon = self.icon.clone();
+        entity.reactions = self.reactions;
+        entity.concentrating.value = self.concentrating.value;
+        entity.legendary_actions = self.legendary_actions;
+        entity.conditions = self.conditions.clone();
+        entity.recharges = self.recharges.iter()
+            .map(|recharge| Recharge { ready: recharge.ready, ..Recharge::new(recharge.name.clone(), recharge.min) })
+            .collect();
+        entity.acted = self.acted;
+        entity.dead = self.dead;
+        entity.zero_prompt = self.zero_prompt;
+        entity.last_turn = self.last_turn;
+        entity.damage_dealt = self.damage_dealt;
+        entity.damage_taken = self.damage_taken;
+        entity.healing_received = self.healing_received;
+        entity.staged = self.staged;
+        entity.surprised = self.surprised;
+        entity.pinned.value = self.pinned.value;
+        entity.companions = self.companions.clone();
+        entity.companion_of = self.companion_of;
+        entity.initiative_bonus = self.initiative_bonus;
+        entity
+    }
 }
 
 #[derive(Default)]
@@ -106,116 +392,449 @@ struct NewEntity {
     init: Hidden<TextInputState>,
     hp: Hidden<TextInputState>,
     leg_acts: Hidden<TextInputState>,
+    /// per round, empty for the usual one
+    reactions: TextInputState,
+    /// how many to add, empty is 1
+    count: TextInputState,
+    /// all of `count` go on one initiative roll, held together as a group
+    single_roll: bool,
+    staged: bool,
+    surprised: bool,
+    disposition: Disposition,
+    disposition_list: pick_list::State<Disposition>,
+    owner: TextInputState,
+    icon: Option<Glyph>,
+    icon_list: pick_list::State<Glyph>,
+    /// overrides `icon` if not empty
+    icon_path: TextInputState,
+}
+
+/// What's wrong with each field of `NewEntity`, shown under that field. Submitting is only
+/// allowed once there's nothing here.
+#[derive(Debug, Default, Copy, Clone)]
+struct NewEntityErrors {
+    name: Option<&'static str>,
+    init: Option<&'static str>,
+    hp: Option<&'static str>,
+    leg_acts: Option<&'static str>,
+    reactions: Option<&'static str>,
+    count: Option<&'static str>,
+}
+
+impl NewEntityErrors {
+    fn any(&self) -> bool {
+        let Self { name, init, hp, leg_acts, reactions, count } = self;
+        name.is_some() || init.is_some() || hp.is_some() || leg_acts.is_some() || reactions.is_some() || count.is_some()
+    }
+}
+
+impl NewEntity {
+    /// Only checks what can be typed, `Message::NewInit` etc. already stop anything else
+    fn errors(&self) -> NewEntityErrors {
+        let init = &self.init.0.content;
+        let hp = &self.hp.0.content;
+        let leg_acts = &self.leg_acts.0.content;
+        NewEntityErrors {
+            name: Some("Name required").filter(|_| self.name.0.content.trim().is_empty()),
+            init: Some("Add a number after the sign").filter(|_| init == "+" || init == "-"),
+            hp: Some("HP formula incomplete").filter(|_| !hp.is_empty() && hp.parse::<CurrentMaxHp>()
+                .ok()
+                .and_then(CurrentMaxHp::into_numbers)
+                .is_none()),
+            leg_acts: Some("Needs at least 1, or leave it empty").filter(|_| leg_acts.parse::<u32>().ok() == Some(0)),
+            reactions: Some("Needs at least 1, or leave it empty").filter(|_| self.reactions.content.parse::<u32>().ok() == Some(0)),
+            count: Some("Needs at least 1, or leave it empty").filter(|_| self.count.content.parse::<u32>().ok() == Some(0)),
+        }
+    }
+
+    /// The name's error is held back until something's been typed, so the empty form isn't red
+    fn started(&self) -> bool {
+        [&self.name.0, &self.init.0, &self.hp.0, &self.leg_acts.0, &self.reactions, &self.count, &self.owner].iter()
+            .any(|txt| !txt.content.is_empty())
+    }
 }
 
 #[derive(Deserialize, Serialize)]
 struct Pc {
+    #[serde(default)]
+    id: Option<EntityId>,
     name: String,
     hp: u32,
+    #[serde(default)]
+    max_hp: Option<u32>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    companions: Vec<Companion>,
 }
 
-#[derive(Deserialize, Serialize)]
+impl Pc {
+    /// For the hp they start at when loaded, which can't be over their max
+    fn hp_invalid(&self, hp: &str) -> bool {
+        hp.parse::<u32>().map_or(false, |hp| hp > self.max_hp.unwrap_or(self.hp))
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 struct Enemy {
+    #[serde(default)]
+    id: Option<EntityId>,
     name: Hidden<String>,
     hp: Hidden<u32>,
+    #[serde(default)]
+    max_hp: Option<u32>,
+    #[serde(default)]
+    ac: Option<u32>,
+    /// ex. `2d8 + 2`, rolled instead of using `hp` if the DM wants
+    #[serde(default)]
+    hp_formula: Option<String>,
+    /// from `hp_formula`, only while loading
+    #[serde(skip)]
+    rolled_hp: Option<u32>,
+    #[serde(default)]
+    icon: Option<EntityIcon>,
+    #[serde(default)]
+    disposition: Disposition,
+    #[serde(default)]
+    owner: Option<String>,
     legendary_actions: Option<Hidden<u32>>,
+    /// reactions per round, `None` for the usual one
+    #[serde(default)]
+    reactions: Option<u32>,
     initiative: Hidden<u32>,
+    /// added to the d20 when a template is loaded, and breaks initiative ties (see
+    /// `InitiativeManager::insert_entity`) since it's usually their DEX modifier
+    #[serde(default)]
+    initiative_bonus: Option<i32>,
+    /// position in the initiative order when saved, so ties load back in the same order
+    #[serde(default)]
+    order: usize,
+    /// for a template, the initiative each extra copy rolled, so the preview shows where they'll
+    /// really go, only while loading
+    #[serde(skip)]
+    copy_initiatives: Vec<u32>,
+}
+
+impl Enemy {
+    /// The initiative of copy number `copy` of this enemy, where 0 is the enemy itself. Copies
+    /// that weren't rolled (ex. not a template) go when it does.
+    fn copy_initiative(&self, copy: usize) -> u32 {
+        copy.checked_sub(1)
+            .and_then(|i| self.copy_initiatives.get(i).copied())
+            .unwrap_or(self.initiative.0)
+    }
+}
+
+/// Changes to an encounter as it's loaded, ex. for more (or fewer) players
+#[derive(Default)]
+struct LoadAdjustments {
+    /// empty is 1
+    hp: TextInputState,
+    /// how many more of each enemy to add, empty is 0
+    copies: TextInputState,
+    /// use `Enemy::rolled_hp` instead of the saved HP
+    reroll: bool,
+    reroll_button: button::State,
+    /// the monsters' initiatives get rolled at the table, see `SaveMode::EnterInitiatives`
+    by_hand: bool,
+    /// from `EncounterFile::template`, so the extra copies roll their own initiative too
+    template: bool,
+    /// from `EncounterFile::lair_actions`, carried along until it's loaded
+    lair_actions: String,
+}
+
+impl LoadAdjustments {
+    fn hp(&self, hp: u32) -> u32 {
+        let multiplier = self.hp.content.parse::<f64>().unwrap_or(1.0);
+        (hp as f64 * multiplier).floor() as u32
+    }
+
+    fn copies(&self) -> usize {
+        self.copies.content.parse().unwrap_or(0)
+    }
+
+    fn reroll_all(enemies: &mut [Enemy]) {
+        for enemy in enemies {
+            enemy.rolled_hp = enemy.hp_formula.as_deref().and_then(roll_hp);
+        }
+    }
+
+    fn roll_initiatives(enemies: &mut [Enemy]) {
+        for enemy in enemies {
+            enemy.initiative.0 = roll_initiative(enemy.initiative_bonus.unwrap_or(0));
+        }
+    }
+
+    /// Rolls initiative for each of the first `copies` copies of each enemy that hasn't rolled yet,
+    /// keeping the ones that have so changing the count doesn't reshuffle the preview
+    fn roll_copies(enemies: &mut [Enemy], copies: usize) {
+        for enemy in enemies {
+            while enemy.copy_initiatives.len() < copies {
+                enemy.copy_initiatives.push(roll_initiative(enemy.initiative_bonus.unwrap_or(0)));
+            }
+        }
+    }
+}
+
+/// Puts `enemies` in the order they were in when saved, to be put in the fight one at a time with
+/// `InitiativeManager::insert_in_order`
+fn sort_for_loading(enemies: &mut [Enemy]) {
+    // files saved before `order` existed all have 0, and the stable sort keeps them in file order
+    enemies.sort_by_key(|enemy| (Reverse(enemy.initiative.0), enemy.order));
+}
+
+/// An entity that could go in a saved encounter or party, see `SaveMode::SaveEncounter`
+struct SaveRow {
+    id: EntityId,
+    name: String,
+    hp: u32,
+    initiative: u32,
+    included: bool,
+}
+
+impl SaveRow {
+    /// starts with the entities that probably belong in an encounter (or party if `!encounter`)
+    /// already included
+    fn all(entities: &[Entity], encounter: bool) -> Vec<Self> {
+        // companions are saved along with their owner in the party file, never on their own
+        entities.iter()
+            .filter(|entity| entity.companion_of.is_none())
+            .map(|entity| {
+                let from_party = entity.origin == Some(Origin::Party);
+                let included = if encounter {
+                    !from_party && (entity.name.1 || entity.disposition == Disposition::Enemy)
+                } else {
+                    from_party || (!entity.name.1 && entity.disposition == Disposition::Ally)
+                };
+                Self {
+                    id: entity.id,
+                    name: entity.name.0.clone(),
+                    hp: entity.hp.0,
+                    initiative: entity.initiative.0,
+                    included,
+                }
+            })
+            .collect()
+    }
+
+    /// Laid out like the `LoadEncounter` preview, with a checkbox by each name
+    fn view<'a>(
+        name: TextInput<'a, Message>,
+        notes: Option<TextInput<'a, Message>>,
+        submit: Button<'a, Message>,
+        scroll: &'a mut scrollable::State,
+        rows: &'a [Self],
+        style: Style,
+    ) -> Element<'a, Message> {
+        let [names, hps, inits] = rows.iter()
+            .enumerate()
+            .fold(["Save", "HP", "Initiative"].map(|title| vec![Element::from(Text::new(title))]),
+                  |[mut names, mut hps, mut inits], (i, row)| {
+                      names.push(Checkbox::new(row.included, &row.name, move |included| Message::SaveSelect(i, included))
+                          .style(style)
+                          .size(16)
+                          .text_size(16)
+                          .into());
+                      hps.push(Text::new(row.hp.to_string()).size(16).into());
+                      inits.push(Text::new(row.initiative.to_string()).size(16).into());
+                      [names, hps, inits]
+                  });
+        let table = Scrollable::new(scroll)
+            .push(Row::new()
+                .push(Column::with_children(names).spacing(5))
+                .push_space(Length::Fill)
+                .push(Column::with_children(hps).spacing(5))
+                .push_space(Length::Fill)
+                .push(Column::with_children(inits).spacing(5)));
+        Column::new()
+            .align_items(Align::Center)
+            .push(Row::new()
+                .align_items(Align::Center)
+                .push(name)
+                .push_space(8)
+                .push(submit))
+            .tap_if_some(notes, |col, notes| col
+                .push_space(7)
+                .push(notes))
+            .push_space(7)
+            .push(table)
+            .into()
+    }
 }
 
 enum SaveMode {
     None,
-    SaveEncounter(TextInputState, button::State),
-    DeleteEncounter(String, TextInputState, button::State),
-    LoadEncounter(String, button::State, scrollable::State, Vec<Enemy>),
-    SaveParty(TextInputState, button::State),
-    DeleteParty(String, TextInputState, button::State),
-    LoadParty(String, button::State, scrollable::State, Vec<(Pc, TextInputState)>),
+    /// the name, notes, lair actions, and whether it's saved as a template (see `EncounterFile::template`)
+    SaveEncounter(TextInputState, TextInputState, TextInputState, bool, button::State, scrollable::State, Vec<SaveRow>),
+    DeleteEncounter(ConfirmDelete),
+    /// the `bool` is whether to number duplicate names, the last button copies it for Roll20, and
+    /// the last `String` is the file's `EncounterFile::notes`
+    LoadEncounter(String, button::State, scrollable::State, Vec<Enemy>, bool, button::State, LoadAdjustments, String),
+    /// the second button saves each player on their own instead, in `PC_DIR`
+    SaveParty(TextInputState, button::State, button::State, scrollable::State, Vec<SaveRow>),
+    DeleteParty(ConfirmDelete),
+    /// each row is the pc, their initiative (typed, or dragged with the slider), how many times the
+    /// player has sent their own initiative (see `server::Submission`), and an hp to start at (blank
+    /// for the saved one)
+    LoadParty(String, button::State, scrollable::State, Vec<(Pc, TextInputState, slider::State, u32, TextInputState)>),
+    /// tonight's roster picked from every party and saved player, each row being where it's from
+    /// and whether it's picked. Continues on to `LoadParty`, saved as a party first if it's named.
+    AssembleParty(TextInputState, button::State, scrollable::State, Vec<(String, Pc, bool)>),
+    /// right after loading an encounter with `LoadAdjustments::by_hand`, the initiatives rolled at
+    /// the table for each enemy that was added, with their saved initiative for any left blank
+    EnterInitiatives(button::State, scrollable::State, Vec<(EntityId, String, u32, TextInputState)>),
+}
+
+/// bump this whenever a field of `EncounterFile` is renamed, removed, or changes meaning
+const ENCOUNTER_VERSION: u32 = 2;
+
+/// In front of encounter files that don't parse in the load list, which won't load them
+const UNREADABLE: &str = "⚠ ";
+
+/// What's saved in `ENCOUNTER_DIR`
+#[derive(Deserialize, Serialize)]
+struct EncounterFile {
+    version: u32,
+    enemies: Vec<Enemy>,
+    /// the DM's prep, terrain, tactics, treasure...
+    #[serde(default)]
+    notes: String,
+    /// fresh monsters each time it's loaded, with HP and initiative rolled again, instead of this
+    /// exact fight. Files from before version 2 are all the exact fight.
+    #[serde(default)]
+    template: bool,
+    /// read out on initiative 20, see `InitiativeManager::lair_banner`
+    #[serde(default)]
+    lair_actions: String,
+}
+
+impl EncounterFile {
+    /// Files saved before `version` existed are just the list of enemies
+    fn from_reader(reader: impl std::io::Read) -> serde_json::Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Saved {
+            File(EncounterFile),
+            Enemies(Vec<Enemy>),
+        }
+
+        Ok(match serde_json::from_reader(reader)? {
+            Saved::File(file) => file,
+            Saved::Enemies(enemies) => Self { version: 0, enemies, notes: String::new(), template: false, lair_actions: String::new() },
+        })
+    }
 }
 
 impl SaveMode {
-    fn view(&mut self, style: Style) -> Element<Message> {
+    /// The name, HP, legendary actions, and initiative columns of the `LoadEncounter` preview, each
+    /// with a heading and then one row per enemy
+    fn preview_columns(enemies: &[Enemy], adjust: &LoadAdjustments, legendary_display: LegendaryDisplay, style: Style) -> [Vec<Element<'static, Message>>; 4] {
+        let copies = adjust.copies();
+        let reroll = adjust.reroll;
+        enemies.iter()
+            .enumerate()
+            .fold(["Name (Hidden)", "HP (Hidden)", "Leg. Acts. (Hidden)", "Initiative (Hidden)"].map(|title| vec![Element::from(Text::new(title))]),
+                  |[mut names, mut hps, mut las, mut inits], (idx, Enemy { name, hp, rolled_hp, legendary_actions, initiative, .. })| {
+                      fn view<T: Display>(Hidden(t, hidden): &Hidden<T>, idx: usize, part: HideablePart, style: Style) -> Element<'static, Message> {
+                          let hide = checkbox(*hidden, move |hidden| Message::EncounterHide(idx, hidden, part))
+                              .style(style)
+                              .size(16);
+                          let row = Row::new()
+                              .push(Text::new(format!("{t} (")).size(16))
+                              .push(hide)
+                              .push(Text::new(')').size(16));
+                          row.into()
+                      }
+
+                      if copies == 0 {
+                          names.push(view(&name, idx, HideablePart::Name, style));
+                      } else {
+                          let name = Hidden(format!("{} ×{}", name.0, copies + 1), name.1);
+                          names.push(view(&name, idx, HideablePart::Name, style));
+                      }
+                      // let name = Text::new(format!("{name} ({})", if *hidden { '✔' } else { '❌' })).size(16);
+                      // names.push(name.into());
+
+                      let hp_before_scaling = rolled_hp.filter(|_| reroll).unwrap_or(hp.0);
+                      hps.push(view(&Hidden(adjust.hp(hp_before_scaling), hp.1), idx, HideablePart::Hp, style));
+                      // let hp = Text::new(hp.to_string()).size(16);
+                      // hps.push(hp.into());
+
+                      // every column gets a row for every enemy so they stay lined up
+                      match legendary_actions {
+                          Some(Hidden(la, hidden)) => {
+                              let la = Hidden(legendary_display.text(*la, *la), *hidden);
+                              las.push(view(&la, idx, HideablePart::LegActs, style));
+                          }
+                          None => las.push(Text::new("—").size(16).into()),
+                      }
+
+                      inits.push(view(&initiative, idx, HideablePart::Initiative, style));
+                      // let init = Text::new(initiative.to_string()).size(16);
+                      // inits.push(init.into());
+
+                      [names, hps, las, inits]
+                  })
+    }
+
+    /// `load_preview` is what loading will change in the current fight, see `InitiativeManager::load_preview`
+    fn view<'a>(&'a mut self, style: Style, legendary_display: LegendaryDisplay, load_preview: &[String]) -> Element<'a, Message> {
         match self {
             SaveMode::None => Space::new(Length::Shrink, Length::Shrink).into(),
-            SaveMode::SaveEncounter(text, button) => {
-                let savable = !text.content.is_empty();
+            SaveMode::SaveEncounter(text, notes, lair, template, button, scroll, rows) => {
+                let savable = !text.content.is_empty() && rows.iter().any(|row| row.included);
                 let encounter_name = text.text_input("Encounter Name", Message::EncounterName)
                     .style(style)
                     .tap_if(savable, |text| text.on_submit(Message::SaveEncounter));
+                let notes = notes.text_input("Notes: terrain, tactics, treasure...", Message::EncounterNotes)
+                    .style(style)
+                    .size(16);
                 let submit = Button::new(button, Text::new("Submit").size(16))
                     .style(style)
                     .tap_if(savable, |btn| btn.on_press(Message::SaveEncounter));
-                Row::new()
-                    .align_items(Align::Center)
-                    .push(encounter_name)
-                    .push_space(8)
-                    .push(submit)
-                    .into()
-            }
-            SaveMode::DeleteEncounter(name, text, button) => {
-                let matches = text.content == *name;
-                let encounter_name = text.text_input("Delete", Message::EncounterName)
-                    .style(style)
-                    .tap_if(matches, |txt| txt.on_submit(Message::DeleteEncounter(name.clone())));
-                let submit = Button::new(
-                    button,
-                    Text::new(format!("Type '{name}' to confirm")).size(16),
-                ).style(style)
-                    .tap_if(matches, |btn| btn.on_press(Message::DeleteEncounter(name.clone())));
-                Row::new()
+                Column::new()
                     .align_items(Align::Center)
-                    .push(encounter_name)
-                    .push_space(8)
-                    .push(submit)
+                    .push(Checkbox::new(*template, "Template (roll HP and initiative fresh each load)", Message::EncounterTemplate)
+                        .style(style)
+                        .size(16)
+                        .text_size(16))
+                    .push_space(7)
+                    .push(lair.text_input("Lair actions, read out on initiative 20", Message::EncounterLair)
+                        .style(style)
+                        .size(16))
+                    .push_space(7)
+                    .push(SaveRow::view(encounter_name, Some(notes), submit, scroll, rows, style))
                     .into()
             }
-            SaveMode::LoadEncounter(name, submit, scroll, enemies) => {
+            SaveMode::DeleteEncounter(confirm)
+            | SaveMode::DeleteParty(confirm) => confirm.view(style),
+            SaveMode::LoadEncounter(name, submit, scroll, enemies, suffix_duplicates, roll20, adjust, notes) => {
                 let submit = Button::new(
                     submit,
                     Text::new("Confirm"),
                 ).style(style)
                     .on_press(Message::LoadEncounter(name.clone()));
+                let roll20 = Button::new(
+                    roll20,
+                    Text::new("Copy for Roll20").size(14),
+                ).style(style)
+                    .on_press(Message::CopyEncounterRoll20);
+                let suffix_duplicates = Checkbox::new(
+                    *suffix_duplicates,
+                    "Suffix duplicate names",
+                    Message::SuffixDuplicates,
+                ).style(style)
+                    .size(16)
+                    .text_size(16);
 
-                let [names, hps, las, inits] = enemies.into_iter()
-                    .enumerate()
-                    .fold(["Name (Hidden)", "HP (Hidden)", "Leg. Acts. (Hidden)", "Initiative (Hidden)"].map(|title| vec![Element::from(Text::new(title))]),
-                          |[mut names, mut hps, mut las, mut inits], (idx, Enemy { name, hp, legendary_actions, initiative })| {
-                              fn view<T: Display>(Hidden(t, hidden): &Hidden<T>, idx: usize, part: HideablePart, style: Style) -> Element<'static, Message> {
-                                  let hide = checkbox(*hidden, move |hidden| Message::EncounterHide(idx, hidden, part))
-                                      .style(style)
-                                      .size(16);
-                                  let row = Row::new()
-                                      .push(Text::new(format!("{t} (")).size(16))
-                                      .push(hide)
-                                      .push(Text::new(')').size(16));
-                                  row.into()
-                              }
-
-                              names.push(view(&name, idx, HideablePart::Name, style));
-                              // let name = Text::new(format!("{name} ({})", if *hidden { '✔' } else { '❌' })).size(16);
-                              // names.push(name.into());
-
-                              hps.push(view(&hp, idx, HideablePart::Hp, style));
-                              // let hp = Text::new(hp.to_string()).size(16);
-                              // hps.push(hp.into());
-
-                              if let Some(la) = legendary_actions {
-                                  las.push(view(&la, idx, HideablePart::LegActs, style));
-                                  // let la = Text::new(roman::to(*la as _).unwrap()).size(16);
-                                  // las.push(la.into());
-                              }
-
-                              inits.push(view(&initiative, idx, HideablePart::Initiative, style));
-                              // let init = Text::new(initiative.to_string()).size(16);
-                              // inits.push(init.into());
-
-                              [names, hps, las, inits]
-                          });
+                let has_legendary_actions = enemies.iter().any(|enemy| enemy.legendary_actions.is_some());
+                let [names, hps, las, inits] = Self::preview_columns(enemies, adjust, legendary_display, style);
                 let table = Scrollable::new(scroll)
                     .push(Row::new()
                         .push(Column::with_children(names).spacing(5))
                         .push_space(Length::Fill)
                         .push(Column::with_children(hps).spacing(5))
-                        .tap_if(las.len() > 1, |row| row
+                        .tap_if(has_legendary_actions, |row| row
                             .push_space(Length::Fill)
                             .push(Column::with_children(las).spacing(5)))
                         .push_space(Length::Fill)
@@ -224,65 +843,173 @@ impl SaveMode {
 
                 Column::new()
                     .align_items(Align::Center)
-                    .push(submit)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(submit)
+                        .push_space(10)
+                        .push(roll20))
+                    .tap_if(adjust.template, |col| col
+                        .push_space(7)
+                        .push(Text::new("A template, so HP and initiative were rolled fresh").size(14)))
+                    .tap_if(!notes.is_empty(), |col| col
+                        .push_space(7)
+                        .push(Text::new(notes.as_str()).size(18)))
+                    .tap_if(!load_preview.is_empty(), |col| load_preview.iter()
+                        .fold(col.push_space(7), |col, line| col.push(Text::new(line).size(14))))
+                    .push_space(7)
+                    .push(suffix_duplicates)
+                    .push_space(7)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(Text::new("HP ×").size(16))
+                        .push_space(4)
+                        .push(adjust.hp.text_input("1", Message::EncounterHpScale)
+                            .style(style)
+                            .size(16)
+                            .width(Length::Units(40)))
+                        .push_space(12)
+                        .push(Text::new("Extra copies").size(16))
+                        .push_space(4)
+                        .push(adjust.copies.text_input("0", Message::EncounterCopies)
+                            .style(style)
+                            .size(16)
+                            .width(Length::Units(40))))
+                    .push_space(7)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(Checkbox::new(reroll, "Reroll HP", Message::RerollHp)
+                            .style(style)
+                            .size(16)
+                            .text_size(16))
+                        .push_space(8)
+                        .push(Button::new(&mut adjust.reroll_button, Text::new("Reroll all").size(14))
+                            .style(style)
+                            .tap_if(reroll, |btn| btn.on_press(Message::RerollAllHp))))
+                    .push_space(7)
+                    .push(Checkbox::new(adjust.by_hand, "Enter initiatives rolled at the table next", Message::InitiativesByHand)
+                        .style(style)
+                        .size(16)
+                        .text_size(16))
                     .push_space(7)
                     .push(table)
                     .into()
             }
-            SaveMode::SaveParty(text, button) => {
-                let savable = !text.content.is_empty();
+            SaveMode::EnterInitiatives(button, scroll, rows) => {
+                let submit = Button::new(button, Text::new("Submit Initiatives"))
+                    .style(style)
+                    .on_press(Message::SubmitInitiatives);
+                let (names, inits) = rows.iter_mut()
+                    .enumerate()
+                    .fold(
+                        (Column::new().align_items(Align::Start).spacing(5), Column::new().align_items(Align::End).spacing(5)),
+                        |(names, inits), (i, (_, name, saved, text))| {
+                            let text = text.text_input(&saved.to_string(), move |str| Message::ManualInitiative(i, str))
+                                .style(style)
+                                .width(Length::Units(70))
+                                .on_submit(Message::SubmitInitiatives);
+                            (names.push(Text::new(name.as_str())), inits.push(text))
+                        },
+                    );
+                Column::new()
+                    .align_items(Align::Center)
+                    .push(submit)
+                    .push_space(7)
+                    .push(Text::new("Blank ones keep the initiative they were saved with").size(14))
+                    .push_space(10)
+                    .push(Scrollable::new(scroll)
+                        .push(Row::new().push(names).push_space(12).push(inits)))
+                    .into()
+            }
+            SaveMode::SaveParty(text, button, pcs_button, scroll, rows) => {
+                let any_included = rows.iter().any(|row| row.included);
+                let savable = !text.content.is_empty() && any_included;
                 let party_name = text.text_input("Party Name", Message::PartyName)
                     .style(style)
                     .tap_if(savable, |txt| txt.on_submit(Message::SaveParty));
                 let submit = Button::new(button, Text::new("Submit"))
                     .style(style)
                     .tap_if(savable, |btn| btn.on_press(Message::SaveParty));
-                Row::new()
+                Column::new()
                     .align_items(Align::Center)
-                    .push(party_name)
-                    .push_space(8)
-                    .push(submit)
+                    .push(Text::new("Anything a player owns is saved as one of their companions").size(14))
+                    .push_space(7)
+                    .push(Button::new(pcs_button, Text::new("Save each as a player").size(14))
+                        .style(style)
+                        .tap_if(any_included, |btn| btn.on_press(Message::SavePcs)))
+                    .push_space(7)
+                    .push(SaveRow::view(party_name, None, submit, scroll, rows, style))
                     .into()
             }
-            SaveMode::DeleteParty(name, text, button) => {
-                let matches = text.content == *name;
-                let party_name = text.text_input("Delete", Message::PartyName)
+            SaveMode::AssembleParty(name, button, scroll, rows) => {
+                let any_picked = rows.iter().any(|(.., picked)| *picked);
+                let name = name.text_input("Save as a party (optional)", Message::AssembledName)
                     .style(style)
-                    .tap_if(matches, |txt| txt.on_submit(Message::DeleteParty(name.clone())));
-                let submit = Button::new(
-                    button,
-                    Text::new(format!("Type '{name}' to confirm"))
-                        .size(16),
-                ).style(style)
-                    .tap_if(matches, |btn| btn.on_press(Message::DeleteParty(name.clone())));
-                Row::new()
+                    .tap_if(any_picked, |txt| txt.on_submit(Message::AssembleParty));
+                let next = Button::new(button, Text::new("Enter Initiatives"))
+                    .style(style)
+                    .tap_if(any_picked, |btn| btn.on_press(Message::AssembleParty));
+                let scrollable = rows.iter()
+                    .enumerate()
+                    .fold(Scrollable::new(scroll).spacing(5), |col, (i, (from, pc, picked))| col
+                        .push(Checkbox::new(*picked, format!("{} ({from})", pc.name), move |picked| Message::AssembleSelect(i, picked))
+                            .style(style)));
+                Column::new()
                     .align_items(Align::Center)
-                    .push(party_name)
-                    .push_space(8)
-                    .push(submit)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(name)
+                        .push_space(10)
+                        .push(next))
+                    .push_space(10)
+                    .push(scrollable)
                     .into()
             }
             SaveMode::LoadParty(party_name, button, scroll, rows) => {
-                let all_entered = rows.iter().all(|(_, txt)| !txt.content.is_empty());
+                const SUBMITTED_COLOR: Color = Color::from_rgb(0.3, 0.6, 0.9);
+
+                let all_entered = rows.iter().all(|(pc, txt, _, _, hp)| !txt.content.is_empty() && !pc.hp_invalid(&hp.content));
                 let button = Button::new(button, Text::new("Submit Initiatives"))
                     .style(style)
                     .tap_if(all_entered, |b| b.on_press(Message::LoadParty(party_name.clone())));
 
-                let (names, inits) = rows.iter_mut()
+                let (names, inits, hps) = rows.iter_mut()
                     .enumerate()
                     .fold(
-                        (Column::new().align_items(Align::Start).spacing(5), Column::new().align_items(Align::End).spacing(5)),
-                        |(names, inits), (i, (pc, text))| {
-                            let names = names.push(Text::new(&pc.name));
+                        (Column::new().align_items(Align::Start).spacing(5), Column::new().align_items(Align::End).spacing(5), Column::new().align_items(Align::End).spacing(5)),
+                        |(names, inits, hps), (i, (pc, text, init_slider, submissions, hp))| {
+                            let name = match submissions {
+                                0 => Text::new(&pc.name),
+                                1 => Text::new(format!("{} (sent)", pc.name)).color(SUBMITTED_COLOR),
+                                _ => Text::new(format!("{} (changed)", pc.name)).color(SUBMITTED_COLOR),
+                            };
+                            let names = names.push(name);
+                            let initiative = text.content.parse().unwrap_or(0).min(MAX_SLIDER_INITIATIVE);
                             let text = text.text_input("Initiative", move |str| Message::PcInitiative(i, str))
                                 .style(style)
                                 .tap_if(all_entered, |txt| txt.on_submit(Message::LoadParty(party_name.clone())));
-                            let inits = inits.push(text);
-                            (names, inits)
+                            // for touchscreens, typing is still the main way
+                            let init_slider = Slider::new(
+                                init_slider,
+                                0..=MAX_SLIDER_INITIATIVE,
+                                initiative,
+                                move |init: u32| Message::PcInitiative(i, init.to_string()),
+                            ).style(style)
+                                .width(Length::Units(90));
+                            let inits = inits.push(Row::new()
+                                .align_items(Align::Center)
+                                .push(text.width(Length::Units(70)))
+                                .push_space(6)
+                                .push(init_slider));
+                            let invalid = pc.hp_invalid(&hp.content);
+                            let hp = hp.text_input(&format!("HP ({})", pc.hp), move |str| Message::PcHp(i, str))
+                                .style(Invalid::new(style, invalid))
+                                .tap_if(all_entered, |txt| txt.on_submit(Message::LoadParty(party_name.clone())));
+                            let hps = hps.push(hp);
+                            (names, inits, hps)
                         },
                     );
                 let scrollable = Scrollable::new(scroll)
-                    .push(Row::new().push(names).push_space(12).push(inits));
+                    .push(Row::new().push(names).push_space(12).push(inits).push_space(8).push(hps));
 
                 Column::new()
                     .align_items(Align::Center)
@@ -303,43 +1030,217 @@ impl Default for SaveMode {
 
 pub struct InitiativeManager {
     update_state: UpdateState,
+    cancel_update: button::State,
+    /// the window was closed while an update was downloading, so exit once it's done
+    close_requested: bool,
+    exit: bool,
     update_url: String,
+    /// shown in the bottom bar, ex. where something was exported to
+    status: Option<String>,
+    export_button: button::State,
+    roll20_button: button::State,
+    /// leave hidden entities out of Roll20 exports instead of using their censored names
+    roll20_skip_hidden: bool,
     dm_view: ToggleButtonState,
+    /// shows the secret stats until then without switching to the DM view, see `PEEK`
+    peek_until: Option<Instant>,
+    peek_button: button::State,
+    /// the button or pick list Tab is on, if it's not on a text input
+    keyboard_focus: Option<Control>,
     style: Style,
     width: u32,
     height: u32,
     style_button: button::State,
+    settings: Settings,
+    settings_button: button::State,
+    settings_panel: SettingsPanel,
+    scratchpad: scratchpad::Scratchpad,
+    scratchpad_button: button::State,
+    table_ratio_slider: slider::State,
+    /// what the players' page served by `server::Server` shows
+    player_view: watch::Sender<server::PlayerView>,
+    player_view_rx: watch::Receiver<server::PlayerView>,
+    server_button: button::State,
+    server_port: TextInputState,
+    host_button: button::State,
+    webhook_url: TextInputState,
+    /// bumped each turn change, only the latest one gets announced
+    announce_generation: u64,
+    webhook_backoff: webhook::Backoff,
+    hook_command: TextInputState,
+    summary_url: TextInputState,
+    /// only the first failure in a row is shown, until it works again or the command changes
+    hook_failed: bool,
+    overlay_path: TextInputState,
+    overlay_template: TextInputState,
+    overlay_next_path: TextInputState,
+    key_color: TextInputState,
+    /// like `announce_generation`, for debouncing overlay writes
+    overlay_generation: u64,
+    /// damage, healing, etc. for the session report, cleared with the encounter
+    combat_log: Vec<report::LogEntry>,
+    /// removed at 0 HP, so they still make it into the recap
+    fallen: Vec<(EntityId, recap::Combatant)>,
+    /// offered after the board is cleared, until it's saved or dismissed
+    recap: Option<recap::Recap>,
+    save_recap: button::State,
+    dismiss_recap: button::State,
+    report_dir: TextInputState,
+    report_file_name: TextInputState,
+    report_button: button::State,
+    /// a report that already exists, which the report button will now replace
+    report_overwrite: Option<PathBuf>,
+    /// set from the command line, ignores local input and just shows what the host sends
+    mirror: Option<sync::Mirror>,
     entities: Vec<Entity>,
-    highlight_state: Option<(usize, container::Style)>,
+    /// one for each `NextTurn`, popped by `PrevTurn`
+    turn_history: Vec<TurnUndo>,
+    /// whose turns were taken each round, in the order they were taken, to settle "didn't they
+    /// already go?". Capped at `ROUND_HISTORY_LEN` rounds.
+    round_history: VecDeque<(u32, Vec<EntityId>)>,
+    /// for rewinding further back than undo goes (say a fireball hit the wrong side three turns ago),
+    /// taken at the start of each round or, with `Settings::snapshot_turns`, each turn. Oldest first,
+    /// capped at `SNAPSHOT_LEN`, and only kept for the session.
+    snapshots: VecDeque<Snapshot>,
+    rewind_list: pick_list::State<String>,
+    /// which of `snapshots` is waiting to be confirmed
+    rewinding: Option<usize>,
+    rewind_confirm: button::State,
+    rewind_cancel: button::State,
+    round_history_open: bool,
+    round_history_toggle: button::State,
+    /// whether each entity can be moved [up, down], see `refresh_adjacency`
+    up_down: Vec<[bool; 2]>,
+    highlight_state: Option<(EntityId, container::Style)>,
     scroll: scrollable::State,
+    /// the controls or settings next to the table, which can be taller than the window
+    side_scroll: scrollable::State,
     new_entity_submit: button::State,
     new_entity: NewEntity,
+    preset_search: TextInputState,
+    preset_list: pick_list::State<String>,
+    /// only changes what the table shows, turns still go through everyone
+    disposition_filter: disposition::Filter,
+    filter_list: pick_list::State<disposition::Filter>,
+    /// the same filter as `filter_list`, just the two most used, above the table
+    filter_chips: [button::State; 2],
+    bulk: bulk::Bulk,
+    /// cleaned up at the end of every `update`, see `reminder::Reminder::owner`
+    reminders: reminder::Reminders,
+    /// the front one is asked about first
+    ties: VecDeque<tie::TiePrompt>,
+    /// picked to swap initiative with whoever's picked next, see `Message::SwapInitiative`
+    swapping: Option<EntityId>,
+    /// the padlock next to the Initiative header, hides everything that reorders the table so it
+    /// can't be bumped by accident. Unlocked again by `ClearEncounter`.
+    order_locked: ToggleButtonState,
+    /// out of the order (banished, swallowed, fled...) but not gone, with their "return" buttons
+    sidelined: Vec<(Entity, button::State)>,
+    sidelined_open: bool,
+    sidelined_toggle: button::State,
+    /// which tag's meaning is being edited
+    tag_legend_color: TagColor,
+    tag_legend_list: pick_list::State<TagColor>,
+    tag_legend: TextInputState,
     turn: usize,
+    round: u32,
     next_turn: button::State,
     prev_turn: button::State,
     save_encounter: button::State,
     delete_encounter: pick_list::State<String>,
     load_encounter: pick_list::State<String>,
+    /// whether each file in `ENCOUNTER_DIR` parses, by name, checked again whenever it's modified
+    encounter_checks: HashMap<String, (Option<SystemTime>, bool)>,
     save_party: button::State,
     delete_party: pick_list::State<String>,
     load_party: pick_list::State<String>,
+    /// the last encounter/party loaded, forgotten once none of their entities are left
+    loaded_encounter: Option<String>,
+    /// from the loaded encounter's file, shown in a panel under the table
+    encounter_notes: String,
+    /// the same, read out on initiative 20 (or at the top of the round without the initiative
+    /// caller), in a banner for the DM while `lair_banner` is up
+    lair_actions: String,
+    lair_banner: bool,
+    lair_dismiss: button::State,
+    notes_open: bool,
+    notes_toggle: button::State,
+    loaded_party: Option<String>,
+    clear_encounter: button::State,
+    /// the last removal, until its undo window is up
+    removed: Option<Removal>,
+    undo_remove: button::State,
     save_mode: SaveMode,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Update(update::Message),
+    /// the window's close button, see `Application::should_exit`
+    Exit,
     ToggleVisibility,
-    ToggleStyle,
+    /// secret stats for `PEEK`, or `PEEK` longer if already peeking
+    Peek,
+    OpenSettings(bool),
+    SetSetting(Setting),
+    TableRatio(f32),
+    /// only saved once it's let go, not for every step of the drag
+    TableRatioReleased,
+    ToggleServer,
+    ServerPort(String),
+    Server(server::Event),
+    ToggleHost,
+    Sync(sync::Event),
+    WebhookUrl(String),
+    HookCommand(String),
+    ToggleHook(bool),
+    HookRan(Result<(), String>),
+    SummaryUrl(String),
+    ToggleHistory(bool),
+    SummaryDelivered(Result<(), String>),
+    ToggleAnnounce(bool),
+    Announce(u64),
+    Announced(Result<(), String>),
+    ToggleOverlay(bool),
+    OverlayPath(String),
+    OverlayTemplate(String),
+    OverlayNextPath(String),
+    ToggleStreaming(bool),
+    KeyColor(String),
+    WriteOverlay(u64),
+    OverlayWritten(Result<(), String>),
+    ReportDir(String),
+    ReportFileName(String),
+    ExportReport,
+    ReportExported(Result<PathBuf, report::Error>),
+    SettingsPath(String),
+    IncludeSecrets(bool),
+    SuffixColors(String),
+    QuickConditions(String),
+    ExportSettings,
+    SettingsExported(Result<PathBuf, String>),
+    ImportSettings,
+    SettingsImported(Result<settings::Import, String>),
+    ExportOrder,
+    CopyRoll20,
+    CopyEncounterRoll20,
+    Roll20SkipHidden(bool),
+    Exported(Result<PathBuf, String>),
     Resize(u32, u32),
     ToggleHidden(usize, HideablePart),
     DeleteEntity(usize),
     EditDamage(usize, String),
     Damage(usize),
-    HighlightConcentration(usize, Instant),
+    HighlightConcentration(EntityId, Instant),
     EditHealing(usize, String),
+    EditAttack(usize, String),
+    ResolveAttack(usize, i32),
+    /// a natural 1 or 20, which ignores AC
+    NaturalAttack(usize, u32),
     Heal(usize),
     Reaction(usize),
+    ReactionPlus(usize),
+    TogglePin(usize),
     Concentrate(usize),
     LegActionMinus(usize),
     LegActionPlus(usize),
@@ -349,21 +1250,102 @@ pub enum Message {
     NewInit(String),
     NewHp(String),
     NewLas(String),
+    NewReactions(String),
+    NewCount(String),
+    NewSingleRoll(bool),
     NewHidden(bool, HideablePart),
+    NewStaged(bool),
+    NewSurprised(bool),
+    Unsurprise(usize),
+    NewIcon(Glyph),
+    NewDisposition(Disposition),
+    CycleDisposition(usize),
+    CycleTag(usize),
+    TagLegendColor(TagColor),
+    TagLegendMeaning(String),
+    FilterDisposition(disposition::Filter),
+    NewIconPath(String),
+    NewOwner(String),
+    /// hold with the entity before them in the order
+    JoinGroup(usize),
+    /// d20s to settle a tie for `usize`'s initiative
+    RollOff(usize),
+    Sideline(usize),
+    ReturnSidelined(usize),
+    ToggleSidelined,
+    ToggleRoundHistory,
+    LeaveGroup(usize),
+    Activate(usize),
     NewEntitySubmit,
+    PresetSearch(String),
+    AddPreset(String),
     HotKey(hotkey::Message),
     NextTurn,
     PrevTurn,
     SaveEncounter,
     EncounterName(String),
+    EncounterNotes(String),
+    ToggleNotes,
     DeleteEncounter(String),
     LoadEncounter(String),
     EncounterHide(usize, bool, HideablePart),
+    SuffixDuplicates(bool),
+    EncounterHpScale(String),
+    EncounterCopies(String),
+    RerollHp(bool),
+    RerollAllHp,
+    ClearEncounter,
+    SaveRecap,
+    DismissRecap,
+    RecapSaved(Result<PathBuf, String>),
+    UndoDelete,
+    /// the undo window is up for the removal starting with this entity
+    ForgetRemoved(EntityId),
     SaveParty,
     PartyName(String),
+    SavePcs,
+    /// opens `SaveMode::AssembleParty`, then moves on from it
+    AssembleParty,
+    AssembleSelect(usize, bool),
+    AssembledName(String),
+    /// include or leave out the `i`th entity from the encounter or party being saved
+    SaveSelect(usize, bool),
     DeleteParty(String),
     LoadParty(String),
     PcInitiative(usize, String),
+    PcHp(usize, String),
+    ToggleActed(usize),
+    EditCondition(usize, String),
+    AddCondition(usize),
+    /// the entity, and which of `Settings::quick_conditions`
+    QuickCondition(usize, usize),
+    RemoveCondition(usize, usize),
+    UseRecharge(usize, usize),
+    Confirm(confirm::Message),
+    Bulk(bulk::Message),
+    Reminder(reminder::Message),
+    /// a `Snapshot` picked by its label, asked about before anything changes
+    Rewind(String),
+    ConfirmRewind,
+    CancelRewind,
+    /// true -> before whoever they tied with, see `tie::TiePrompt`
+    PlaceTie(bool),
+    ResetAllReactions,
+    /// adds or dismisses one of the entity's `companions`, the `String` is from `companion::choices`
+    ToggleCompanion(usize, String),
+    /// the first picks who to swap with, the second does the swap (or cancels, if it's the same one)
+    SwapInitiative(usize),
+    /// redraws the "since last turn" times
+    Tick,
+    ToggleOrderLock,
+    Scratchpad(scratchpad::Message),
+    AtZero(usize, ZeroChoice),
+    InitiativesByHand(bool),
+    EncounterTemplate(bool),
+    EncounterLair(String),
+    DismissLair,
+    ManualInitiative(usize, String),
+    SubmitInitiatives,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -374,36 +1356,51 @@ pub enum HideablePart {
     Initiative,
 }
 
+/// How big everything in the table that gets clicked or typed in is, see `Settings::large_controls`
+#[derive(Debug, Copy, Clone)]
+struct Controls {
+    spacing: u16,
+    /// the damage/heal/attack boxes
+    mod_width: u16,
+    mod_text: u16,
+    mod_padding: u16,
+    button_padding: u16,
+    /// the small buttons under names, ex. conditions
+    small_text: u16,
+    arrow_size: u16,
+}
+
+impl Controls {
+    const NORMAL: Self = Self {
+        spacing: 5,
+        mod_width: 26,
+        mod_text: 9,
+        mod_padding: 0,
+        button_padding: 0,
+        small_text: 10,
+        arrow_size: 8,
+    };
+
+    /// about 32px to hit with a finger
+    const LARGE: Self = Self {
+        spacing: 10,
+        mod_width: 60,
+        mod_text: 18,
+        mod_padding: 7,
+        button_padding: 8,
+        small_text: 16,
+        arrow_size: 16,
+    };
+}
+
 impl Application for InitiativeManager {
     type Executor = iced_futures::executor::Tokio;
     type Message = Message;
-    type Flags = (u32, u32);
+    /// window size, and the host to mirror if any
+    type Flags = (u32, u32, Option<String>);
 
-    fn new((width, height): Self::Flags) -> (Self, Command<Message>) {
-        let window = Self {
-            update_state: UpdateState::Checking,
-            update_url: "".to_string(),
-            dm_view: ToggleButtonState::new_with(true, [Icon::EyeSlashFill, Icon::EyeFill]),
-            style: Default::default(),
-            width,
-            height,
-            style_button: Default::default(),
-            entities: vec![],
-            highlight_state: None,
-            scroll: Default::default(),
-            new_entity_submit: Default::default(),
-            new_entity: Default::default(),
-            turn: 0,
-            next_turn: Default::default(),
-            prev_turn: Default::default(),
-            save_encounter: Default::default(),
-            delete_encounter: Default::default(),
-            load_encounter: Default::default(),
-            save_party: Default::default(),
-            delete_party: Default::default(),
-            load_party: Default::default(),
-            save_mode: Default::default(),
-        };
+    fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+        let window = Self::with_settings(Settings::load(), scratchpad::Scratchpad::load(), flags);
         let command = async {
             // wait briefly to so that loading doesn't take so long
             tokio::time::sleep(Duration::from_millis(500)).await;
@@ -416,86 +1413,452 @@ impl Application for InitiativeManager {
         "Initiatives".into()
     }
 
-    fn update(&mut self, message: Self::Message, _: &mut iced::Clipboard) -> Command<Message> {
+    fn background_color(&self) -> Color {
+        self.key_color().unwrap_or(Color::WHITE)
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exit
+    }
+
+    fn update(&mut self, message: Self::Message, clipboard: &mut iced::Clipboard) -> Command<Message> {
+        if let Some(mirror) = &mut self.mirror {
+            match message {
+                Message::Sync(sync::Event::Connected) => mirror.disconnected = None,
+                Message::Sync(sync::Event::Disconnected(reason)) => mirror.disconnected = Some(reason),
+                Message::Sync(sync::Event::View(view)) => mirror.view = view,
+                Message::Resize(width, height) => {
+                    self.width = width;
+                    self.height = height;
+                }
+                // no settings or download of its own to finish first
+                Message::Exit => self.exit = true,
+                // the host is in charge
+                _ => {}
+            }
+            return Command::none();
+        }
+
         let mut commands = Vec::new();
+        let turn_changed = matches!(message, Message::NextTurn | Message::PrevTurn | Message::ClearEncounter);
+        let combat_ended = matches!(message, Message::ClearEncounter);
+        let round = self.round;
         match message {
-            Message::Update(msg) => if let Err(e) = update::handle(self, msg) {
-                self.update_state = UpdateState::Errored(e.to_string());
-            },
-            Message::ToggleVisibility => self.dm_view.invert(),
-            Message::ToggleStyle => self.style = !self.style,
-            Message::Resize(width, height) => {
-                self.width = width;
-                self.height = height;
-            }
-            Message::ToggleHidden(i, part) => {
-                let entity = &mut self.entities[i];
-                match part {
-                    HideablePart::Name => entity.name.1 = !entity.name.1,
-                    HideablePart::Hp => entity.hp.1 = !entity.hp.1,
-                    HideablePart::LegActs => { entity.legendary_actions.as_mut().map(|las| las.1 = !las.1); }
-                    HideablePart::Initiative => entity.initiative.1 = !entity.initiative.1,
+            Message::Update(msg) => {
+                if let Err(e) = update::handle(self, msg) {
+                    self.update_state = UpdateState::Errored(e.to_string());
+                }
+                if self.close_requested && !matches!(self.update_state, UpdateState::Downloading(_)) {
+                    self.exit = true;
                 }
             }
-            Message::DeleteEntity(i) => {
-                self.entities.remove(i);
-                if i < self.turn {
-                    self.turn -= 1;
+            Message::Exit => {
+                // the table ratio is only saved when the slider is let go, this catches anything else
+                self.settings.save();
+                // quitting part way through would leave the update half downloaded
+                if matches!(self.update_state, UpdateState::Downloading(_)) {
+                    self.close_requested = true;
+                    self.status = Some(String::from("Closing once the update finishes downloading"));
+                } else {
+                    self.exit = true;
                 }
             }
-            Message::EditDamage(i, damage) => {
-                if damage.parse::<u32>().is_ok() || damage.is_empty() {
-                    self.entities[i].damage.content = damage;
+            Message::ToggleVisibility => self.dm_view.invert(),
+            Message::Peek => self.peek(),
+            Message::OpenSettings(open) => self.settings_panel.open = open,
+            Message::SetSetting(setting) => {
+                if let Setting::Style(style) = setting {
+                    self.style = style;
                 }
+                self.settings.set(setting);
             }
-            Message::Damage(i) => {
-                let entity = &mut self.entities[i];
-                let damage = &mut entity.damage.content;
-                if !damage.is_empty() {
-                    entity.hp.0 = entity.hp.0.saturating_sub(damage.parse().unwrap());
-                    damage.clear();
-                    if entity.concentrating.value {
-                        commands.push(async move {
-                            Message::HighlightConcentration(i, Instant::now() + Duration::from_millis(1400))
-                        }.into());
+            Message::TableRatio(ratio) => self.settings.table_ratio = ratio,
+            Message::TableRatioReleased => self.settings.save(),
+            Message::ToggleServer => {
+                self.settings.server = !self.settings.server;
+                self.settings.save();
+            }
+            Message::ServerPort(port) => {
+                if port.is_empty() || port.parse::<u16>().is_ok() {
+                    if let Ok(port) = port.parse() {
+                        self.settings.server_port = port;
+                        self.settings.save();
                     }
+                    self.server_port.content = port;
                 }
             }
-            Message::HighlightConcentration(i, highlight_done) => {
-                let now = Instant::now();
-                if highlight_done > now {
-                    self.highlight_state = Some((i, container::Style {
-                        text_color: {
-                            let millis = highlight_done.duration_since(now).as_millis();
-                            let r = 1.0 - (millis % 700) as f32 / 1400.0;
-                            Some(Color::new(r, 0.0, 0.0, 1.0))
-                        },
-                        background: Color::TRANSPARENT.into(),
-                        ..Box::<dyn container::StyleSheet>::from(self.style).style()
-                    }));
-                    commands.push(async move {
-                        tokio::time::sleep(Duration::from_millis(15)).await;
-                        Message::HighlightConcentration(i, highlight_done)
-                    }.into())
-                } else {
-                    self.highlight_state = None;
+            Message::Server(event) => match event {
+                server::Event::Started(port) => self.status = Some(format!("Sharing the player view on port {port}")),
+                server::Event::Failed(e) => {
+                    self.status = Some(e);
+                    // not saved, so it'll try again next time
+                    self.settings.server = false;
                 }
+                // last one wins, the row says it was changed
+                server::Event::Initiative(server::Submission { name, roll }) => if let SaveMode::LoadParty(_, _, _, rows) = &mut self.save_mode {
+                    if let Some((_, txt, _, submissions, _)) = rows.iter_mut().find(|(pc, ..)| pc.name == name) {
+                        txt.content = roll.to_string();
+                        *submissions += 1;
+                    }
+                },
+            },
+            Message::ToggleHost => {
+                self.settings.host = !self.settings.host;
+                self.settings.save();
             }
-            Message::EditHealing(i, healing) => {
-                if healing.parse::<u32>().is_ok() || healing.is_empty() {
-                    self.entities[i].heal.content = healing;
+            Message::Sync(event) => match event {
+                sync::Event::Hosting(port) => self.status = Some(format!("Hosting mirrors on port {port}")),
+                sync::Event::HostFailed(e) => {
+                    self.status = Some(e);
+                    self.settings.host = false;
+                }
+                // only mirrors connect
+                sync::Event::Connected | sync::Event::Disconnected(_) | sync::Event::View(_) => {}
+            },
+            Message::WebhookUrl(url) => {
+                self.settings.webhook_url = url.clone();
+                self.settings.save();
+                self.webhook_url.content = url;
+            }
+            Message::HookCommand(command) => {
+                self.settings.hook_command = command.clone();
+                self.settings.save();
+                self.hook_command.content = command;
+                self.hook_failed = false;
+            }
+            Message::ToggleHook(hook) => {
+                self.settings.hook = hook;
+                self.settings.save();
+                self.hook_failed = false;
+            }
+            Message::HookRan(result) => match result {
+                Ok(()) => self.hook_failed = false,
+                Err(e) => if !self.hook_failed {
+                    self.hook_failed = true;
+                    self.status = Some(format!("Turn command failed: {e}"));
+                },
+            },
+            Message::SummaryUrl(url) => {
+                self.settings.summary_url = url.clone();
+                self.settings.save();
+                self.summary_url.content = url;
+            }
+            Message::ToggleHistory(history) => {
+                self.settings.history = history;
+                self.settings.save();
+            }
+            Message::SummaryDelivered(result) => if let Err(e) = result {
+                self.status = Some(format!("Couldn't save the combat summary: {e}"));
+            },
+            Message::ToggleAnnounce(announce) => {
+                self.settings.announce = announce;
+                self.settings.save();
+            }
+            Message::Announce(generation) => {
+                let enabled = self.settings.announce && !self.settings.webhook_url.is_empty();
+                if enabled && generation == self.announce_generation && self.webhook_backoff.ready() {
+                    let turn = self.player_rows().into_iter().find(|row| row.is_turn);
+                    if let Some(turn) = turn {
+                        let content = format!("Round {} — it's **{}**'s turn", self.round, turn.name);
+                        commands.push(Command::perform(
+                            webhook::post(self.settings.webhook_url.clone(), content),
+                            Message::Announced,
+                        ));
+                    }
+                }
+            }
+            Message::Announced(result) => match result {
+                Ok(()) => self.webhook_backoff.succeeded(),
+                Err(e) => if self.webhook_backoff.failed() {
+                    self.status = Some(format!("Couldn't announce the turn: {e}"));
+                },
+            },
+            Message::ToggleOverlay(overlay) => {
+                self.settings.overlay = overlay;
+                self.settings.save();
+                if overlay {
+                    commands.push(self.schedule_overlay());
+                }
+            }
+            Message::OverlayPath(path) => {
+                self.settings.overlay_path = path.clone();
+                self.settings.save();
+                self.overlay_path.content = path;
+            }
+            Message::OverlayTemplate(template) => {
+                self.settings.overlay_template = template.clone();
+                self.settings.save();
+                self.overlay_template.content = template;
+            }
+            Message::ToggleStreaming(streaming) => {
+                self.settings.streaming = streaming;
+                self.settings.save();
+            }
+            Message::KeyColor(color) => {
+                self.settings.key_color = color.clone();
+                self.settings.save();
+                self.key_color.content = color;
+            }
+            Message::OverlayNextPath(path) => {
+                self.settings.overlay_next_path = path.clone();
+                self.settings.save();
+                self.overlay_next_path.content = path;
+            }
+            Message::ReportDir(dir) => {
+                self.settings.report_dir = dir.clone();
+                self.settings.save();
+                self.report_dir.content = dir;
+                self.report_overwrite = None;
+            }
+            Message::ReportFileName(file_name) => {
+                self.settings.report_file_name = file_name.clone();
+                self.settings.save();
+                self.report_file_name.content = file_name;
+                self.report_overwrite = None;
+            }
+            Message::ExportReport => {
+                let report = report::Report {
+                    encounter: self.loaded_encounter.clone().unwrap_or_else(|| String::from("Encounter")),
+                    date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+                    round: self.round,
+                    rows: self.entities.iter()
+                        .map(|entity| report::ReportRow {
+                            name: entity.name.0.clone(),
+                            initiative: entity.initiative.0,
+                            hp: entity.hp.0,
+                            max_hp: entity.max_hp,
+                            ac: entity.ac,
+                        })
+                        .collect(),
+                    log: self.combat_log.clone(),
+                };
+                commands.push(Command::perform(
+                    report::write(
+                        report,
+                        self.settings.report_dir.clone(),
+                        self.settings.report_file_name.clone(),
+                        self.report_overwrite.take(),
+                    ),
+                    Message::ReportExported,
+                ));
+            }
+            Message::ReportExported(result) => self.status = Some(match result {
+                Ok(path) => format!("Wrote the session report to {}", path.display()),
+                Err(report::Error::Exists(path)) => {
+                    let status = format!("{} already exists, export again to replace it", path.display());
+                    self.report_overwrite = Some(path);
+                    status
+                }
+                Err(report::Error::Other(e)) => format!("Couldn't write the session report: {e}"),
+            }),
+            Message::SettingsPath(path) => self.settings_panel.path.content = path,
+            Message::SuffixColors(colors) => {
+                self.settings.suffix_colors = colors.split(',')
+                    .map(str::trim)
+                    .filter(|color| !color.is_empty())
+                    .map(String::from)
+                    .collect();
+                self.settings.save();
+                self.settings_panel.suffix_colors.content = colors;
+            }
+            Message::QuickConditions(conditions) => {
+                self.settings.quick_conditions = conditions.split(',')
+                    .map(str::trim)
+                    .filter(|condition| !condition.is_empty())
+                    .map(String::from)
+                    .collect();
+                self.settings.save();
+                self.settings_panel.quick_conditions.content = conditions;
+            }
+            Message::IncludeSecrets(include) => self.settings_panel.include_secrets = include,
+            Message::ExportSettings => commands.push(Command::perform(
+                settings::export(self.settings.clone(), self.settings_panel.path.content.clone(), self.settings_panel.include_secrets),
+                Message::SettingsExported,
+            )),
+            Message::SettingsExported(result) => self.status = Some(match result {
+                Ok(path) if self.settings_panel.include_secrets => format!("Exported settings to {}, webhook urls included", path.display()),
+                Ok(path) => format!("Exported settings to {}", path.display()),
+                Err(e) => format!("Couldn't export settings: {e}"),
+            }),
+            Message::ImportSettings => commands.push(Command::perform(
+                settings::import(self.settings_panel.path.content.clone(), self.settings.clone()),
+                Message::SettingsImported,
+            )),
+            Message::SettingsImported(result) => match result {
+                Ok(settings::Import { settings, unknown, invalid }) => {
+                    self.apply_settings(settings);
+                    if self.settings.overlay {
+                        commands.push(self.schedule_overlay());
+                    }
+                    let mut status = String::from("Imported settings");
+                    if !unknown.is_empty() {
+                        status.push_str(&format!(", ignored unknown {}", unknown.join(", ")));
+                    }
+                    if !invalid.is_empty() {
+                        status.push_str(&format!(", kept the current {} since the file's didn't make sense", invalid.join(", ")));
+                    }
+                    self.status = Some(status);
+                }
+                Err(e) => self.status = Some(format!("Couldn't import settings: {e}")),
+            },
+            Message::WriteOverlay(generation) => if self.settings.overlay && generation == self.overlay_generation {
+                let names = self.player_rows().into_iter()
+                    .map(|row| row.name)
+                    .collect::<Vec<_>>();
+                let overlay = overlay::Overlay::new(
+                    &self.settings.overlay_path,
+                    &self.settings.overlay_template,
+                    &self.settings.overlay_next_path,
+                    self.round,
+                    &names,
+                );
+                match overlay {
+                    Ok(overlay) => commands.push(Command::perform(overlay::write(overlay), Message::OverlayWritten)),
+                    Err(e) => self.status = Some(format!("Couldn't write the overlay: {e}")),
+                }
+            },
+            Message::OverlayWritten(result) => if let Err(e) = result {
+                self.status = Some(format!("Couldn't write the overlay: {e}"));
+            },
+            Message::ExportOrder => {
+                commands.push(Command::perform(export::write_html(self.player_rows(), self.round), Message::Exported));
+            }
+            Message::CopyRoll20 => {
+                let entries = self.entities.iter()
+                    .filter(|entity| !entity.staged)
+                    .map(|entity| export::Roll20Entry {
+                        name: (!entity.name.1).then(|| entity.name.0.clone()),
+                        censored_name: entity.censored_name.clone(),
+                        initiative: entity.initiative.0,
+                    })
+                    .collect_vec();
+                clipboard.write(export::roll20(&entries, self.roll20_skip_hidden));
+                self.status = Some(String::from("Copied the Roll20 turn order"));
+            }
+            Message::CopyEncounterRoll20 => if let SaveMode::LoadEncounter(_, _, _, enemies, ..) = &self.save_mode {
+                let entries = enemies.iter()
+                    .sorted_by_key(|enemy| (Reverse(enemy.initiative.0), enemy.order))
+                    .map(|enemy| export::Roll20Entry {
+                        name: (!enemy.name.1).then(|| enemy.name.0.clone()),
+                        censored_name: censor_name(&enemy.name.0),
+                        initiative: enemy.initiative.0,
+                    })
+                    .collect_vec();
+                clipboard.write(export::roll20(&entries, self.roll20_skip_hidden));
+                self.status = Some(String::from("Copied the encounter's Roll20 turn order"));
+            },
+            Message::Roll20SkipHidden(skip) => self.roll20_skip_hidden = skip,
+            Message::Exported(result) => self.status = Some(match result {
+                Ok(path) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Error exporting: {}", e),
+            }),
+            Message::Resize(width, height) => {
+                self.width = width;
+                self.height = height;
+            }
+            Message::ToggleHidden(i, part) => {
+                let entity = &mut self.entities[i];
+                match part {
+                    HideablePart::Name => {
+                        entity.name.1 = !entity.name.1;
+                        entity.rescramble();
+                    }
+                    HideablePart::Hp => entity.hp.1 = !entity.hp.1,
+                    HideablePart::LegActs => { entity.legendary_actions.as_mut().map(|las| las.1 = !las.1); }
+                    HideablePart::Initiative => entity.initiative.1 = !entity.initiative.1,
+                }
+            }
+            Message::DeleteEntity(i) => {
+                let entity = self.remove_entity(i);
+                if entity.hp.0 == 0 {
+                    self.fallen.push((entity.id, entity.combatant()));
+                }
+                self.status = Some(format!("Removed {}", entity.name.0));
+                let owner = entity.name.0.clone();
+                let mut entities = vec![(i, entity)];
+                if self.settings.remove_summons {
+                    while let Some(summon) = self.entities.iter().position(|e| e.owner.as_ref() == Some(&owner)) {
+                        entities.push((summon, self.remove_entity(summon)));
+                    }
+                }
+                self.offer_undo(Removal { entities, logged: false }, UNDO_DELETE_WINDOW, &mut commands);
+                self.forget_origins();
+            }
+            Message::EditDamage(i, damage) => {
+                if damage.is_empty() || damage == "-" || damage.parse::<i32>().is_ok() {
+                    self.entities[i].damage.content = damage;
+                }
+            }
+            Message::Damage(i) => {
+                if let Ok(damage) = self.entities[i].damage.content.parse::<i64>() {
+                    self.entities[i].damage.content.clear();
+                    self.damage(i, damage, &mut commands);
+                }
+            }
+            Message::HighlightConcentration(id, highlight_done) => {
+                let now = Instant::now();
+                if highlight_done > now {
+                    self.highlight_state = Some((id, container::Style {
+                        text_color: {
+                            let millis = highlight_done.duration_since(now).as_millis();
+                            let r = 1.0 - (millis % 700) as f32 / 1400.0;
+                            Some(Color::new(r, 0.0, 0.0, 1.0))
+                        },
+                        background: Color::TRANSPARENT.into(),
+                        ..Box::<dyn container::StyleSheet>::from(self.style).style()
+                    }));
+                    commands.push(async move {
+                        tokio::time::sleep(Duration::from_millis(15)).await;
+                        Message::HighlightConcentration(id, highlight_done)
+                    }.into())
+                } else {
+                    self.highlight_state = None;
+                }
+            }
+            Message::EditHealing(i, healing) => {
+                if healing.is_empty() || healing == "-" || healing.parse::<i32>().is_ok() {
+                    self.entities[i].heal.content = healing;
                 }
             }
             Message::Heal(i) => {
+                if let Ok(heal) = self.entities[i].heal.content.parse::<i64>() {
+                    self.entities[i].heal.content.clear();
+                    self.heal(i, heal);
+                }
+            }
+            Message::EditAttack(i, attack) => {
+                if AttackRoll::in_progress(&attack) {
+                    self.entities[i].attack.content = attack;
+                }
+            }
+            Message::ResolveAttack(i, total) => {
                 let entity = &mut self.entities[i];
-                let heal = &mut entity.heal.content;
-                if !heal.is_empty() {
-                    entity.hp.0 += heal.parse::<u32>().unwrap();
-                    heal.clear();
+                entity.attack.content.clear();
+                if let Some(ac) = entity.ac {
+                    let result = if total >= ac as i32 { "hits" } else { "misses" };
+                    self.status = Some(format!("{total} {result} {} (AC {ac})", entity.name.0));
                 }
             }
-            Message::Reaction(i) => self.entities[i].reaction_free.invert(),
+            Message::NaturalAttack(i, roll) => {
+                let entity = &mut self.entities[i];
+                entity.attack.content.clear();
+                self.status = Some(if roll == 20 {
+                    format!("Natural 20, critical hit on {}!", entity.name.0)
+                } else {
+                    format!("Natural 1, automatically misses {}", entity.name.0)
+                });
+            }
+            // with one reaction this is a toggle, with more it uses one until there are none left
+            Message::Reaction(i) => {
+                let (left, max) = &mut self.entities[i].reactions;
+                *left = if *left == 0 { *max } else { *left - 1 };
+            }
+            Message::ReactionPlus(i) => {
+                let (left, max) = &mut self.entities[i].reactions;
+                *left = (*left + 1).min(*max);
+            }
             Message::Concentrate(i) => self.entities[i].concentrating.invert(),
+            Message::TogglePin(i) => self.entities[i].pinned.invert(),
             Message::LegActionMinus(i) => {
                 if let Some(Hidden((_, left), _)) = &mut self.entities[i].legendary_actions {
                     *left -= 1;
@@ -506,8 +1869,67 @@ impl Application for InitiativeManager {
                     *left += 1;
                 }
             }
-            Message::MoveUp(i) => self.entities.swap(i, i - 1),
-            Message::MoveDown(i) => self.entities.swap(i, i + 1),
+            Message::MoveUp(_)
+            | Message::MoveDown(_)
+            | Message::RollOff(_)
+            | Message::JoinGroup(_)
+            | Message::SwapInitiative(_) if self.order_locked.value => {}
+            Message::Scratchpad(msg) => self.scratchpad.update(msg),
+            Message::AtZero(i, choice) => {
+                self.entities[i].zero_prompt = false;
+                match choice {
+                    ZeroChoice::Remove => self.remove_fallen(i, &mut commands),
+                    ZeroChoice::KeepDead => self.entities[i].dead = true,
+                    ZeroChoice::AtOne => self.heal(i, 1),
+                }
+            }
+            Message::ToggleActed(i) => self.entities[i].acted = !self.entities[i].acted,
+            Message::ToggleOrderLock => {
+                self.order_locked.invert();
+                self.swapping = None;
+            }
+            Message::MoveUp(i) => {
+                self.entities.swap(i, i - 1);
+                self.refresh_adjacency();
+            }
+            Message::MoveDown(i) => {
+                self.entities.swap(i, i + 1);
+                self.refresh_adjacency();
+            }
+            Message::RollOff(i) => {
+                let initiative = self.entities[i].initiative.0;
+                let tied = |e: &Entity| e.initiative.0 == initiative;
+                let start = self.entities[..i].iter().rposition(|e| !tied(e)).map_or(0, |j| j + 1);
+                let end = self.entities[i..].iter().position(|e| !tied(e)).map_or(self.entities.len(), |j| i + j);
+                let current = self.turn.checked_rem(self.entities.len()).map(|turn| self.entities[turn].id);
+                let mut rng = rand::thread_rng();
+                let mut rolled = self.entities.drain(start..end)
+                    .map(|entity| (rng.gen_range(1..=20), entity))
+                    .collect_vec();
+                // stable, so a tied roll-off keeps the order they were in
+                rolled.sort_by_key(|(roll, _)| Reverse(*roll));
+                self.status = Some(format!("Roll-off: {}", rolled.iter()
+                    .map(|(roll, entity)| format!("{} {roll}", entity.name.0))
+                    .join(", ")));
+                self.entities.splice(start..start, rolled.into_iter().map(|(_, entity)| entity));
+                if let Some(current) = current {
+                    self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+                }
+                self.refresh_adjacency();
+            }
+            Message::JoinGroup(i) => if i != 0 {
+                let leader = &self.entities[i - 1];
+                let (group, initiative) = (leader.group.unwrap_or(leader.id), leader.initiative.0);
+                self.entities[i - 1].group = Some(group);
+                let entity = &mut self.entities[i];
+                entity.group = Some(group);
+                entity.initiative.0 = initiative;
+                self.refresh_adjacency();
+            },
+            Message::LeaveGroup(i) => {
+                self.entities[i].group = None;
+                self.refresh_adjacency();
+            }
             Message::NewName(name) => self.new_entity.name.0.content = name,
             Message::NewInit(init) => {
                 if init.is_empty() || init == "-" || init == "+" || init.parse::<i32>().is_ok() {
@@ -515,7 +1937,7 @@ impl Application for InitiativeManager {
                 }
             }
             Message::NewHp(hp) => {
-                if hp.is_empty() || hp.parse::<Hp>().is_ok() {
+                if hp.is_empty() || hp.parse::<CurrentMaxHp>().is_ok() {
                     self.new_entity.hp.0.content = hp;
                 }
             }
@@ -524,161 +1946,429 @@ impl Application for InitiativeManager {
                     self.new_entity.leg_acts.0.content = las;
                 }
             }
+            Message::NewReactions(reactions) => {
+                if reactions.is_empty() || reactions.parse::<u32>().is_ok() {
+                    self.new_entity.reactions.content = reactions;
+                }
+            }
+            Message::NewCount(count) => {
+                if count.is_empty() || count.parse::<u32>().is_ok() {
+                    self.new_entity.count.content = count;
+                }
+            }
+            Message::NewSingleRoll(single_roll) => self.new_entity.single_roll = single_roll,
             Message::NewHidden(hidden, part) => match part {
                 HideablePart::Name => self.new_entity.name.1 = hidden,
                 HideablePart::Hp => self.new_entity.hp.1 = hidden,
                 HideablePart::LegActs => self.new_entity.leg_acts.1 = hidden,
                 HideablePart::Initiative => self.new_entity.init.1 = hidden,
             },
+            Message::NewStaged(staged) => self.new_entity.staged = staged,
+            Message::NewSurprised(surprised) => self.new_entity.surprised = surprised,
+            Message::Unsurprise(i) => self.entities[i].surprised = false,
+            Message::NewIcon(glyph) => self.new_entity.icon = Some(glyph),
+            Message::NewDisposition(disposition) => self.new_entity.disposition = disposition,
+            Message::Sideline(i) => {
+                let entity = self.remove_entity(i);
+                self.status = Some(format!("{} is out of combat", entity.name.0));
+                self.sidelined.push((entity, Default::default()));
+            }
+            Message::ReturnSidelined(i) => {
+                let (entity, _) = self.sidelined.remove(i);
+                self.status = Some(format!("{} is back in the fight", entity.name.0));
+                self.add_entity(entity);
+            }
+            Message::ToggleSidelined => self.sidelined_open = !self.sidelined_open,
+            Message::ToggleRoundHistory => self.round_history_open = !self.round_history_open,
+            Message::CycleTag(i) => {
+                let entity = &mut self.entities[i];
+                entity.tag = TagColor::cycle(entity.tag);
+            }
+            Message::TagLegendColor(color) => {
+                self.tag_legend_color = color;
+                self.tag_legend.content = self.settings.tag_legend.get(&color).cloned().unwrap_or_default();
+            }
+            Message::TagLegendMeaning(meaning) => {
+                if meaning.trim().is_empty() {
+                    self.settings.tag_legend.remove(&self.tag_legend_color);
+                } else {
+                    self.settings.tag_legend.insert(self.tag_legend_color, meaning.clone());
+                }
+                self.settings.save();
+                self.tag_legend.content = meaning;
+            }
+            Message::CycleDisposition(i) => {
+                let entity = &mut self.entities[i];
+                entity.disposition = entity.disposition.next();
+            }
+            Message::FilterDisposition(filter) => self.disposition_filter = filter,
+            Message::NewIconPath(path) => self.new_entity.icon_path.content = path,
+            Message::NewOwner(owner) => self.new_entity.owner.content = owner,
+            Message::Activate(i) => {
+                let mut entity = self.entities.remove(i);
+                if i < self.turn {
+                    self.turn -= 1;
+                }
+                entity.staged = false;
+                Self::insert_entity(&mut self.entities, &mut self.turn, entity);
+                self.refresh_adjacency();
+            }
             Message::NewEntitySubmit => {
-                if !self.new_entity.name.0.content.is_empty() {
+                if !self.new_entity.errors().any() {
                     let NewEntity {
                         name: Hidden(TextInputState { content: name, .. }, name_hidden),
                         init: Hidden(TextInputState { content: init, .. }, init_hidden),
                         hp: Hidden(TextInputState { content: hp, .. }, hp_hidden),
                         leg_acts: Hidden(TextInputState { content: leg_acts, .. }, leg_acts_hidden),
+                        reactions: TextInputState { content: reactions, .. },
+                        count: TextInputState { content: count, .. },
+                        single_roll,
+                        staged,
+                        surprised,
+                        disposition,
+                        owner: TextInputState { content: owner, .. },
+                        icon,
+                        icon_path: TextInputState { content: icon_path, .. },
+                        ..
                     } = std::mem::take(&mut self.new_entity);
-                    let hp = if hp.is_empty() {
-                        Hp::new(0)
-                    } else { hp.parse().unwrap() }
-                        .into_number()
-                        .unwrap_or(0);
-                    let init = if init.is_empty() || init.starts_with(['+', '-']) {
-                        let modifier = init.parse().unwrap_or(0);
-                        let roll = rand::thread_rng().gen_range(1..=20);
-                        std::cmp::max(0, roll + modifier) as u32
+                    let count = count.parse().unwrap_or(1_usize);
+                    // only a plain roll, anything with a `/` has a max that changes things
+                    let hp_formula = Some(hp.trim().to_string()).filter(|hp| hp.contains('d') && !hp.contains('/'));
+                    let roll = || if init.is_empty() || init.starts_with(['+', '-']) {
+                        roll_initiative(init.parse().unwrap_or(0))
                     } else {
                         init.parse().unwrap()
                     };
-                    let mut entity = Entity::new(
-                        Hidden(name, name_hidden),
-                        Hidden(hp, hp_hidden),
-                        Hidden(init, init_hidden),
-                    );
-                    if !leg_acts.is_empty() {
-                        let leg_acts = leg_acts.parse().unwrap();
-                        if leg_acts != 0 {
-                            entity.legendary_actions = Some((leg_acts, leg_acts).hidden(leg_acts_hidden));
+                    let group_roll = (single_roll && count > 1).then(roll);
+                    let owner = Some(owner.trim().to_string()).filter(|owner| !owner.is_empty());
+                    let icon = if icon_path.is_empty() {
+                        icon.map(EntityIcon::Glyph)
+                    } else {
+                        Some(EntityIcon::Image(PathBuf::from(icon_path).into()))
+                    };
+                    let mut added = Vec::new();
+                    for _ in 0..count {
+                        // a formula is rolled again for each of them
+                        let (hp, max_hp) = if hp.is_empty() {
+                            CurrentMaxHp::new(0)
+                        } else { hp.parse().unwrap() }
+                            .into_numbers()
+                            .unwrap_or((0, 0));
+                        let name = self.dedupe_name(name.clone());
+                        let mut entity = Entity::new(
+                            Hidden(name, name_hidden),
+                            Hidden(hp, hp_hidden),
+                            Hidden(group_roll.unwrap_or_else(roll), init_hidden),
+                        );
+                        entity.max_hp = max_hp;
+                        entity.hp_formula = hp_formula.clone();
+                        entity.staged = staged;
+                        entity.surprised = surprised;
+                        entity.disposition = disposition;
+                        entity.owner = owner.clone();
+                        entity.icon = icon.clone();
+                        if group_roll.is_some() {
+                            entity.group = added.first().copied().or(Some(entity.id));
                         }
-                    }
-                    Self::insert_entity(&mut self.entities, &mut self.turn, entity)
-                }
-            }
-            Message::HotKey(hotkey) => match hotkey {
-                hotkey::Message::NextField(forwards) => {
-                    // todo add other set of states for player inits
-                    let cycle = |states: &mut [&mut text_input::State]| {
-                        if let Some(i) = states.into_iter().position(|state| state.is_focused()) {
-                            if forwards {
-                                states[i].unfocus();
-                                states[(i + 1) % states.len()].focus();
-                            } else if !forwards {
-                                states[i].unfocus();
-                                states[if i == 0 { states.len() - 1 } else { i - 1 }].focus();
+                        if !leg_acts.is_empty() {
+                            let leg_acts = leg_acts.parse().unwrap();
+                            if leg_acts != 0 {
+                                entity.legendary_actions = Some((leg_acts, leg_acts).hidden(leg_acts_hidden));
                             }
                         }
-                    };
-                    cycle(&mut [
-                        &mut self.new_entity.name.0.state,
-                        &mut self.new_entity.init.0.state,
-                        &mut self.new_entity.hp.0.state,
-                        &mut self.new_entity.leg_acts.0.state,
-                    ]);
-                    match &mut self.save_mode {
-                        SaveMode::LoadParty(_, _, _, rows) => {
-                            let mut vec = rows.into_iter()
-                                .map(|(_, text_input)| &mut text_input.state)
-                                .collect_vec();
-                            cycle(&mut vec);
+                        if let Ok(reactions) = reactions.parse() {
+                            entity.reactions = (reactions, reactions);
                         }
-                        _ => {}
+                        added.push(entity.id);
+                        self.add_entity(entity);
                     }
+                    self.ask_about_ties(&added);
                 }
             }
-            Message::NextTurn => {
-                self.turn = (self.turn + 1).checked_rem(self.entities.len()).unwrap_or(0);
-                if let Some(entity) = self.entities.get_mut(self.turn) {
-                    entity.reaction_free.value = true;
-                    if let Some(Hidden((tot, left), _)) = &mut entity.legendary_actions {
-                        *left = *tot;
-                    }
+            Message::PresetSearch(search) => self.preset_search.content = search,
+            Message::AddPreset(name) => if let Some(preset) = srd::find(&name) {
+                let hp = preset.hp.parse::<Hp>()
+                    .ok()
+                    .and_then(Hp::into_number)
+                    .unwrap_or(0);
+                let mut entity = Entity::new(
+                    unique_name(&self.entities, &preset.name, &self.settings).into(),
+                    hp.into(),
+                    roll_initiative(preset.initiative).into(),
+                );
+                entity.ac = Some(preset.ac);
+                entity.hp_formula = Some(preset.hp.clone());
+                entity.icon = preset.icon.map(EntityIcon::Glyph);
+                entity.recharges = preset.recharges.iter()
+                    .map(|(name, min)| Recharge::new(name.clone(), *min))
+                    .collect();
+                if preset.legendary_actions != 0 {
+                    entity.legendary_actions = Some((preset.legendary_actions, preset.legendary_actions).into());
+                }
+                let id = entity.id;
+                self.add_entity(entity);
+                self.ask_about_ties(&[id]);
+            },
+            Message::HotKey(hotkey) => match hotkey {
+                hotkey::Message::NextField(forwards) => self.focus_next(forwards),
+                hotkey::Message::Activate => if let Some(message) = self.activate() {
+                    commands.push(self.update(message, clipboard));
+                },
+                hotkey::Message::Unfocus => self.keyboard_focus = None,
+                hotkey::Message::Cancel => {
+                    self.swapping = None;
+                    self.lair_banner = false;
                 }
+                hotkey::Message::Peek => self.peek(),
+                hotkey::Message::ToggleStreaming => {
+                    self.settings.streaming = !self.settings.streaming;
+                    self.settings.save();
+                }
+                hotkey::Message::NudgeHp(by) => self.nudge_hp(by, &mut commands),
             }
-            Message::PrevTurn => self.turn = if self.turn == 0 {
-                self.entities.len().saturating_sub(1)
-            } else {
-                self.turn.saturating_sub(1)
+            Message::NextTurn => self.advance_turn(&mut commands),
+            Message::PrevTurn => if let Some(prev) = self.next_turn(false) {
+                self.lair_banner = false;
+                let curr = self.turn % self.entities.len();
+                if prev >= curr {
+                    self.round = self.round.saturating_sub(1).max(1);
+                }
+                self.unrecord_turn(self.entities[prev].id);
+                // only undo if it's the same step back, otherwise (ex. an entity was deleted or
+                // moved in between) it's just a pointer move
+                let undo = self.turn_history.last()
+                    .filter(|undo| undo.incoming.id == self.entities[curr].id && undo.outgoing.0 == self.entities[prev].id)
+                    .is_some();
+                if undo {
+                    let TurnUndo { incoming, outgoing: (_, acted, legendary_actions), cleared } = self.turn_history.pop().unwrap();
+                    for entity in &mut self.entities {
+                        if cleared.contains(&entity.id) {
+                            entity.acted = true;
+                        }
+                    }
+                    incoming.restore(&mut self.entities[curr]);
+                    let prev = &mut self.entities[prev];
+                    prev.acted = acted;
+                    if let (Some(Hidden((_, left), _)), Some(saved)) = (&mut prev.legendary_actions, legendary_actions) {
+                        *left = saved;
+                    }
+                } else {
+                    self.turn_history.clear();
+                }
+                self.turn = prev;
             },
             Message::SaveEncounter => {
                 match &mut self.save_mode {
-                    SaveMode::SaveEncounter(name, _) if !name.content.is_empty() => {
-                        let enemies = self.entities.iter()
-                            .map(|Entity { name, hp, initiative, legendary_actions, .. }| Enemy {
-                                name: name.clone(),
-                                hp: *hp,
-                                legendary_actions: legendary_actions.map(|Hidden((las, _), hidden)| Hidden(las, hidden)),
-                                initiative: *initiative,
-                            }).collect_vec();
+                    SaveMode::SaveEncounter(name, notes, lair, template, _, _, rows) if !name.content.is_empty() => {
+                        let template = *template;
+                        let enemies = Self::enemies(&self.entities, rows);
                         let file = OpenOptions::new()
                             .create(true)
                             .write(true)
+                            .truncate(true)
                             .open(ENCOUNTER_DIR.join(format!("{}.json", name.content)))
                             .unwrap();
-                        serde_json::to_writer(file, &enemies).unwrap();
+                        let notes = notes.content.trim().to_string();
+                        let lair_actions = lair.content.trim().to_string();
+                        serde_json::to_writer(file, &EncounterFile {
+                            version: ENCOUNTER_VERSION,
+                            enemies,
+                            notes: notes.clone(),
+                            template,
+                            lair_actions: lair_actions.clone(),
+                        }).unwrap();
+                        if self.loaded_encounter.as_ref() == Some(&name.content) {
+                            self.encounter_notes = notes;
+                            self.lair_actions = lair_actions;
+                        }
 
                         self.save_mode = SaveMode::None;
                     }
-                    other => *other = SaveMode::SaveEncounter(TextInputState::focused(), Default::default()),
-                }
-            }
-            Message::EncounterName(name) => match &mut self.save_mode {
-                SaveMode::SaveEncounter(state, _)
-                | SaveMode::DeleteEncounter(_, state, _) => {
-                    state.content = name;
+                    other => {
+                        let name = TextInputState {
+                            content: self.loaded_encounter.clone().unwrap_or_default(),
+                            ..TextInputState::focused()
+                        };
+                        let notes = TextInputState {
+                            content: self.encounter_notes.clone(),
+                            ..Default::default()
+                        };
+                        let lair = TextInputState {
+                            content: self.lair_actions.clone(),
+                            ..Default::default()
+                        };
+                        let rows = SaveRow::all(&self.entities, true);
+                        *other = SaveMode::SaveEncounter(name, notes, lair, false, Default::default(), Default::default(), rows);
+                    }
                 }
-                _ => {}
             }
+            Message::EncounterName(name) => if let SaveMode::SaveEncounter(state, ..) = &mut self.save_mode {
+                state.content = name;
+            },
+            Message::EncounterNotes(notes) => if let SaveMode::SaveEncounter(_, state, ..) = &mut self.save_mode {
+                state.content = notes;
+            },
+            Message::EncounterLair(lair) => if let SaveMode::SaveEncounter(_, _, state, ..) = &mut self.save_mode {
+                state.content = lair;
+            },
+            Message::EncounterTemplate(template) => if let SaveMode::SaveEncounter(_, _, _, state, ..) = &mut self.save_mode {
+                *state = template;
+            },
+            Message::DismissLair => self.lair_banner = false,
+            Message::Rewind(label) => self.rewinding = self.snapshots.iter().position(|snapshot| snapshot.label == label),
+            Message::ConfirmRewind => if let Some(i) = self.rewinding.take().filter(|&i| i < self.snapshots.len()) {
+                // anything newer is from the timeline being undone
+                self.snapshots.truncate(i + 1);
+                let snapshot = &self.snapshots[i];
+                self.entities = snapshot.entities.iter().map(Entity::snapshot).collect();
+                self.turn = snapshot.turn;
+                self.round = snapshot.round;
+                self.status = Some(format!("Rewound to {}", snapshot.label));
+                // they all point at the timeline being undone too
+                let round = self.round;
+                self.round_history.retain(|(r, _)| *r < round);
+                self.turn_history.clear();
+                self.removed = None;
+                self.ties.clear();
+                self.swapping = None;
+                self.refresh_adjacency();
+            },
+            Message::CancelRewind => self.rewinding = None,
+            Message::ToggleNotes => self.notes_open = !self.notes_open,
             Message::DeleteEncounter(name) => {
                 match &mut self.save_mode {
-                    SaveMode::DeleteEncounter(curr_name, _, _) if name == *curr_name => {
+                    SaveMode::DeleteEncounter(confirm) if name == confirm.name => {
                         // ignore error
                         let _ = fs::remove_file(ENCOUNTER_DIR.join(format!("{name}.json")));
+                        if self.loaded_encounter.as_ref() == Some(&name) {
+                            self.loaded_encounter = None;
+                        }
 
                         self.save_mode = SaveMode::None;
                     }
-                    other => *other = SaveMode::DeleteEncounter(name, TextInputState::focused(), Default::default())
+                    other => {
+                        let confirm = ConfirmDelete::new(name.clone(), Message::DeleteEncounter(name.clone()))
+                            .tap_if(self.loaded_encounter.as_ref() == Some(&name), |confirm| confirm
+                                .warning("This encounter is currently loaded, deleting it won't remove anything from the board"));
+                        *other = SaveMode::DeleteEncounter(confirm);
+                    }
                 }
             }
-            Message::LoadEncounter(name) => {
+            Message::LoadEncounter(name) => if let Some(name) = name.strip_prefix(UNREADABLE) {
+                self.status = Some(format!("{name} couldn't be read, fix or delete its file to load it"));
+            } else {
                 // rows to enter initiative for each character
                 match &mut self.save_mode {
-                    SaveMode::LoadEncounter(curr_name, _, _, rows) if name == *curr_name => {
-                        rows.drain(0..)
-                            .map(|Enemy { name, hp, legendary_actions, initiative }| {
-                                Entity::new(name, hp, initiative)
-                                    .tap_if_some(legendary_actions, |mut e, Hidden(las, hidden)| {
-                                        e.legendary_actions = Some(Hidden((las, las), hidden));
-                                        e
-                                    })
-                            }).for_each(|e| Self::insert_entity(&mut self.entities, &mut self.turn, e));
+                    SaveMode::LoadEncounter(curr_name, _, _, rows, suffix_duplicates, _, adjust, notes) if name == *curr_name => {
+                        let notes = std::mem::take(notes);
+                        sort_for_loading(rows);
+                        let mut taken = self.entities.iter()
+                            .map(|e| e.name.0.clone())
+                            .collect::<HashSet<_>>();
+                        let mut added = Vec::new();
+                        let copies = adjust.copies();
+                        let by_hand = adjust.by_hand;
+                        let lair_actions = std::mem::take(&mut adjust.lair_actions);
+                        // the copies each get their own HP roll, the preview only has one per enemy,
+                        // and a template's copies the initiative the preview placed them at
+                        let enemies = rows.drain(0..)
+                            .flat_map(|enemy| (0..=copies).map(move |copy| {
+                                let mut enemy = enemy.clone();
+                                if copy != 0 {
+                                    LoadAdjustments::reroll_all(std::slice::from_mut(&mut enemy));
+                                    enemy.initiative.0 = enemy.copy_initiative(copy);
+                                }
+                                enemy
+                            }))
+                            .collect_vec();
+                        let mut duplicate = None;
+                        for Enemy { id, name, hp, max_hp, ac, hp_formula, rolled_hp, icon, disposition, owner, legendary_actions, reactions, initiative, initiative_bonus, .. } in enemies {
+                            // no formula falls back to the saved hp
+                            let (hp, max_hp) = match rolled_hp.filter(|_| adjust.reroll) {
+                                Some(rolled) => (Hidden(rolled, hp.1), Some(rolled)),
+                                None => (hp, max_hp),
+                            };
+                            let mut entity = Entity::new(name, Hidden(adjust.hp(hp.0), hp.1), initiative)
+                                .tap_if_some(max_hp, |mut e, max_hp| {
+                                    e.max_hp = adjust.hp(max_hp);
+                                    e
+                                })
+                                .tap_if_some(legendary_actions, |mut e, Hidden(las, hidden)| {
+                                    e.legendary_actions = Some(Hidden((las, las), hidden));
+                                    e
+                                });
+                            if *suffix_duplicates || copies != 0 {
+                                entity.name.0 = self.settings.suffixed_name(&taken, &entity.name.0);
+                                entity.rescramble();
+                            } else if taken.contains(&entity.name.0) {
+                                duplicate = Some(entity.name.0.clone());
+                            }
+                            taken.insert(entity.name.0.clone());
+                            entity.ac = ac;
+                            entity.hp_formula = hp_formula;
+                            entity.icon = icon;
+                            entity.disposition = disposition;
+                            entity.owner = owner;
+                            if let Some(reactions) = reactions {
+                                entity.reactions = (reactions, reactions);
+                            }
+                            entity.initiative_bonus = initiative_bonus;
+                            entity.id = Self::claim_id(&self.entities, id);
+                            entity.origin = Some(Origin::Encounter);
+                            added.push(entity.id);
+                            Self::insert_in_order(&mut self.entities, &mut self.turn, entity);
+                        }
+                        self.refresh_adjacency();
+                        if let Some(duplicate) = duplicate {
+                            self.status = Some(duplicate_warning(&duplicate));
+                        }
+                        self.loaded_encounter = Some(name);
+                        self.encounter_notes = notes;
+                        self.notes_open = !self.encounter_notes.is_empty();
+                        self.lair_actions = lair_actions;
 
                         self.save_mode = SaveMode::None;
+                        if by_hand {
+                            // ties are asked about once the real initiatives are in
+                            let mut rows: Vec<_> = self.entities.iter()
+                                .filter(|e| added.contains(&e.id))
+                                .map(|e| (e.id, e.name.0.clone(), e.initiative.0, TextInputState::default()))
+                                .collect();
+                            if let Some((.., TextInputState { state, .. })) = rows.first_mut() {
+                                state.focus();
+                            }
+                            self.save_mode = SaveMode::EnterInitiatives(Default::default(), Default::default(), rows);
+                        } else {
+                            self.ask_about_ties(&added);
+                        }
                     }
                     other => {
                         let file = OpenOptions::new()
                             .read(true)
                             .open(ENCOUNTER_DIR.join(format!("{name}.json")))
-                            .unwrap();
-                        let rows = serde_json::from_reader::<_, Vec<Enemy>>(file)
-                            .unwrap()
-                            .into_iter()
-                            .collect();
-                        *other = SaveMode::LoadEncounter(name, Default::default(), Default::default(), rows)
+                            .map_err(|e| e.to_string())
+                            .and_then(|file| EncounterFile::from_reader(file).map_err(|e| e.to_string()));
+                        match file {
+                            Ok(EncounterFile { enemies: mut rows, notes, template, lair_actions, .. }) => {
+                                let mut adjust = LoadAdjustments { template, lair_actions, ..Default::default() };
+                                if template {
+                                    adjust.reroll = true;
+                                    LoadAdjustments::reroll_all(&mut rows);
+                                    LoadAdjustments::roll_initiatives(&mut rows);
+                                }
+                                *other = SaveMode::LoadEncounter(name, Default::default(), Default::default(), rows, true, Default::default(), adjust, notes)
+                            }
+                            Err(e) => {
+                                // changed since the list was checked
+                                self.encounter_checks.remove(&name);
+                                self.status = Some(format!("Couldn't load {name}: {e}"));
+                            }
+                        }
                     }
                 }
-            }
+            },
             Message::EncounterHide(idx, hide, part) => match &mut self.save_mode {
-                SaveMode::LoadEncounter(_, _, _, enemies) => match part {
+                SaveMode::LoadEncounter(_, _, _, enemies, ..) => match part {
                     HideablePart::Name => enemies[idx].name.1 = hide,
                     HideablePart::Hp => enemies[idx].hp.1 = hide,
                     HideablePart::LegActs => if let Some(las) = &mut enemies[idx].legendary_actions {
@@ -688,51 +2378,305 @@ impl Application for InitiativeManager {
                 }
                 _ => {}
             },
+            Message::SuffixDuplicates(suffix) => if let SaveMode::LoadEncounter(_, _, _, _, suffix_duplicates, ..) = &mut self.save_mode {
+                *suffix_duplicates = suffix;
+            },
+            Message::EncounterHpScale(hp) => if let SaveMode::LoadEncounter(.., adjust, _) = &mut self.save_mode {
+                if hp.is_empty() || hp.parse::<f64>().map_or(false, |hp| hp >= 0.0 && hp.is_finite()) {
+                    adjust.hp.content = hp;
+                }
+            },
+            Message::RerollHp(reroll) => if let SaveMode::LoadEncounter(_, _, _, enemies, .., adjust, _) = &mut self.save_mode {
+                adjust.reroll = reroll;
+                if reroll {
+                    LoadAdjustments::reroll_all(enemies);
+                }
+            },
+            Message::InitiativesByHand(by_hand) => if let SaveMode::LoadEncounter(.., adjust, _) = &mut self.save_mode {
+                adjust.by_hand = by_hand;
+            },
+            Message::ManualInitiative(idx, init) => if let SaveMode::EnterInitiatives(_, _, rows) = &mut self.save_mode {
+                if init.is_empty() || init.parse::<u32>().is_ok() {
+                    rows[idx].3.content = init;
+                }
+            },
+            Message::SubmitInitiatives => if let SaveMode::EnterInitiatives(_, _, rows) = std::mem::take(&mut self.save_mode) {
+                let entered: HashMap<_, u32> = rows.iter()
+                    .filter_map(|(id, _, _, text)| Some((*id, text.content.parse().ok()?)))
+                    .collect();
+                // taken out and put back in like they'd just been added, so they go after anyone
+                // already at their new initiative
+                let current = self.turn.checked_rem(self.entities.len()).map(|turn| self.entities[turn].id);
+                let (mut moved, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entities)
+                    .into_iter()
+                    .partition(|e| entered.contains_key(&e.id));
+                self.entities = rest;
+                // in the order they were listed
+                moved.sort_by_key(|e| rows.iter().position(|(id, ..)| *id == e.id));
+                for mut entity in moved {
+                    entity.initiative.0 = entered[&entity.id];
+                    Self::insert_entity(&mut self.entities, &mut self.turn, entity);
+                }
+                if let Some(current) = current {
+                    self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+                }
+                self.refresh_adjacency();
+                let ids = rows.iter().map(|(id, ..)| *id).collect_vec();
+                self.ask_about_ties(&ids);
+            },
+            Message::RerollAllHp => if let SaveMode::LoadEncounter(_, _, _, enemies, ..) = &mut self.save_mode {
+                LoadAdjustments::reroll_all(enemies);
+            },
+            Message::EncounterCopies(copies) => if let SaveMode::LoadEncounter(_, _, _, enemies, _, _, adjust, _) = &mut self.save_mode {
+                if copies.is_empty() || copies.parse::<u8>().is_ok() {
+                    adjust.copies.content = copies;
+                    if adjust.template {
+                        LoadAdjustments::roll_copies(enemies, adjust.copies());
+                    }
+                }
+            },
+            Message::ClearEncounter => {
+                let url = self.settings.summary_url.trim().to_string();
+                if self.settings.history || !url.is_empty() {
+                    let summary = history::Summary {
+                        version: history::VERSION,
+                        timestamp: chrono::Local::now().to_rfc3339(),
+                        encounter: self.loaded_encounter.clone(),
+                        rounds: self.round,
+                        damage: report::totals(&self.combat_log),
+                        survivors: self.entities.iter()
+                            .filter(|entity| entity.hp.0 != 0)
+                            .map(|entity| entity.name.0.clone())
+                            .collect(),
+                    };
+                    if self.settings.history {
+                        commands.push(Command::perform(history::append(summary.clone()), Message::SummaryDelivered));
+                    }
+                    if !url.is_empty() {
+                        commands.push(Command::perform(history::post(url, summary), Message::SummaryDelivered));
+                    }
+                }
+                let combatants = self.entities.iter()
+                    .map(Entity::combatant)
+                    .chain(self.fallen.drain(..).map(|(_, combatant)| combatant))
+                    .collect();
+                self.recap = Some(recap::Recap { rounds: self.round, combatants });
+                // pinned entities stay for the next fight, keeping their HP but starting fresh otherwise
+                self.entities.retain(|entity| entity.pinned.value);
+                self.sidelined.retain(|(entity, _)| entity.pinned.value);
+                for entity in &mut self.entities {
+                    entity.acted = false;
+                    entity.last_turn = None;
+                    entity.damage_dealt = 0;
+                    entity.damage_taken = 0;
+                    entity.healing_received = 0;
+                }
+                self.turn_history.clear();
+                self.round_history.clear();
+                self.snapshots.clear();
+                self.rewinding = None;
+                self.ties.clear();
+                self.swapping = None;
+                self.order_locked.value = false;
+                self.removed = None;
+                self.combat_log.clear();
+                self.turn = 0;
+                self.round = 1;
+                self.refresh_adjacency();
+                self.forget_origins();
+            }
+            Message::SaveRecap => if let Some(recap) = self.recap.take() {
+                commands.push(Command::perform(recap::save(recap), Message::RecapSaved));
+            },
+            Message::DismissRecap => self.recap = None,
+            Message::PlaceTie(before) => self.place_tie(before),
+            Message::ToggleCompanion(i, choice) => {
+                let owner = &self.entities[i];
+                let owner_id = owner.id;
+                let choices = companion::choices(&owner.companions, |c| self.entities.iter()
+                    .any(|e| e.companion_of == Some((owner_id, c))));
+                if let Some(c) = choices.iter().position(|label| *label == choice) {
+                    match self.entities.iter().position(|e| e.companion_of == Some((owner_id, c))) {
+                        Some(present) => {
+                            let companion = self.remove_entity(present);
+                            self.status = Some(format!("{} left the fight", companion.name.0));
+                        }
+                        None => self.add_companion(i, c),
+                    }
+                }
+            }
+            Message::SwapInitiative(i) => {
+                let id = self.entities[i].id;
+                match self.swapping.take() {
+                    None => self.swapping = Some(id),
+                    Some(first) if first == id => {}
+                    Some(first) => if let Some(first) = self.entities.iter().position(|e| e.id == first) {
+                        self.swap_initiative(first, i);
+                    },
+                }
+            }
+            Message::ResetAllReactions => {
+                for entity in &mut self.entities {
+                    entity.reactions.0 = entity.reactions.1;
+                }
+                self.status = Some(String::from("Everyone has their reaction back"));
+            }
+            Message::Tick => if self.peek_until.map_or(false, |until| until <= Instant::now()) {
+                self.peek_until = None;
+            },
+            Message::RecapSaved(result) => self.status = Some(match result {
+                Ok(path) => format!("Saved the recap to {}", path.display()),
+                Err(e) => format!("Couldn't save the recap: {e}"),
+            }),
+            Message::UndoDelete => {
+                if let Some(Removal { entities, logged }) = self.removed.take() {
+                    if let Some((_, first)) = entities.first() {
+                        self.status = Some(format!("Restored {}", first.name.0));
+                    }
+                    // backwards, so each goes back where it was before the ones removed after it
+                    for (i, entity) in entities.into_iter().rev() {
+                        let i = i.min(self.entities.len());
+                        self.fallen.retain(|(id, _)| *id != entity.id);
+                        if logged {
+                            let removed = self.combat_log.iter()
+                                .rposition(|entry| matches!(&entry.event, report::LogEvent::Removed { name } if *name == entity.name.0));
+                            if let Some(removed) = removed {
+                                self.combat_log.remove(removed);
+                            }
+                        }
+                        self.entities.insert(i, entity);
+                        if i < self.turn {
+                            self.turn += 1;
+                        }
+                    }
+                    self.refresh_adjacency();
+                }
+            }
+            Message::ForgetRemoved(id) => {
+                // a newer removal has its own timer
+                if matches!(&self.removed, Some(removal) if removal.entities.first().map(|(_, e)| e.id) == Some(id)) {
+                    self.removed = None;
+                    self.status = None;
+                }
+            }
             Message::SaveParty => {
                 // create name field, once submitted save names and HP of all entities
                 match &mut self.save_mode {
-                    SaveMode::SaveParty(name, _) if !name.content.is_empty() => {
-                        let pcs = self.entities.iter()
-                            .map(|Entity { name, hp, .. }| Pc { name: name.0.clone(), hp: hp.0 })
-                            .collect_vec();
+                    SaveMode::SaveParty(name, _, _, _, rows) if !name.content.is_empty() => {
+                        let pcs = Self::pcs(&self.entities, rows);
                         let file = OpenOptions::new()
                             .create(true)
                             .write(true)
+                            .truncate(true)
                             .open(PARTY_DIR.join(format!("{}.json", name.content)))
                             .unwrap();
                         serde_json::to_writer(file, &pcs).unwrap();
 
                         self.save_mode = SaveMode::None;
                     }
-                    other => *other = SaveMode::SaveParty(TextInputState::focused(), Default::default()),
+                    other => {
+                        let name = TextInputState {
+                            content: self.loaded_party.clone().unwrap_or_default(),
+                            ..TextInputState::focused()
+                        };
+                        let rows = SaveRow::all(&self.entities, false);
+                        *other = SaveMode::SaveParty(name, Default::default(), Default::default(), Default::default(), rows);
+                    }
                 };
             }
-            Message::PartyName(name) => match &mut self.save_mode {
-                SaveMode::SaveParty(state, _)
-                | SaveMode::DeleteParty(_, state, _) => {
-                    state.content = name;
+            Message::SavePcs => if let SaveMode::SaveParty(.., rows) = &self.save_mode {
+                let pcs = Self::pcs(&self.entities, rows);
+                let saved = pcs.iter()
+                    .filter(|pc| fs::write(PC_DIR.join(format!("{}.json", pc.name)), serde_json::to_string(pc).unwrap()).is_ok())
+                    .count();
+                self.status = Some(format!("Saved {saved} {}", if saved == 1 { "player" } else { "players" }));
+                self.save_mode = SaveMode::None;
+            },
+            Message::AssembleParty => match &mut self.save_mode {
+                SaveMode::AssembleParty(save_as, _, _, rows) if rows.iter().any(|(.., picked)| *picked) => {
+                    let pcs = std::mem::take(rows).into_iter()
+                        .filter(|(.., picked)| *picked)
+                        .map(|(_, pc, _)| pc)
+                        .collect_vec();
+                    let name = save_as.content.trim().to_string();
+                    let name = if name.is_empty() {
+                        String::from(ADHOC_PARTY)
+                    } else {
+                        match serde_json::to_string(&pcs).map(|json| fs::write(PARTY_DIR.join(format!("{name}.json")), json)) {
+                            Ok(Ok(())) => self.status = Some(format!("Saved {name}")),
+                            Ok(Err(e)) => self.status = Some(format!("Couldn't save {name}: {e}")),
+                            Err(e) => self.status = Some(format!("Couldn't save {name}: {e}")),
+                        }
+                        name
+                    };
+                    let mut rows = pcs.into_iter()
+                        .map(|pc| (pc, TextInputState::default(), Default::default(), 0, TextInputState::default()))
+                        .collect_vec();
+                    if let Some((_, TextInputState { state, .. }, ..)) = rows.first_mut() {
+                        state.focus();
+                    }
+                    self.save_mode = SaveMode::LoadParty(name, Default::default(), Default::default(), rows);
                 }
+                SaveMode::AssembleParty(..) => {}
+                other => *other = SaveMode::AssembleParty(TextInputState::focused(), Default::default(), Default::default(), assemble_rows()),
+            },
+            Message::AssembleSelect(i, picked) => if let SaveMode::AssembleParty(.., rows) = &mut self.save_mode {
+                rows[i].2 = picked;
+            },
+            Message::AssembledName(name) => if let SaveMode::AssembleParty(state, ..) = &mut self.save_mode {
+                state.content = name;
+            },
+            Message::PartyName(name) => if let SaveMode::SaveParty(state, ..) = &mut self.save_mode {
+                state.content = name;
+            },
+            Message::SaveSelect(i, included) => match &mut self.save_mode {
+                SaveMode::SaveEncounter(.., rows) | SaveMode::SaveParty(.., rows) => rows[i].included = included,
                 _ => {}
             },
             Message::DeleteParty(name) => {
                 match &mut self.save_mode {
-                    SaveMode::DeleteParty(curr_name, _, _) if name == *curr_name => {
+                    SaveMode::DeleteParty(confirm) if name == confirm.name => {
                         // ignore error
                         let _ = fs::remove_file(PARTY_DIR.join(format!("{name}.json")));
+                        if self.loaded_party.as_ref() == Some(&name) {
+                            self.loaded_party = None;
+                        }
 
                         self.save_mode = SaveMode::None;
                     }
-                    other => *other = SaveMode::DeleteParty(name, TextInputState::focused(), Default::default())
+                    other => {
+                        let confirm = ConfirmDelete::new(name.clone(), Message::DeleteParty(name.clone()))
+                            .tap_if(self.loaded_party.as_ref() == Some(&name), |confirm| confirm
+                                .warning("This party is currently loaded, deleting it won't remove anything from the board"));
+                        *other = SaveMode::DeleteParty(confirm);
+                    }
                 }
             }
             Message::LoadParty(name) => {
                 // rows to enter initiative for each character
                 match &mut self.save_mode {
                     SaveMode::LoadParty(curr_name, _, _, rows) if name == *curr_name => {
-                        rows.drain(0..)
-                            .map(|(Pc { name, hp }, txt)| {
-                                Entity::new(name.hidden(false), hp.hidden(false), Hidden(txt.content.parse().unwrap(), false))
-                            }).for_each(|e| Self::insert_entity(&mut self.entities, &mut self.turn, e));
+                        // added all together and sorted once, so players who tie each other keep the
+                        // order their initiatives were entered in
+                        let mut added = Vec::new();
+                        let mut batch = Vec::new();
+                        for (Pc { id, name, hp, max_hp, owner, companions }, txt, _, _, current_hp) in std::mem::take(rows) {
+                            let name = self.dedupe_name(name);
+                            let current_hp = current_hp.content.parse().unwrap_or(hp);
+                            let mut entity = Entity::new(name.hidden(false), current_hp.hidden(false), Hidden(txt.content.parse().unwrap(), false));
+                            entity.max_hp = max_hp.unwrap_or(hp);
+                            entity.id = Self::claim_id(&self.entities, id);
+                            entity.origin = Some(Origin::Party);
+                            entity.disposition = Disposition::Ally;
+                            entity.owner = owner;
+                            entity.companions = companions;
+                            added.push(entity.id);
+                            batch.push(entity);
+                        }
+                        Self::insert_batch(&mut self.entities, &mut self.turn, batch);
+                        self.refresh_adjacency();
+                        self.ask_about_ties(&added);
+                        self.loaded_party = Some(name);
 
                         self.save_mode = SaveMode::None;
                     }
@@ -744,9 +2688,9 @@ impl Application for InitiativeManager {
                         let mut rows: Vec<_> = serde_json::from_reader::<_, Vec<Pc>>(file)
                             .unwrap()
                             .into_iter()
-                            .map(|pc| (pc, TextInputState::default()))
+                            .map(|pc| (pc, TextInputState::default(), Default::default(), 0, TextInputState::default()))
                             .collect();
-                        if let Some((_, TextInputState { state, .. })) = rows.first_mut() {
+                        if let Some((_, TextInputState { state, .. }, ..)) = rows.first_mut() {
                             state.focus();
                         }
                         *other = SaveMode::LoadParty(name, Default::default(), Default::default(), rows)
@@ -758,52 +2702,233 @@ impl Application for InitiativeManager {
                     rows[idx].1.content = init;
                 }
             },
-        };
-        Command::batch(commands)
-    }
-
-    fn subscription(&self) -> Subscription<Self::Message> {
-        let listeners = iced_native::subscription::events_with(|event, _status| {
-            match event {
-                Event::Keyboard(e) => hotkey::handle(e),
-                Event::Window(e) => match e {
-                    iced_native::window::Event::Resized { width, height } => Some(Message::Resize(width, height)),
-                    iced_native::window::Event::FileDropped(path) => {
-                        println!("path = {:?}", path);
-                        todo!()
-                    }
+            Message::PcHp(idx, hp) => if let SaveMode::LoadParty(_, _, _, rows) = &mut self.save_mode {
+                if hp.is_empty() || hp.parse::<u32>().is_ok() {
+                    rows[idx].4.content = hp;
+                }
+            },
+            Message::EditCondition(i, text) => self.entities[i].new_condition.content = text,
+            Message::AddCondition(i) => {
+                let entity = &mut self.entities[i];
+                if let Some(condition) = Condition::parse(&entity.new_condition.content, self.round) {
+                    entity.conditions.push(condition);
+                    entity.new_condition.content.clear();
+                }
+            }
+            Message::QuickCondition(i, q) => if let Some(condition) = self.settings.quick_conditions.get(q)
+                .and_then(|text| Condition::parse(text, self.round)) {
+                let entity = &mut self.entities[i];
+                if !entity.conditions.iter().any(|c| c.name.eq_ignore_ascii_case(&condition.name)) {
+                    entity.conditions.push(condition);
+                }
+            },
+            Message::RemoveCondition(i, c) => { self.entities[i].conditions.remove(c); }
+            Message::UseRecharge(i, r) => {
+                let recharge = &mut self.entities[i].recharges[r];
+                recharge.ready = !recharge.ready;
+            }
+            Message::Confirm(msg) => match &mut self.save_mode {
+                SaveMode::DeleteEncounter(confirm)
+                | SaveMode::DeleteParty(confirm) => confirm.update(msg),
+                _ => {}
+            },
+            Message::Bulk(msg) => match msg {
+                bulk::Message::Toggle => {
+                    self.bulk.open = !self.bulk.open;
+                    self.bulk.confirming = None;
+                }
+                bulk::Message::HealParty => self.heal_party(),
+                bulk::Message::Amount(amount) => if amount.is_empty() || amount.parse::<u32>().is_ok() {
+                    self.bulk.amount.content = amount;
+                    self.bulk.confirming = None;
+                },
+                bulk::Message::Target(target) => {
+                    self.bulk.target = target;
+                    self.bulk.confirming = None;
+                }
+                bulk::Message::Damage => self.bulk.confirming = Some(self.bulk_targets().len()),
+                bulk::Message::ConfirmDamage => {
+                    if let Some(amount) = self.bulk.amount() {
+                        let targets = self.bulk_targets();
+                        // ids, since anyone auto-removed shifts everyone after them
+                        for id in &targets {
+                            if let Some(i) = self.entities.iter().position(|e| e.id == *id) {
+                                self.damage(i, amount, &mut commands);
+                            }
+                        }
+                        self.status = Some(format!("Dealt {amount} damage to {}", targets.len()));
+                        self.bulk.amount.content.clear();
+                    }
+                    self.bulk.confirming = None;
+                }
+                bulk::Message::Cancel => self.bulk.confirming = None,
+            },
+            Message::Reminder(msg) => match msg {
+                reminder::Message::Toggle => self.reminders.open = !self.reminders.open,
+                reminder::Message::Owner(pick) => self.reminders.owner = Some(pick),
+                reminder::Message::On(pick) => self.reminders.on = Some(pick),
+                reminder::Message::Text(text) => self.reminders.text.content = text,
+                reminder::Message::Add => if self.reminders.add() {
+                    self.status = Some(String::from("Added a reminder"));
+                },
+                reminder::Message::Dismiss(i) => if let Some(reminder) = self.reminders.list.get_mut(i) {
+                    reminder.dismissed = true;
+                },
+                reminder::Message::Delete(i) => if i < self.reminders.list.len() {
+                    self.reminders.list.remove(i);
+                },
+            },
+        };
+        if turn_changed {
+            for reminder in &mut self.reminders.list {
+                reminder.dismissed = false;
+            }
+        }
+        // a reminder goes with its owner when they die or are deleted (sidelined is still here), and
+        // with whoever it's for when they're deleted
+        let alive = |id: EntityId| self.entities.iter().any(|e| e.id == id && !e.dead)
+            || self.sidelined.iter().any(|(e, _)| e.id == id);
+        let present = |id: EntityId| self.entities.iter().any(|e| e.id == id)
+            || self.sidelined.iter().any(|(e, _)| e.id == id);
+        self.reminders.list.retain(|reminder| alive(reminder.owner) && match reminder.trigger {
+            reminder::Trigger::Everyone => true,
+            reminder::Trigger::Entity(id) => present(id),
+        });
+        for pick in [&mut self.reminders.owner, &mut self.reminders.on] {
+            if matches!(pick, Some(reminder::Pick { id: Some(id), .. }) if !present(*id)) {
+                *pick = None;
+            }
+        }
+        if turn_changed && self.settings.overlay {
+            commands.push(self.schedule_overlay());
+        }
+        if turn_changed && self.settings.hook && !self.settings.hook_command.is_empty() {
+            let events = if combat_ended {
+                vec![hook::Event::CombatEnd]
+            } else if self.round != round {
+                vec![hook::Event::Turn, hook::Event::Round]
+            } else {
+                vec![hook::Event::Turn]
+            };
+            let name = self.player_rows().into_iter()
+                .find(|row| row.is_turn)
+                .map_or_else(String::new, |row| row.name);
+            commands.extend(events.into_iter().map(|event| Command::perform(
+                hook::run(self.settings.hook_command.clone(), event, self.round, name.clone()),
+                Message::HookRan,
+            )));
+        }
+        let awaiting_initiative = match &self.save_mode {
+            SaveMode::LoadParty(_, _, _, rows) => rows.iter().map(|(pc, ..)| pc.name.clone()).collect(),
+            _ => Vec::new(),
+        };
+        let _ = self.player_view.send(server::PlayerView {
+            round: self.round,
+            rows: self.player_rows(),
+            awaiting_initiative,
+        });
+        Command::batch(commands)
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let listeners = iced_native::subscription::events_with(|event, _status| {
+            match event {
+                Event::Keyboard(e) => hotkey::handle(e),
+                Event::Window(e) => match e {
+                    iced_native::window::Event::Resized { width, height } => Some(Message::Resize(width, height)),
+                    iced_native::window::Event::CloseRequested => Some(Message::Exit),
+                    iced_native::window::Event::FileDropped(path) => {
+                        println!("path = {:?}", path);
+                        todo!()
+                    }
                     _ => None,
                 },
+                Event::Mouse(iced_native::mouse::Event::ButtonPressed(_)) => Some(Message::HotKey(hotkey::Message::Unfocus)),
                 // Event::Mouse(e) => hotmouse::handle(e),
                 // Event::Touch(_) => None,
                 _ => None
             }
         });
-        match &self.update_state {
-            UpdateState::Ready | UpdateState::Downloading(_) => {
-                let download = Subscription::from_recipe(update::Download { url: self.update_url.clone() })
-                    .map(|p| Message::Update(update::Message::Progress(p)));
-                Subscription::batch([
-                    listeners,
-                    download,
-                ])
-            }
-            _ => listeners
+        if let Some(mirror) = &self.mirror {
+            let connect = Subscription::from_recipe(sync::Connect { address: mirror.address.clone() })
+                .map(Message::Sync);
+            return Subscription::batch([listeners, connect]);
         }
+        let mut subscriptions = vec![listeners];
+        if self.entities.iter().any(|e| e.last_turn.is_some()) {
+            subscriptions.push(iced_futures::time::every(SINCE_TICK).map(|_| Message::Tick));
+        }
+        if self.peek_until.is_some() {
+            subscriptions.push(iced_futures::time::every(PEEK_TICK).map(|_| Message::Tick));
+        }
+        if let UpdateState::Ready | UpdateState::Downloading(_) = &self.update_state {
+            subscriptions.push(Subscription::from_recipe(update::Download { url: self.update_url.clone() })
+                .map(|p| Message::Update(update::Message::Progress(p))));
+        }
+        if self.settings.server {
+            subscriptions.push(Subscription::from_recipe(server::Server {
+                port: self.settings.server_port,
+                view: self.player_view_rx.clone(),
+            }).map(Message::Server));
+        }
+        if self.settings.host {
+            subscriptions.push(Subscription::from_recipe(sync::Host {
+                port: self.settings.sync_port,
+                view: self.player_view_rx.clone(),
+            }).map(Message::Sync));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn view(&mut self) -> Element<'_, Self::Message> {
         const INITIATIVES_PADDING: u16 = 8;
         const INITIATIVES_BORDER_PADDING: u16 = 4;
         const INITIATIVES_INTERIOR_PADDING: u16 = 4;
-        const CONTROL_SPACING: u16 = 5;
-        const HP_MOD_WIDTH: u16 = 26;
-        const COLUMN_WIDTH_RATIO: (u16, u16) = (3, 2);
+        const COMPACT_INTERIOR_PADDING: u16 = 1;
+        const CALLER_TEXT_SIZE: u16 = 48;
+        /// rows need room around their touch-sized controls, even when compact
+        const LARGE_INTERIOR_PADDING: u16 = 6;
+        /// `FillPortion`s are integers, so the ratio is split into this many parts
+        const RATIO_PARTS: f32 = 1000.0;
+        const STAGED_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.5);
+
+        if let Some(mirror) = &self.mirror {
+            return mirror.view(self.style);
+        }
 
-        let dm_view = self.dm_view.value;
+        let dm_view = self.dm_view();
         let style = self.style;
+        let origin = self.origin();
+        let Settings { compact, striped, legendary_display, large_controls, censor, .. } = self.settings;
+        // what the players don't get to see while the DM view is off
+        let (hide_names, hide_hp, hide_conditions) = (!dm_view && censor.names, !dm_view && censor.hp, !dm_view && censor.conditions);
+        let focus = self.keyboard_focus;
+        let load_preview = self.load_preview().unwrap_or_default();
+        let round_history_open = self.round_history_open;
+        let round_history_lines = if round_history_open { self.round_history_lines(!hide_names) } else { Vec::new() };
+        // for the DM to read out, or the players to read off a projector
+        let lair_banner = self.lair_banner && dm_view;
+        let called_initiative = self.settings.initiative_caller
+            .then(|| self.turn.checked_rem(self.entities.len()).and_then(|turn| self.entities.get(turn)))
+            .flatten()
+            .map(|entity| match entity.initiative {
+                Hidden(_, true) if !dm_view => String::from("?"),
+                Hidden(initiative, _) => initiative.to_string(),
+            });
+        // compact only shrinks the text, large controls decide how big everything clickable is
+        let controls = if large_controls { Controls::LARGE } else { Controls::NORMAL };
+        let interior_padding = if large_controls {
+            LARGE_INTERIOR_PADDING
+        } else if compact {
+            COMPACT_INTERIOR_PADDING
+        } else {
+            INITIATIVES_INTERIOR_PADDING
+        };
+        let (text_size, header_size) = if compact { (13, 14) } else { (16, 17) };
         let width = self.width;
-        let init_width = (width as u16 * COLUMN_WIDTH_RATIO.0) as f64 / (COLUMN_WIDTH_RATIO.0 + COLUMN_WIDTH_RATIO.1) as f64;
+        let table_ratio = self.settings.table_ratio.clamp(settings::MIN_TABLE_RATIO, settings::MAX_TABLE_RATIO);
+        let table_portion = (table_ratio * RATIO_PARTS) as u16;
+        let init_width = width as f64 * table_ratio as f64;
         let options_width = width as f64 - init_width;
 
         let has_legendary_action = self.entities.iter()
@@ -829,25 +2954,77 @@ impl Application for InitiativeManager {
 
         let n_entities = self.entities.len();
         let turn = self.turn;
+        let round = self.round;
 
-        let mut up_down = vec![false];
-        up_down.extend(
-            self.entities.array_windows::<2>()
-                .map(|[a, b]| a.initiative.0 == b.initiative.0)
-                .flat_map(|bool| [bool, bool])
-        );
-        up_down.push(false);
-        let up_down = up_down.array_chunks::<2>().collect_vec();
+        let up_down = &self.up_down;
+
+        // who each entity belongs to, as the current view should show it, and whether they're still here
+        let owners = self.entities.iter()
+            .map(|entity| entity.owner.as_ref().map(|owner| {
+                match self.entities.iter().find(|e| e.name.0 == *owner) {
+                    Some(e) if e.name.1 && hide_names => (e.censored_name.clone(), true),
+                    Some(_) => (owner.clone(), true),
+                    None => (owner.clone(), false),
+                }
+            }))
+            .collect_vec();
+
+        let companion_choices = self.entities.iter()
+            .map(|owner| companion::choices(&owner.companions, |c| self.entities.iter()
+                .any(|e| e.companion_of == Some((owner.id, c)))))
+            .collect_vec();
+
+        // ┌ │ └ down the side of each held group
+        let brackets = (0..n_entities)
+            .map(|i| {
+                let group = self.entities[i].group?;
+                let before = i != 0 && self.entities[i - 1].group == Some(group);
+                let after = self.entities.get(i + 1).map_or(false, |e| e.group == Some(group));
+                Some(match (before, after) {
+                    (false, _) => "┌",
+                    (true, true) => "│",
+                    (true, false) => "└",
+                })
+            })
+            .collect_vec();
+
+        // reminders go under whoever's turn it is, with the names the players are allowed to see
+        let reminder_names = self.entities.iter()
+            .chain(self.sidelined.iter().map(|(entity, _)| entity))
+            .map(|e| (e.id, if e.name.1 && hide_names { e.censored_name.clone() } else { e.name.0.clone() }))
+            .collect::<HashMap<_, _>>();
+        let reminder_picks = self.entities.iter()
+            .map(|e| reminder::Pick { id: Some(e.id), name: e.name.0.clone() })
+            .collect_vec();
+        let turn_id = turn.checked_rem(n_entities).map(|turn| self.entities[turn].id);
+        let (reminder_menu, mut due_reminders) = self.reminders.view(style, reminder_picks, &reminder_names, turn_id, controls.small_text);
+
+        // what each quick condition is called once it's on someone, to tell if it already is
+        let quick_conditions = self.settings.quick_conditions.iter()
+            .map(|text| Condition::parse(text, round).map_or_else(String::new, |condition| condition.name))
+            .collect_vec();
+        for entity in &mut self.entities {
+            entity.quick_states.resize_with(quick_conditions.len(), Default::default);
+        }
 
+        let tag_legend = &self.settings.tag_legend;
         let (end, start) = self.entities.split_at_mut(turn);
-        let highlight = self.highlight_state.map(|(mut idx, style)| {
-            idx = (idx as isize - turn as isize).wrapping_rem_euclid(n_entities as _) as _;
-            (idx, style)
-        });
+        let highlight = self.highlight_state;
+        let disposition_filter = self.disposition_filter;
+        let swapping = self.swapping;
+        let locked = self.order_locked.value;
+        let order_lock = self.order_locked.button_with(|text| text.size(header_size))
+            .style(style)
+            .padding(0)
+            .on_press(Message::ToggleOrderLock);
+        let now = Instant::now();
 
         let scrollable = start.iter_mut()
             .chain(end.iter_mut())
             .enumerate()
+            // whoever's turn it is is always shown
+            .filter(|(i, entity)| *i == 0 || disposition_filter.shows(entity.disposition))
+            .enumerate()
             .fold(
                 Scrollable::new(&mut self.scroll)
                     .align_items(Align::Center)
@@ -856,40 +3033,56 @@ impl Application for InitiativeManager {
                             .align_items(Align::Center)
                             .spacing(spacing_w as _)
                             .push(Text::new("Name")
-                                .size(17)
+                                .size(header_size)
                                 .width(Length::Units(name_w as _)))
                             .push(Text::new("HP")
-                                .size(17)
+                                .size(header_size)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Units(hp_w as _)))
                             .push(Text::new("Reaction Free")
-                                .size(17)
+                                .size(header_size)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Units(reaction_w as _)))
                             .push(Text::new("Concentrating")
-                                .size(17)
+                                .size(header_size)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Units(conc_w as _)))
                             .tap_if(has_legendary_action, |row| row
                                 .push(Text::new("Legendary Actions ")
-                                    .size(17)
+                                    .size(header_size)
                                     .horizontal_alignment(HorizontalAlignment::Center)
                                     .width(Length::Units(leg_acts_w as _))))
-                            .push(Text::new("Initiative")
-                                .size(17)
-                                .horizontal_alignment(HorizontalAlignment::Center)
-                                .width(Length::Units(initiative_w as u16)))
+                            .push(Row::new()
+                                .align_items(Align::Center)
+                                .width(Length::Units(initiative_w as u16))
+                                .push(Text::new("Initiative")
+                                    .size(header_size))
+                                .push_space(4)
+                                .push(order_lock))
                     )
-                        .padding(INITIATIVES_INTERIOR_PADDING)
-                        .style(style.initiative_table(1))),
-                |col, (i, Entity {
+                        .padding(interior_padding)
+                        .style(style.initiative_table(1, striped))),
+                |col, (row, (i, Entity {
+                    id,
+                    disposition,
+                    disposition_state,
+                    tag,
+                    tag_state,
+                    group,
+                    group_state,
                     name,
-                    // censored_name,
+                    censored_name,
                     remove_state,
                     hp,
+                    max_hp,
+                    ac,
+                    icon,
                     damage,
                     heal,
-                    reaction_free,
+                    attack,
+                    reactions,
+                    reaction_state,
+                    reaction_plus,
                     concentrating,
                     legendary_actions,
                     la_minus,
@@ -897,23 +3090,47 @@ impl Application for InitiativeManager {
                     initiative,
                     init_up,
                     init_down,
-                })| {
+                    roll_off_state,
+                    conditions,
+                    recharges,
+                    new_condition,
+                    quick_states,
+                    staged,
+                    activate_state,
+                    surprised,
+                    surprised_state,
+                    pinned,
+                    sideline_state,
+                    swap_state,
+                    companion_list,
+                    damage_taken,
+                    healing_received,
+                    last_turn,
+                    acted,
+                    acted_state,
+                    dead,
+                    zero_prompt,
+                    zero_choices,
+                    ..
+                }))| {
                     let idx = (i + turn) % n_entities;
                     // let hidden = hidden_toggle.value;
                     // let is_visible = !hidden || dm_view;
-                    let style = style.initiative_table(i);
+                    let style = style.initiative_table(row, striped);
+                    // only whoever's turn it is gets its buttons in the Tab order
+                    let ring = |control| Focusable::new(style, i == 0 && focus == Some(Control::Row(control)));
 
                     // let hide_entity_button = hidden_toggle.button_with(|text| text.size(16))
                     //     .style(style)
                     //     .on_press(Message::ToggleHidden(idx));
                     let name = Button::new(
-                        remove_state, Text::new(if dm_view || !name.1 {
+                        remove_state, Text::new(if !hide_names || !name.1 {
                             name.0.to_string()
                         } else {
-                            // censored_name.clone()
-                            censor_name(&name.0)
-                        }).size(16),
-                    ).style(style)
+                            censored_name.clone()
+                        }).size(text_size)
+                            .tap_if(*staged || *dead, |txt| txt.color(STAGED_COLOR)),
+                    ).style(ring(RowControl::Remove))
                         .padding(0)
                         .width(Length::Fill)
                         .on_press(Message::DeleteEntity(idx));
@@ -923,57 +3140,260 @@ impl Application for InitiativeManager {
                             // .tap_if(!dm_view, |row| row
                             //     .push(hide_entity_button)
                             //     .push_space(5))
+                            .tap_if(dm_view || tag.is_some(), |row| row
+                                .push(tag_button(tag_state, *tag, tag_legend, dm_view.then(|| Message::CycleTag(idx)), style))
+                                .push_space(controls.spacing))
+                            .tap_if_some(brackets[idx], |row, bracket| row
+                                .push(Text::new(bracket).size(text_size))
+                                .push_space(controls.spacing))
+                            .push(Button::new(
+                                disposition_state,
+                                Text::new(Icon::CircleFill).font(ICON_FONT).size(8).color(disposition.color()),
+                            ).style(style)
+                                .padding(controls.button_padding)
+                                .tap_if(dm_view, |btn| btn.on_press(Message::CycleDisposition(idx))))
+                            .push_space(controls.spacing)
+                            .tap_if(dm_view, |row| row
+                                .push(pinned.button_with(|txt| txt.size(controls.small_text))
+                                    .style(ring(RowControl::Pin))
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::TogglePin(idx))
+                                    .tooltip(if pinned.value { "Unpin" } else { "Keep when the encounter is cleared" }, Position::Top)
+                                    .size(controls.small_text))
+                                .push_space(controls.spacing))
+                            .tap_if(*staged, |row| row
+                                .push(Button::new(
+                                    activate_state,
+                                    Text::new(Icon::PlayFill).font(ICON_FONT).size(text_size),
+                                ).style(style)
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::Activate(idx)))
+                                .push_space(controls.spacing))
+                            .tap_if_some(icon.as_ref(), |row, icon| row
+                                .push(icon.text(text_size))
+                                .push_space(controls.spacing))
                             .push(name))
                         .align_x(Align::Start)
                         .style(style);
+                    let show_conditions = !hide_conditions || *disposition != Disposition::Enemy;
+                    let has_quick = quick_conditions.iter()
+                        .map(|quick| conditions.iter().any(|c| c.name.eq_ignore_ascii_case(quick)))
+                        .collect_vec();
+                    let effects = conditions.iter_mut()
+                        .enumerate()
+                        .filter(|_| show_conditions)
+                        .fold(Row::new().align_items(Align::Center).spacing(3), |row, (c, condition)| {
+                            let text = match condition.rounds {
+                                Some(rounds) => format!("{} ({rounds})", condition.name),
+                                None => condition.name.clone(),
+                            };
+                            row.push(Button::new(&mut condition.remove_state, Text::new(text).size(controls.small_text))
+                                .style(style)
+                                .padding(controls.button_padding)
+                                .tap_if(dm_view, |btn| btn.on_press(Message::RemoveCondition(idx, c))))
+                        });
+                    let effects = recharges.iter_mut()
+                        .enumerate()
+                        .filter(|_| show_conditions)
+                        .fold(effects, |row, (r, recharge)| {
+                            let text = format!("{} {}", recharge.name, if recharge.ready { "✓" } else { "✗" });
+                            row.push(Button::new(&mut recharge.use_state, Text::new(text).size(controls.small_text))
+                                .style(style)
+                                .padding(controls.button_padding)
+                                .tap_if(dm_view, |btn| btn.on_press(Message::UseRecharge(idx, r))))
+                        })
+                        .tap_if(*dead, |row| row
+                            .push(Text::new("dead").size(controls.small_text).color(STAGED_COLOR)))
+                        .tap_if(dm_view && *zero_prompt, |row| {
+                            let [remove, keep, at_one] = zero_choices;
+                            row.push(Text::new("0 HP:").size(controls.small_text))
+                                .push(Button::new(remove, Text::new("remove").size(controls.small_text))
+                                    .style(style)
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::AtZero(idx, ZeroChoice::Remove)))
+                                .push(Button::new(keep, Text::new("keep as dead").size(controls.small_text))
+                                    .style(style)
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::AtZero(idx, ZeroChoice::KeepDead)))
+                                .push(Button::new(at_one, Text::new("it's at 1").size(controls.small_text))
+                                    .style(style)
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::AtZero(idx, ZeroChoice::AtOne)))
+                        })
+                        .tap_if(dm_view && !locked && (group.is_some() || (idx != 0 && i != 0)), |row| row
+                            .push(Button::new(
+                                group_state,
+                                Text::new(if group.is_some() { "leave group" } else { "hold with ↑" }).size(controls.small_text),
+                            ).style(style)
+                                .padding(controls.button_padding)
+                                .on_press(if group.is_some() { Message::LeaveGroup(idx) } else { Message::JoinGroup(idx) })))
+                        .tap_if(dm_view, |row| row
+                            .push(Button::new(sideline_state, Text::new("sideline").size(controls.small_text))
+                                .style(ring(RowControl::Sideline))
+                                .padding(controls.button_padding)
+                                .on_press(Message::Sideline(idx))))
+                        .tap_if(dm_view && !locked, |row| row
+                            .push(Button::new(swap_state, Text::new(match swapping {
+                                None => "swap init",
+                                Some(swapping) if swapping == *id => "cancel swap",
+                                Some(_) => "swap with this",
+                            }).size(controls.small_text))
+                                .style(style)
+                                .padding(controls.button_padding)
+                                .on_press(Message::SwapInitiative(idx))))
+                        .tap_if(dm_view && !companion_choices[idx].is_empty(), |row| row
+                            .push(PickList::new(
+                                companion_list,
+                                companion_choices[idx].clone(),
+                                Some(String::from("companions")),
+                                move |choice| Message::ToggleCompanion(idx, choice),
+                            ).style(style)
+                                .text_size(controls.small_text)
+                                .padding(controls.button_padding)))
+                        .tap_if_some(owners[idx].as_ref(), |row, (owner, present)| row
+                            .push(Text::new(format!("↳ {owner}"))
+                                .size(controls.small_text)
+                                .tap_if(!*present, |txt| txt.color(STAGED_COLOR))))
+                        .tap_if_some((*last_turn).filter(|_| dm_view), |row, last_turn| row
+                            .push(Text::new(time_since(now.saturating_duration_since(last_turn)))
+                                .size(controls.small_text)
+                                .color(STAGED_COLOR)))
+                        .tap_if(round == 1 && *surprised, |row| row
+                            .push(Button::new(surprised_state, Text::new("surprised").size(controls.small_text).color(STAGED_COLOR))
+                                .style(style)
+                                .padding(controls.button_padding)
+                                .tap_if(dm_view, |btn| btn.on_press(Message::Unsurprise(idx)))))
+                        // already on them, it's removed like any other condition
+                        .tap_if(dm_view, |row| quick_states.iter_mut()
+                            .zip(&quick_conditions)
+                            .enumerate()
+                            .filter(|&(q, _)| !has_quick[q])
+                            .fold(row, |row, (q, (state, quick))| row
+                                .push(Button::new(state, Text::new(format!("+{quick}")).size(controls.small_text))
+                                    .style(style)
+                                    .padding(controls.button_padding)
+                                    .on_press(Message::QuickCondition(idx, q)))))
+                        .tap_if(dm_view, |row| row.push(new_condition.text_input(
+                            "condition",
+                            move |s| Message::EditCondition(idx, s),
+                        ).style(style)
+                            .size(controls.mod_text)
+                            .padding(controls.mod_padding)
+                            .width(Length::Units(controls.mod_width * 2))
+                            .on_submit(Message::AddCondition(idx))));
+                    let name = Column::new()
+                        .push(name)
+                        .push(effects)
+                        .tap_if_some(if i == 0 { due_reminders.take() } else { None }, |col, due| col.push(due));
 
-                    let hp = Text::new(if dm_view || !hp.1 {
+                    let hp_visible = !hide_hp || !hp.1;
+                    let hp = Text::new(if !hp_visible {
+                        "??".to_string()
+                    } else if hp.0 == *max_hp {
                         hp.0.to_string()
                     } else {
-                        "??".to_string()
-                    }).horizontal_alignment(HorizontalAlignment::Right)
-                        .size(16);
+                        format!("{}/{}", hp.0, max_hp)
+                    }).horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Shrink)
+                        .size(text_size);
+                    // running totals say as much about hidden HP as the HP itself
+                    let hp_tooltip = [
+                        ac.map(|ac| format!("AC {}", ac)),
+                        (*damage_taken != 0).then(|| format!("{damage_taken} taken")),
+                        (*healing_received != 0).then(|| format!("{healing_received} healed")),
+                    ].into_iter()
+                        .flatten()
+                        .join(", ");
+                    let hp: Element<_> = if hp_visible && !hp_tooltip.is_empty() {
+                        hp.tooltip(hp_tooltip, Position::Top)
+                            .size(12)
+                            .into()
+                    } else {
+                        hp.into()
+                    };
                     let damage = damage.text_input(
                         "damage",
                         move |s| Message::EditDamage(idx, s),
                     ).style(style)
-                        .size(9)
-                        .width(Length::Units(HP_MOD_WIDTH))
+                        .size(controls.mod_text)
+                        .padding(controls.mod_padding)
+                        .width(Length::Units(controls.mod_width))
                         .on_submit(Message::Damage(idx));
                     let heal = heal.text_input(
                         "heal",
                         move |s| Message::EditHealing(idx, s),
                     ).style(style)
-                        .size(9)
-                        .width(Length::Units(HP_MOD_WIDTH))
+                        .size(controls.mod_text)
+                        .padding(controls.mod_padding)
+                        .width(Length::Units(controls.mod_width))
                         .on_submit(Message::Heal(idx));
+                    let resolve = match attack.content.parse() {
+                        Ok(AttackRoll::Total(total)) => Some(Message::ResolveAttack(idx, total)),
+                        Ok(AttackRoll::Natural(roll)) => Some(Message::NaturalAttack(idx, roll)),
+                        Err(()) => None,
+                    };
+                    let attack = attack.text_input(
+                        "attack",
+                        move |s| Message::EditAttack(idx, s),
+                    ).style(style)
+                        .size(controls.mod_text)
+                        .padding(controls.mod_padding)
+                        .width(Length::Units(controls.mod_width))
+                        .tap_if_some(resolve, TextInput::on_submit);
                     let hp_mods = Column::new()
                         .align_items(Align::Start)
                         .push(damage)
-                        .push(heal);
+                        .push(heal)
+                        .tap_if(ac.is_some(), |col| col.push(attack));
                     let hp = Container::new(
                         Row::new()
                             .align_items(Align::Center)
-                            .push(hp
-                                .horizontal_alignment(HorizontalAlignment::Center)
-                                .width(Length::Shrink))
+                            .push(hp)
                             .tap_if(dm_view, |row| row
-                                .push_space(CONTROL_SPACING)
+                                .push_space(controls.spacing)
                                 .push(hp_mods.width(Length::Shrink)))
                     )
                         .style(style)
                         .align_x(Align::Center);
 
-                    let reaction = reaction_free.button()
-                        .style(style)
+                    let (reactions_left, max_reactions) = *reactions;
+                    let reaction_style = style::Reaction { style, available: reactions_left != 0 };
+                    let reaction = if max_reactions == 1 {
+                        Text::new(ToggleButtonState::DEFAULT_STATES[usize::from(reactions_left != 0)])
+                            .font(ICON_FONT)
+                    } else {
+                        Text::new(format!("{reactions_left}/{max_reactions}"))
+                            .size(text_size)
+                    }.horizontal_alignment(HorizontalAlignment::Center);
+                    let reaction = Button::new(reaction_state, reaction)
+                        .style(Focusable::new(
+                            reaction_style,
+                            i == 0 && focus == Some(Control::Row(RowControl::Reaction)),
+                        ))
                         .on_press(Message::Reaction(idx));
+                    let reaction: Element<_> = if max_reactions == 1 {
+                        reaction.into()
+                    } else {
+                        Row::new()
+                            .spacing(2)
+                            .align_items(Align::Center)
+                            .push(reaction)
+                            .push(Button::new(reaction_plus, Text::new(" + ").size(text_size))
+                                .padding(controls.button_padding)
+                                .style(style)
+                                .tap_if(reactions_left != max_reactions, |btn| btn.on_press(Message::ReactionPlus(idx))))
+                            .into()
+                    };
+                    let reaction = Container::new(reaction)
+                        .style(style)
+                        .align_x(Align::Center);
 
                     let conc = concentrating.button_with(|txt| {
                         let mut cont = Container::new(txt)
                             .align_x(Align::Center)
                             .style(style);
                         match highlight {
-                            Some((idx, style)) if idx == i => {
+                            Some((highlight_id, style)) if highlight_id == *id => {
                                 struct ContainerStyle(container::Style);
                                 impl container::StyleSheet for ContainerStyle {
                                     fn style(&self) -> container::Style {
@@ -986,19 +3406,19 @@ impl Application for InitiativeManager {
                         };
                         cont
                     })
-                        .style(style)
+                        .style(ring(RowControl::Concentrate))
                         .on_press(Message::Concentrate(idx));
 
                     let legendary_actions = if let Some(Hidden((tot, left), _)) = legendary_actions {
-                        let mut minus = Button::new(la_minus, Text::new(" - ").size(16))
-                            .padding(0)
-                            .style(style);
+                        let mut minus = Button::new(la_minus, Text::new(" - ").size(text_size))
+                            .padding(controls.button_padding)
+                            .style(ring(RowControl::LegendaryMinus));
                         if *left != 0 {
                             minus = minus.on_press(Message::LegActionMinus(idx));
                         }
-                        let mut plus = Button::new(la_plus, Text::new(" + ").size(16))
-                            .padding(0)
-                            .style(style);
+                        let mut plus = Button::new(la_plus, Text::new(" + ").size(text_size))
+                            .padding(controls.button_padding)
+                            .style(ring(RowControl::LegendaryPlus));
                         if *left != *tot {
                             plus = plus.on_press(Message::LegActionPlus(idx));
                         }
@@ -1006,7 +3426,7 @@ impl Application for InitiativeManager {
                             .spacing(2)
                             .align_items(Align::Center)
                             .push(minus)
-                            .push(Text::new(roman::to(*left as _).unwrap_or_else(String::new)).size(16))
+                            .push(Text::new(legendary_display.text(*left, *tot)).size(text_size))
                             .push(plus)
                     } else {
                         Row::new()
@@ -1015,36 +3435,37 @@ impl Application for InitiativeManager {
                         .style(style)
                         .align_x(Align::Center);
 
-                    let &[move_up, move_down] = up_down[idx];
+                    let [move_up, move_down] = up_down.get(idx).copied().unwrap_or_default()
+                        .map(|can_move| can_move && !locked);
                     // let initiative = Text::new(format!("{} ({})", initiative, tiebreaker));
                     let initiative = Text::new(initiative.0.to_string())
-                        .size(16)
+                        .size(text_size)
                         .horizontal_alignment(HorizontalAlignment::Left);
                     let mut up = Button::new(
                         init_up,
                         if move_up {
-                            Text::new(Icon::ArrowUp).font(ICON_FONT).size(8)
+                            Text::new(Icon::ArrowUp).font(ICON_FONT).size(controls.arrow_size)
                                 .horizontal_alignment(HorizontalAlignment::Left)
                         } else {
-                            Text::new(" ").size(8)
+                            Text::new(" ").size(controls.arrow_size)
                                 .horizontal_alignment(HorizontalAlignment::Left)
                         },
                     ).style(style)
-                        .padding(0);
+                        .padding(controls.button_padding);
                     if move_up {
                         up = up.on_press(Message::MoveUp(idx));
                     }
                     let mut down = Button::new(
                         init_down,
                         if move_down {
-                            Text::new(Icon::ArrowDown).font(ICON_FONT).size(8)
+                            Text::new(Icon::ArrowDown).font(ICON_FONT).size(controls.arrow_size)
                                 .horizontal_alignment(HorizontalAlignment::Left)
                         } else {
-                            Text::new(" ").size(8)
+                            Text::new(" ").size(controls.arrow_size)
                                 .horizontal_alignment(HorizontalAlignment::Left)
                         },
                     ).style(style)
-                        .padding(0);
+                        .padding(controls.button_padding);
                     if move_down {
                         down = down.on_press(Message::MoveDown(idx));
                     }
@@ -1053,13 +3474,35 @@ impl Application for InitiativeManager {
                         .push_space(5)
                         .push(down)
                         .align_items(Align::Start);
+                    // on the first of each tie
+                    let roll_off = dm_view && move_down && !move_up;
+                    let init_mods = Row::new()
+                        .align_items(Align::Center)
+                        .push(init_mods)
+                        .tap_if(roll_off, |row| row
+                            .push_space(controls.spacing)
+                            .push(Button::new(roll_off_state, Text::new("roll off").size(controls.small_text))
+                                .style(style)
+                                .padding(controls.button_padding)
+                                .on_press(Message::RollOff(idx))));
                     let initiative = Container::new(
                         Row::new()
                             .push(initiative
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Shrink))
-                            .push_space(CONTROL_SPACING)
+                            .push_space(controls.spacing)
                             .push(init_mods.width(Length::Shrink))
+                            .push_space(controls.spacing)
+                            .push(Button::new(
+                                acted_state,
+                                Text::new(if *acted { Icon::Check } else { Icon::Dot })
+                                    .font(ICON_FONT)
+                                    .size(controls.arrow_size)
+                                    .color(STAGED_COLOR)
+                                    .tap_if(!*acted && !dm_view, |txt| txt.color(Color::TRANSPARENT)),
+                            ).style(style)
+                                .padding(controls.button_padding)
+                                .tap_if(dm_view, |btn| btn.on_press(Message::ToggleActed(idx))))
                     )
                         .style(style)
                         .align_x(Align::Center);
@@ -1071,7 +3514,7 @@ impl Application for InitiativeManager {
                                 .width(Length::Units(name_w as _)))
                             .push_space(Length::Units(spacing_w as _))
                             .push(hp
-                                .width(Length::Units(hp_w as u16 + CONTROL_SPACING)))
+                                .width(Length::Units(hp_w as u16 + controls.spacing)))
                             .push_space(Length::Units(spacing_w as _))
                             .push(reaction
                                 .width(Length::Units(reaction_w as _)))
@@ -1084,54 +3527,152 @@ impl Application for InitiativeManager {
                                     .width(Length::Units(leg_acts_w as _))))
                             .push_space(Length::Units(spacing_w as _))
                             .push(initiative
-                                .width(Length::Units(initiative_w as u16 + CONTROL_SPACING)))
+                                .width(Length::Units(initiative_w as u16 + controls.spacing)))
                     )
-                        .padding(INITIATIVES_INTERIOR_PADDING)
+                        .padding(interior_padding)
                         .style(style))
                 });
 
-        let initiatives = Container::new(
-            Container::new(scrollable)
+        let key_color = self.key_color();
+        let border_style: Box<dyn container::StyleSheet> = match key_color {
+            Some(color) => style::Keyed { style, color }.into(),
+            None => style.initiative_table_border().into(),
+        };
+        let [enemy_chip, ally_chip] = &mut self.filter_chips;
+        let filter_chips = [(enemy_chip, Disposition::Enemy, "enemies"), (ally_chip, Disposition::Ally, "allies")]
+            .into_iter()
+            .fold(Row::new().spacing(6).align_items(Align::Center), |row, (state, disposition, label)| {
+                let on = disposition_filter.0 == Some(disposition);
+                row.push(Button::new(state, Text::new(label).size(12).tap_if(on, |txt| txt.color(disposition.color())))
+                    .style(style)
+                    .padding(2)
+                    .on_press(Message::FilterDisposition(disposition::Filter(if on { None } else { Some(disposition) }))))
+            });
+        let initiatives = Container::new(Column::new()
+            .align_items(Align::Center)
+            // not over the key color, that's just the rows
+            .tap_if(!self.settings.streaming && (dm_view || disposition_filter.0.is_some()), |col| col
+                .push(filter_chips)
+                .push_space(4))
+            .tap_if_some(called_initiative, |col, initiative| col
+                .push(Text::new(format!("Initiative {initiative}")).size(CALLER_TEXT_SIZE))
+                .push_space(6))
+            .tap_if(lair_banner, |col| col
+                .push(Button::new(&mut self.lair_dismiss, Text::new(format!("Lair actions: {} (click or Esc to dismiss)", self.lair_actions)).size(16))
+                    .style(style)
+                    .on_press(Message::DismissLair))
+                .push_space(6))
+            .push(Container::new(scrollable)
                 .padding(INITIATIVES_BORDER_PADDING)
-                .style(style.initiative_table_border())
-                .center_x()
+                .style(border_style)
+                .center_x())
         ).padding(INITIATIVES_PADDING)
             .center_x();
+        // only the rows themselves are drawn over the key color, everything else is hidden
+        if let Some(color) = key_color {
+            return Container::new(initiatives)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x()
+                .align_y(Align::Start)
+                .style(style::Keyed { style, color })
+                .into();
+        }
+
+        let n_sidelined = self.sidelined.len();
+        let sidelined_open = self.sidelined_open;
+        let sidelined = Column::new()
+            .align_items(Align::Center)
+            .push(Button::new(
+                &mut self.sidelined_toggle,
+                Text::new(format!("Out of combat ({n_sidelined}) {}", if sidelined_open { "▾" } else { "▸" })).size(14),
+            ).style(style)
+                .on_press(Message::ToggleSidelined))
+            .tap_if(sidelined_open, |col| self.sidelined.iter_mut()
+                .enumerate()
+                .fold(col, |col, (i, (entity, return_state))| col
+                    .push_space(4)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(Text::new(format!("{} ({}/{})", entity.name.0, entity.hp.0, entity.max_hp)).size(14))
+                        .push_space(8)
+                        .push(Button::new(return_state, Text::new("return").size(12))
+                            .style(style)
+                            .on_press(Message::ReturnSidelined(i))))));
+        let round_history = Column::new()
+            .align_items(Align::Center)
+            .push(Button::new(
+                &mut self.round_history_toggle,
+                Text::new(format!("Round history {}", if round_history_open { "▾" } else { "▸" })).size(14),
+            ).style(style)
+                .on_press(Message::ToggleRoundHistory))
+            .tap_if(round_history_open, |col| round_history_lines.into_iter()
+                .fold(col, |col, line| col
+                    .push_space(4)
+                    .push(Text::new(line).size(14))));
+
+        let notes_open = self.notes_open;
+        let notes = Column::new()
+            .align_items(Align::Center)
+            .push(Button::new(
+                &mut self.notes_toggle,
+                Text::new(format!("Encounter notes {}", if notes_open { "▾" } else { "▸" })).size(14),
+            ).style(style)
+                .on_press(Message::ToggleNotes))
+            .tap_if(notes_open, |col| col
+                .push_space(4)
+                .push(Text::new(self.encounter_notes.as_str()).size(14)));
+
+        let initiatives = Column::new()
+            .align_items(Align::Center)
+            .push(initiatives)
+            .tap_if(dm_view && n_sidelined != 0, |col| col.push(sidelined))
+            .push_space(6)
+            .push(round_history)
+            .tap_if(dm_view && !self.encounter_notes.is_empty(), |col| col
+                .push_space(6)
+                .push(notes));
 
         let next = Button::new(
             &mut self.next_turn,
             Text::new("Next Turn"),
-        ).style(style)
+        ).style(Focusable::new(style, focus == Some(Control::NextTurn)))
             .on_press(Message::NextTurn);
 
         let prev = Button::new(
             &mut self.prev_turn,
             Text::new("Previous Turn"),
-        ).style(style)
+        ).style(Focusable::new(style, focus == Some(Control::PrevTurn)))
             .on_press(Message::PrevTurn);
 
         let next_btns = Row::new()
+            .align_items(Align::Center)
             .push_space(Length::FillPortion(2))
             .push(next)
             .push_space(Length::Fill)
+            .push(Text::new(format!("Round {}", round)))
+            .push_space(Length::Fill)
             .push(prev)
             .push_space(Length::FillPortion(2));
 
-        let new_ready = {
-            let hp_empty = self.new_entity.hp.0.content.is_empty();
-            let hp_parses = self.new_entity.hp.0.content.parse::<Hp>()
-                .ok()
-                .and_then(|hp| hp.into_number())
-                .is_some();
-            let hp_ready = hp_empty || hp_parses;
-            let name_ready = !self.new_entity.name.0.content.is_empty();
-            hp_ready && name_ready
+        let new_errors = self.new_entity.errors();
+        let new_ready = !new_errors.any();
+        let new_errors = NewEntityErrors {
+            name: new_errors.name.filter(|_| self.new_entity.started()),
+            ..new_errors
         };
+        fn with_error<'a>(field: impl Into<Element<'a, Message>>, error: Option<&'static str>) -> Column<'a, Message> {
+            Column::new()
+                .push(field)
+                .tap_if_some(error, |col, error| col
+                    .push_space(2)
+                    .push(Text::new(error).size(12).color(style::INVALID)))
+        }
 
         let submit_new_button = Button::new(
             &mut self.new_entity_submit,
             Text::new("Submit"),
-        ).style(style)
+        ).style(Focusable::new(style, focus == Some(Control::SubmitNewEntity)))
             .tap_if(new_ready,
                     |btn| btn.on_press(Message::NewEntitySubmit));
 
@@ -1140,7 +3681,7 @@ impl Application for InitiativeManager {
         let new_name = self.new_entity.name.0.text_input(
             "Name",
             Message::NewName,
-        ).style(style)
+        ).style(Invalid::new(style, new_errors.name.is_some()))
             .tap_if(new_ready,
                     |txt| txt.on_submit(Message::NewEntitySubmit));
         let hide = Checkbox::new(
@@ -1148,16 +3689,17 @@ impl Application for InitiativeManager {
             "Hide?",
             hide_msg(HideablePart::Name),
         ).style(style);
-        let new_name = Row::new()
+        let new_name = with_error(Row::new()
             .push(new_name.width(Length::FillPortion(2)))
             .push_space(Length::Fill)
-            .push(hide);
+            .push(hide), new_errors.name);
 
         // should display a d20 somehow if you put like +3 (it'll roll)
+        let init_hint = self.init_hint();
         let new_init = self.new_entity.init.0.text_input(
             "init or ±mod",
             Message::NewInit,
-        ).style(style)
+        ).style(Invalid::new(style, new_errors.init.is_some()))
             .tap_if(new_ready,
                     |txt| txt.on_submit(Message::NewEntitySubmit));
         let hide = Checkbox::new(
@@ -1169,11 +3711,15 @@ impl Application for InitiativeManager {
             .push(new_init.width(Length::FillPortion(2)))
             .push_space(Length::Fill)
             .push(hide);
+        let new_init = with_error(new_init, new_errors.init)
+            .tap_if_some(init_hint.filter(|_| new_errors.init.is_none()), |col, hint| col
+                .push_space(2)
+                .push(Text::new(hint).size(12).color(STAGED_COLOR)));
 
         let new_hp = self.new_entity.hp.0.text_input(
             "hp",
             Message::NewHp,
-        ).style(style)
+        ).style(Invalid::new(style, new_errors.hp.is_some()))
             .tap_if(new_ready,
                     |txt| txt.on_submit(Message::NewEntitySubmit));
         let hide = Checkbox::new(
@@ -1181,15 +3727,15 @@ impl Application for InitiativeManager {
             "Hide?",
             hide_msg(HideablePart::Hp),
         ).style(style);
-        let new_hp = Row::new()
+        let new_hp = with_error(Row::new()
             .push(new_hp.width(Length::FillPortion(2)))
             .push_space(Length::Fill)
-            .push(hide);
+            .push(hide), new_errors.hp);
 
         let new_las = self.new_entity.leg_acts.0.text_input(
             "# of legendary actions",
             Message::NewLas,
-        ).style(style)
+        ).style(Invalid::new(style, new_errors.leg_acts.is_some()))
             .tap_if(new_ready,
                     |txt| txt.on_submit(Message::NewEntitySubmit));
         let hide = Checkbox::new(
@@ -1197,10 +3743,100 @@ impl Application for InitiativeManager {
             "Hide?",
             hide_msg(HideablePart::LegActs),
         ).style(style);
-        let new_las = Row::new()
+        let new_las = with_error(Row::new()
             .push(new_las.width(Length::FillPortion(2)))
             .push_space(Length::Fill)
-            .push(hide);
+            .push(hide), new_errors.leg_acts);
+
+        let new_reactions = self.new_entity.reactions.text_input(
+            "# of reactions (1)",
+            Message::NewReactions,
+        ).style(Invalid::new(style, new_errors.reactions.is_some()))
+            .tap_if(new_ready,
+                    |txt| txt.on_submit(Message::NewEntitySubmit));
+        let new_reactions = with_error(Row::new()
+            .push(new_reactions.width(Length::FillPortion(2)))
+            .push_space(Length::Fill), new_errors.reactions);
+
+        let new_count = self.new_entity.count.text_input(
+            "How many (1)",
+            Message::NewCount,
+        ).style(Invalid::new(style, new_errors.count.is_some()))
+            .tap_if(new_ready,
+                    |txt| txt.on_submit(Message::NewEntitySubmit));
+        let single_roll = Checkbox::new(
+            self.new_entity.single_roll,
+            "One initiative roll for all",
+            Message::NewSingleRoll,
+        ).style(style);
+        let new_count = with_error(Row::new()
+            .align_items(Align::Center)
+            .push(new_count.width(Length::FillPortion(2)))
+            .push_space(Length::Fill)
+            .push(single_roll), new_errors.count);
+
+        let new_staged = Checkbox::new(
+            self.new_entity.staged,
+            "Staged? (skipped until activated)",
+            Message::NewStaged,
+        ).style(style);
+        let new_surprised = Checkbox::new(
+            self.new_entity.surprised,
+            "Surprised? (skips their turn in round 1)",
+            Message::NewSurprised,
+        ).style(style);
+
+        let new_disposition = PickList::new(
+            &mut self.new_entity.disposition_list,
+            Disposition::ALL.to_vec(),
+            Some(self.new_entity.disposition),
+            Message::NewDisposition,
+        ).style(Focusable::new(style, focus == Some(Control::NewDisposition)))
+            .text_size(14);
+
+        let new_icon = PickList::new(
+            &mut self.new_entity.icon_list,
+            Glyph::ALL.to_vec(),
+            self.new_entity.icon,
+            Message::NewIcon,
+        ).style(style)
+            .text_size(14);
+        let new_icon_path = self.new_entity.icon_path.text_input(
+            "or image path",
+            Message::NewIconPath,
+        ).style(style);
+        let new_owner = self.new_entity.owner.text_input(
+            "Owner, for summons & companions",
+            Message::NewOwner,
+        ).style(style)
+            .tap_if(new_ready,
+                    |txt| txt.on_submit(Message::NewEntitySubmit));
+
+        let new_icon = Row::new()
+            .align_items(Align::Center)
+            .push(Text::new("Icon"))
+            .push_space(8)
+            .push(new_icon)
+            .push_space(8)
+            .push(new_icon_path);
+
+        let preset_matches = srd::search(&self.preset_search.content);
+        let preset_search = self.preset_search.text_input(
+            "Search SRD monsters",
+            Message::PresetSearch,
+        ).style(style);
+        let presets = PickList::new(
+            &mut self.preset_list,
+            preset_matches,
+            Some(String::from("Add Monster")),
+            Message::AddPreset,
+        ).style(style)
+            .text_size(14);
+        let presets = Row::new()
+            .align_items(Align::Center)
+            .push(preset_search.width(Length::FillPortion(2)))
+            .push_space(Length::Fill)
+            .push(presets);
 
         let save_encounter = Button::new(
             &mut self.save_encounter,
@@ -1212,7 +3848,29 @@ impl Application for InitiativeManager {
         let encounters = fs::read_dir(&*ENCOUNTER_DIR).unwrap()
             .flatten()
             .filter(|entry| entry.file_type().ok().filter(FileType::is_file).is_some())
-            .map(|entry| entry.path().file_stem().unwrap().to_string_lossy().into_owned())
+            .map(|entry| {
+                let name = entry.path().file_stem().unwrap().to_string_lossy().into_owned();
+                let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+                (name, modified, entry.path())
+            })
+            .collect_vec();
+        let checks = &mut self.encounter_checks;
+        checks.retain(|name, _| encounters.iter().any(|(n, ..)| n == name));
+        let loadable = encounters.iter()
+            .map(|(name, modified, path)| {
+                let (checked, readable) = checks.entry(name.clone())
+                    .or_insert((None, false));
+                if checked.is_none() || *checked != *modified {
+                    *checked = *modified;
+                    *readable = fs::File::open(path).ok()
+                        .map_or(false, |file| EncounterFile::from_reader(file).is_ok());
+                }
+                if *readable { name.clone() } else { format!("{UNREADABLE}{name}") }
+            })
+            .collect_vec();
+        // the unreadable ones can still be deleted
+        let encounters = encounters.into_iter()
+            .map(|(name, ..)| name)
             .collect_vec();
         // println!("read encounters = {:?}", start.elapsed());
 
@@ -1226,12 +3884,167 @@ impl Application for InitiativeManager {
 
         let load_encounter = PickList::new(
             &mut self.load_encounter,
-            encounters,
+            loadable,
             Some(String::from("Load Encounter")),
             Message::LoadEncounter,
         ).style(style)
             .text_size(14);
 
+        let clear_encounter = Button::new(
+            &mut self.clear_encounter,
+            Text::new("Clear Encounter").size(14),
+        ).style(style)
+            .tap_if(n_entities != 0, |btn| btn.on_press(Message::ClearEncounter));
+        let clear_encounter = Row::new()
+            .align_items(Align::Center)
+            .push(clear_encounter)
+            .tap_if(self.recap.is_some(), |row| row
+                .push_space(8)
+                .push(Button::new(&mut self.save_recap, Text::new("Save recap").size(14))
+                    .style(style)
+                    .on_press(Message::SaveRecap))
+                .push_space(4)
+                .push(Button::new(&mut self.dismiss_recap, Text::new("Dismiss").size(14))
+                    .style(style)
+                    .on_press(Message::DismissRecap)));
+
+        let tag_legend = Row::new()
+            .align_items(Align::Center)
+            .push(Text::new(Icon::DiamondFill)
+                .font(ICON_FONT)
+                .size(14)
+                .color(self.tag_legend_color.color(style)))
+            .push_space(6)
+            .push(PickList::new(
+                &mut self.tag_legend_list,
+                TagColor::ALL.to_vec(),
+                Some(self.tag_legend_color),
+                Message::TagLegendColor,
+            ).style(style)
+                .text_size(14))
+            .push_space(8)
+            .push(self.tag_legend.text_input("What this tag means tonight", Message::TagLegendMeaning)
+                .style(style)
+                .size(14));
+
+        let webhook = Row::new()
+            .align_items(Align::Center)
+            .push(self.webhook_url.text_input("Discord webhook url", Message::WebhookUrl)
+                .style(style)
+                .size(14)
+                .password())
+            .push_space(8)
+            .push(Checkbox::new(
+                self.settings.announce,
+                "Announce turns",
+                Message::ToggleAnnounce,
+            ).style(style)
+                .size(16)
+                .text_size(14));
+
+        let hook = Row::new()
+            .align_items(Align::Center)
+            .push(self.hook_command.text_input("Command on each turn, ex. lights.sh {event} {round} {name}", Message::HookCommand)
+                .style(style)
+                .size(14))
+            .push_space(8)
+            .push(Checkbox::new(
+                self.settings.hook,
+                "Run it (runs anything!)",
+                Message::ToggleHook,
+            ).style(style)
+                .size(16)
+                .text_size(14));
+
+        let summary = Row::new()
+            .align_items(Align::Center)
+            .push(self.summary_url.text_input("Post combat summaries to (optional)", Message::SummaryUrl)
+                .style(style)
+                .size(14))
+            .push_space(8)
+            .push(Checkbox::new(
+                self.settings.history,
+                "Save combat history",
+                Message::ToggleHistory,
+            ).style(style)
+                .size(16)
+                .text_size(14));
+
+        let overlay = Column::new()
+            .push(Row::new()
+                .align_items(Align::Center)
+                .push(self.overlay_path.text_input("OBS overlay file", Message::OverlayPath)
+                    .style(style)
+                    .size(14))
+                .push_space(8)
+                .push(Checkbox::new(
+                    self.settings.overlay,
+                    "Write overlay",
+                    Message::ToggleOverlay,
+                ).style(style)
+                    .size(16)
+                    .text_size(14)))
+            .push_space(4)
+            .push(Row::new()
+                .push(self.overlay_template.text_input(overlay::DEFAULT_TEMPLATE, Message::OverlayTemplate)
+                    .style(style)
+                    .size(14))
+                .push_space(8)
+                .push(self.overlay_next_path.text_input("Up next file (optional)", Message::OverlayNextPath)
+                    .style(style)
+                    .size(14)))
+            .push_space(4)
+            .push(Row::new()
+                .align_items(Align::Center)
+                .push(Checkbox::new(
+                    self.settings.streaming,
+                    "Streaming mode (F12 to leave)",
+                    Message::ToggleStreaming,
+                ).style(style)
+                    .size(16)
+                    .text_size(14))
+                .push_space(8)
+                .push(self.key_color.text_input(settings::DEFAULT_KEY_COLOR, Message::KeyColor)
+                    .style(style)
+                    .size(14)
+                    .width(Length::Units(90))));
+
+        let report_label = match &self.report_overwrite {
+            Some(_) => "Replace session report",
+            None => "Export session report",
+        };
+        let session_report = Row::new()
+            .align_items(Align::Center)
+            .push(self.report_dir.text_input("Report folder", Message::ReportDir)
+                .style(style)
+                .size(14))
+            .push_space(8)
+            .push(self.report_file_name.text_input(report::DEFAULT_FILE_NAME, Message::ReportFileName)
+                .style(style)
+                .size(14))
+            .push_space(8)
+            .push(Button::new(
+                &mut self.report_button,
+                Text::new(report_label).size(14),
+            ).style(style)
+                .on_press(Message::ExportReport));
+
+        let roll20 = Row::new()
+            .align_items(Align::Center)
+            .push(Button::new(
+                &mut self.roll20_button,
+                Text::new("Copy Roll20 turn order").size(14),
+            ).style(style)
+                .on_press(Message::CopyRoll20))
+            .push_space(8)
+            .push(Checkbox::new(
+                self.roll20_skip_hidden,
+                "Skip hidden",
+                Message::Roll20SkipHidden,
+            ).style(style)
+                .size(16)
+                .text_size(14));
+
         let save_party = Button::new(
             &mut self.save_party,
             Text::new("Save Players").size(14),
@@ -1257,49 +4070,141 @@ impl Application for InitiativeManager {
 
         let load_party = PickList::new(
             &mut self.load_party,
-            parties,
+            std::iter::once(String::from(ASSEMBLE_PARTY)).chain(parties).collect(),
             Some(String::from("Load Players")),
-            Message::LoadParty,
+            |name| if name == ASSEMBLE_PARTY { Message::AssembleParty } else { Message::LoadParty(name) },
         ).style(style)
             .text_size(14);
 
+        let filter = PickList::new(
+            &mut self.filter_list,
+            disposition::Filter::ALL.to_vec(),
+            Some(self.disposition_filter),
+            Message::FilterDisposition,
+        ).style(Focusable::new(style, focus == Some(Control::Filter)))
+            .text_size(14);
+
+        // short windows (ex. 768px laptops) get half the space between controls, and can scroll the rest
+        let short = self.height < SHORT_WINDOW;
+        let gap = |px: u16| if short { px / 2 } else { px };
         let new_entity_col = Container::new(
             Column::new()
                 .push(next_btns)
-                .push_space(10)
-                .push_rule(20)
+                .push_space(gap(6))
+                .push(Row::new()
+                    .push_space(Length::Fill)
+                    .push(filter)
+                    .push_space(Length::Fill))
+                .push_space(gap(6))
+                .push(Row::new()
+                    .push_space(Length::Fill)
+                    .push(self.bulk.view(style, dm_view))
+                    .push_space(Length::Fill))
+                .tap_if(dm_view, |col| col
+                    .push_space(gap(6))
+                    .push(Row::new()
+                        .push_space(Length::Fill)
+                        .push(reminder_menu)
+                        .push_space(Length::Fill)))
+                .tap_if(dm_view && !self.snapshots.is_empty(), |col| col
+                    .push_space(gap(6))
+                    .push(Row::new()
+                        .push_space(Length::Fill)
+                        .push(PickList::new(
+                            &mut self.rewind_list,
+                            self.snapshots.iter().rev().map(|snapshot| snapshot.label.clone()).collect_vec(),
+                            Some(String::from("Rewind to...")),
+                            Message::Rewind,
+                        ).style(style)
+                            .text_size(14))
+                        .push_space(Length::Fill))
+                    .tap_if_some(self.rewinding.and_then(|i| self.snapshots.get(i)), |col, snapshot| col
+                        .push_space(gap(6))
+                        .push(Row::new()
+                            .align_items(Align::Center)
+                            .push_space(Length::Fill)
+                            .push(Text::new(format!("Rewind to {}? Anything after it is lost", snapshot.label)).size(14))
+                            .push_space(6)
+                            .push(Button::new(&mut self.rewind_confirm, Text::new("Rewind").size(14))
+                                .style(style)
+                                .on_press(Message::ConfirmRewind))
+                            .push_space(4)
+                            .push(Button::new(&mut self.rewind_cancel, Text::new("Cancel").size(14))
+                                .style(style)
+                                .on_press(Message::CancelRewind))
+                            .push_space(Length::Fill))))
+                .tap_if_some(self.ties.front_mut(), |col, tie| col
+                    .push_space(gap(6))
+                    .push(Row::new()
+                        .push_space(Length::Fill)
+                        .push(tie.view(style))
+                        .push_space(Length::Fill)))
+                .push_space(gap(10))
+                .push_rule(gap(20))
                 .push(Column::new()
                     .align_items(Align::Center)
                     .push(submit_new_button)
-                    .push_space(15)
+                    .push_space(gap(15))
                     .push(new_name)
-                    .push_space(6)
+                    .push_space(gap(6))
                     .push(new_init)
-                    .push_space(6)
+                    .push_space(gap(6))
                     .push(new_hp)
-                    .push_space(6)
+                    .push_space(gap(6))
                     .push(new_las)
+                    .push_space(gap(6))
+                    .push(new_reactions)
+                    .push_space(gap(6))
+                    .push(new_count)
+                    .push_space(gap(6))
+                    .push(new_owner)
+                    .push_space(gap(6))
+                    .push(new_icon)
+                    .push_space(gap(6))
+                    .push(new_staged)
+                    .push_space(gap(6))
+                    .push(new_surprised)
+                    .push_space(gap(6))
+                    .push(new_disposition)
+                    .push_space(gap(12))
+                    .push(presets)
                 )
-                .push_rule(40)
+                .push_rule(gap(40))
                 .push(Container::new(Row::new()
                     .push(Column::new()
                         .push(save_encounter.width(Length::Units((options_width / 3.3) as _)))
-                        .push_space(10)
+                        .push_space(gap(10))
                         .push(save_party.width(Length::Units((options_width / 3.3) as _))))
                     .push_space(Length::Fill)
                     .push(Column::new()
                         .push(delete_encounter.width(Length::Units((options_width / 3.3) as _)))
-                        .push_space(10)
+                        .push_space(gap(10))
                         .push(delete_party.width(Length::Units((options_width / 3.3) as _))))
                     .push_space(Length::Fill)
                     .push(Column::new()
                         .push(load_encounter.width(Length::Units((options_width / 3.3) as _)))
-                        .push_space(10)
+                        .push_space(gap(10))
                         .push(load_party.width(Length::Units((options_width / 3.3) as _))))
                 ).width(Length::Shrink))
+                .push_space(gap(10))
+                .push(clear_encounter)
+                .push_space(gap(10))
+                .push(tag_legend)
+                .push_space(gap(10))
+                .push(webhook)
+                .push_space(gap(10))
+                .push(hook)
+                .push_space(gap(10))
+                .push(summary)
+                .push_space(gap(10))
+                .push(overlay)
+                .push_space(gap(10))
+                .push(roll20)
+                .push_space(gap(10))
+                .push(session_report)
                 .tap_if(
                     !matches!(self.save_mode, SaveMode::None),
-                    |col| col.push_space(10).push(self.save_mode.view(style)),
+                    |col| col.push_space(gap(10)).push(self.save_mode.view(style, legendary_display, &load_preview)),
                 )
         ).padding(8)
             .center_x();
@@ -1307,37 +4212,160 @@ impl Application for InitiativeManager {
         let toggle_visibility = self.dm_view.button_with(|text| text.size(12))
             .style(style.settings_bar())
             .on_press(Message::ToggleVisibility)
-            .tooltip(if dm_view { "Hide Secret Stats" } else { "Show Secret Stats" }, Position::Top)
+            .tooltip(if self.dm_view.value { "Hide Secret Stats" } else { "Show Secret Stats" }, Position::Top)
             .size(10);
+        let peek = (!self.dm_view.value).then(|| Button::new(
+            &mut self.peek_button,
+            Text::new(Icon::Eye)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::Peek)
+            .tooltip(format!("Peek at Secret Stats for {}s (Ctrl+P)", PEEK.as_secs()), Position::Top)
+            .size(10));
 
         let toggle_style = Button::new(
             &mut self.style_button,
-            Text::new(Icon::BrightnessHigh)
+            // what it'll switch to, not what it is now
+            Text::new(match !style {
+                Style::Light => Icon::BrightnessHigh,
+                Style::Dark => Icon::Moon,
+            })
                 .font(ICON_FONT)
                 .size(12),
         ).style(style.settings_bar())
-            .on_press(Message::ToggleStyle)
+            .on_press(Message::SetSetting(Setting::Style(!style)))
             .tooltip(format!("Switch to {} theme", !style), Position::Top)
             .size(10);
 
+        let toggle_scratchpad = Button::new(
+            &mut self.scratchpad_button,
+            Text::new(Icon::JournalText)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::Scratchpad(scratchpad::Message::Toggle))
+            .tooltip(if self.scratchpad.open { "Hide the scratchpad" } else { "Scratchpad" }, Position::Top)
+            .size(10);
+
+        let open_settings = Button::new(
+            &mut self.settings_button,
+            Text::new(Icon::GearFill)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::OpenSettings(!self.settings_panel.open))
+            .tooltip("Settings", Position::Top)
+            .size(10);
+
+        let server_port = self.server_port.text_input("port", Message::ServerPort)
+            .style(style.settings_bar())
+            .size(10)
+            .width(Length::Units(36));
+        let toggle_server = Button::new(
+            &mut self.server_button,
+            Text::new(Icon::Wifi)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::ToggleServer)
+            .tooltip(if self.settings.server { "Stop sharing the player view" } else { "Share the player view on your network" }, Position::Top)
+            .size(10);
+
+        let toggle_host = Button::new(
+            &mut self.host_button,
+            Text::new(Icon::Display)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::ToggleHost)
+            .tooltip(if self.settings.host {
+                "Stop hosting mirrors".to_string()
+            } else {
+                format!("Let mirrors connect on port {}", self.settings.sync_port)
+            }, Position::Top)
+            .size(10);
+
+        let table_ratio = Slider::new(
+            &mut self.table_ratio_slider,
+            settings::MIN_TABLE_RATIO..=settings::MAX_TABLE_RATIO,
+            table_ratio,
+            Message::TableRatio,
+        ).style(style)
+            .step(0.01)
+            .on_release(Message::TableRatioReleased)
+            .width(Length::Units(80))
+            .tooltip("Table width", Position::Top)
+            .size(10);
+
+        let export = Button::new(
+            &mut self.export_button,
+            Text::new(Icon::Printer)
+                .font(ICON_FONT)
+                .size(12),
+        ).style(style.settings_bar())
+            .on_press(Message::ExportOrder)
+            .tooltip("Export turn order", Position::Top)
+            .size(10);
+
+        let n_pinned = self.entities.iter()
+            .filter(|entity| entity.pinned.value)
+            .count();
         let bottom_bar = Container::new(Row::new()
             .spacing(2)
             .push_space(4)
-            .push(self.update_state.view(style.settings_bar()))
+            .push(self.update_state.view(&mut self.cancel_update, style.settings_bar()))
+            .tap_if_some(origin, |row, origin| row
+                .push_space(12)
+                .push(Text::new(origin).size(10)))
+            .tap_if_some(self.status.as_ref(), |row, status| row
+                .push_space(12)
+                .push(Text::new(status).size(10)))
+            .tap_if(n_pinned != 0, |row| row
+                .push_space(12)
+                .push(Text::new(format!("{n_pinned} pinned")).size(10)))
+            .tap_if(n_sidelined != 0, |row| row
+                .push_space(12)
+                .push(Text::new(format!("{n_sidelined} out of combat")).size(10)))
+            .tap_if(self.removed.is_some(), |row| row
+                .push_space(6)
+                .push(Button::new(&mut self.undo_remove, Text::new("Undo").size(10))
+                    .style(style.settings_bar())
+                    .on_press(Message::UndoDelete)))
             .push_space(Length::Fill)
+            .push(table_ratio)
+            .push_space(6)
+            .push(server_port)
+            .push(toggle_server)
+            .push(toggle_host)
+            .push(export)
+            .tap_if_some(peek, |row, peek| row.push(peek))
             .push(toggle_visibility)
             .push(toggle_style)
+            .push(toggle_scratchpad)
+            .push(open_settings)
             .height(Length::Units(20))
             .align_items(Align::Center)
         ).style(style.settings_bar())
             .align_y(Align::Center);
 
+        let side: Element<_> = if self.settings_panel.open {
+            self.settings_panel.view(style, &self.settings)
+        } else {
+            new_entity_col.width(Length::Fill).into()
+        };
+
+        let side = Scrollable::new(&mut self.side_scroll)
+            .push(side)
+            .height(Length::Fill);
+
         let content = Column::new()
             .push(Row::new()
-                .push(initiatives.width(Length::FillPortion(COLUMN_WIDTH_RATIO.0)))
-                .push(new_entity_col.width(Length::FillPortion(COLUMN_WIDTH_RATIO.1)))
-                .height(Length::Shrink)
-            ).push_space(Length::Fill)
+                .push(initiatives.width(Length::FillPortion(table_portion)))
+                .push(Container::new(side).width(Length::FillPortion(RATIO_PARTS as u16 - table_portion)))
+                .height(Length::Fill)
+            )
+            .tap_if(self.scratchpad.open, |col| col.push(self.scratchpad.view(style)))
             .push(bottom_bar);
 
         Container::new(content)
@@ -1351,35 +4379,1397 @@ impl Application for InitiativeManager {
 }
 
 impl InitiativeManager {
-    fn insert_entity(entities: &mut Vec<Entity>, turn: &mut usize, entity: Entity) {
-        let index = entities.iter()
-            .position(|e| e.initiative.0 < entity.initiative.0)
-            .unwrap_or(entities.len());
-        entities.insert(index, entity);
-        if *turn >= index {
-            *turn += 1;
-        }
-    }
+    /// A manager with nothing in the fight, without the update check `Application::new` starts.
+    /// Takes its settings and scratchpad so it can be made without reading them from disk.
+    fn with_settings(settings: Settings, scratchpad: scratchpad::Scratchpad, (width, height, mirror): (u32, u32, Option<String>)) -> Self {
+        let (player_view, player_view_rx) = watch::channel(Default::default());
+        Self {
+            update_state: UpdateState::Checking,
+            cancel_update: Default::default(),
+            close_requested: false,
+            exit: false,
+            update_url: "".to_string(),
+            status: None,
+            export_button: Default::default(),
+            roll20_button: Default::default(),
+            roll20_skip_hidden: false,
+            dm_view: ToggleButtonState::new_with(true, [Icon::EyeSlashFill, Icon::EyeFill]),
+            peek_until: None,
+            peek_button: Default::default(),
+            keyboard_focus: None,
+            style: settings.style,
+            width,
+            height,
+            style_button: Default::default(),
+            settings_button: Default::default(),
+            settings_panel: SettingsPanel::new(&settings),
+            scratchpad,
+            scratchpad_button: Default::default(),
+            table_ratio_slider: Default::default(),
+            player_view,
+            player_view_rx,
+            server_button: Default::default(),
+            server_port: TextInputState {
+                content: settings.server_port.to_string(),
+                ..Default::default()
+            },
+            host_button: Default::default(),
+            webhook_url: TextInputState {
+                content: settings.webhook_url.clone(),
+                ..Default::default()
+            },
+            announce_generation: 0,
+            webhook_backoff: Default::default(),
+            hook_command: TextInputState {
+                content: settings.hook_command.clone(),
+                ..Default::default()
+            },
+            hook_failed: false,
+            summary_url: TextInputState {
+                content: settings.summary_url.clone(),
+                ..Default::default()
+            },
+            overlay_path: TextInputState {
+                content: settings.overlay_path.clone(),
+                ..Default::default()
+            },
+            overlay_template: TextInputState {
+                content: settings.overlay_template.clone(),
+                ..Default::default()
+            },
+            overlay_next_path: TextInputState {
+                content: settings.overlay_next_path.clone(),
+                ..Default::default()
+            },
+            key_color: TextInputState {
+                content: settings.key_color.clone(),
+                ..Default::default()
+            },
+            overlay_generation: 0,
+            combat_log: vec![],
+            fallen: vec![],
+            recap: None,
+            save_recap: Default::default(),
+            dismiss_recap: Default::default(),
+            report_dir: TextInputState {
+                content: settings.report_dir.clone(),
+                ..Default::default()
+            },
+            report_file_name: TextInputState {
+                content: settings.report_file_name.clone(),
+                ..Default::default()
+            },
+            report_button: Default::default(),
+            report_overwrite: None,
+            mirror: mirror.map(sync::Mirror::new),
+            entities: vec![],
+            turn_history: vec![],
+            round_history: VecDeque::new(),
+            snapshots: VecDeque::new(),
+            rewind_list: Default::default(),
+            rewinding: None,
+            rewind_confirm: Default::default(),
+            rewind_cancel: Default::default(),
+            round_history_open: false,
+            round_history_toggle: Default::default(),
+            up_down: vec![],
+            highlight_state: None,
+            scroll: Default::default(),
+            side_scroll: Default::default(),
+            new_entity_submit: Default::default(),
+            new_entity: Default::default(),
+            preset_search: Default::default(),
+            preset_list: Default::default(),
+            disposition_filter: Default::default(),
+            filter_chips: Default::default(),
+            filter_list: Default::default(),
+            bulk: Default::default(),
+            reminders: Default::default(),
+            ties: Default::default(),
+            swapping: None,
+            order_locked: ToggleButtonState::new_with(false, [Icon::UnlockFill, Icon::LockFill]),
+            sidelined: Vec::new(),
+            sidelined_open: true,
+            sidelined_toggle: Default::default(),
+            tag_legend_color: TagColor::ALL[0],
+            tag_legend_list: Default::default(),
+            tag_legend: TextInputState {
+                content: settings.tag_legend.get(&TagColor::ALL[0]).cloned().unwrap_or_default(),
+                ..Default::default()
+            },
+            turn: 0,
+            round: 1,
+            next_turn: Default::default(),
+            prev_turn: Default::default(),
+            save_encounter: Default::default(),
+            delete_encounter: Default::default(),
+            load_encounter: Default::default(),
+            encounter_checks: Default::default(),
+            save_party: Default::default(),
+            delete_party: Default::default(),
+            load_party: Default::default(),
+            loaded_encounter: None,
+            encounter_notes: String::new(),
+            lair_actions: String::new(),
+            lair_banner: false,
+            lair_dismiss: Default::default(),
+            notes_open: false,
+            notes_toggle: Default::default(),
+            loaded_party: None,
+            clear_encounter: Default::default(),
+            removed: None,
+            undo_remove: Default::default(),
+            save_mode: Default::default(),
+            // last, everything above reads from it
+            settings,
+        }
+    }
+
+    /// Switches to `settings` and saves them, updating every text box that shows one
+    fn apply_settings(&mut self, settings: Settings) {
+        self.style = settings.style;
+        self.server_port.content = settings.server_port.to_string();
+        self.webhook_url.content = settings.webhook_url.clone();
+        self.hook_command.content = settings.hook_command.clone();
+        self.summary_url.content = settings.summary_url.clone();
+        self.overlay_path.content = settings.overlay_path.clone();
+        self.overlay_template.content = settings.overlay_template.clone();
+        self.overlay_next_path.content = settings.overlay_next_path.clone();
+        self.key_color.content = settings.key_color.clone();
+        self.report_dir.content = settings.report_dir.clone();
+        self.report_file_name.content = settings.report_file_name.clone();
+        self.tag_legend.content = settings.tag_legend.get(&self.tag_legend_color).cloned().unwrap_or_default();
+        self.report_overwrite = None;
+        self.settings_panel.suffix_colors.content = settings.suffix_colors.join(", ");
+        self.settings_panel.quick_conditions.content = settings.quick_conditions.join(", ");
+        self.settings = settings;
+        self.settings.save();
+    }
+
+    fn schedule_overlay(&mut self) -> Command<Message> {
+        self.overlay_generation += 1;
+        let generation = self.overlay_generation;
+        Command::perform(
+            async move {
+                tokio::time::sleep(overlay::DEBOUNCE).await;
+                generation
+            },
+            Message::WriteOverlay,
+        )
+    }
+
+    /// The order as the players should see it, starting from whoever's turn it is
+    fn player_rows(&self) -> Vec<export::Row> {
+        let len = self.entities.len();
+        (0..len)
+            .map(|offset| (offset, &self.entities[(self.turn + offset) % len]))
+            .filter(|(_, entity)| !entity.staged)
+            .map(|(offset, entity)| export::Row {
+                name: if entity.name.1 && self.settings.censor.names { entity.censored_name.clone() } else { entity.name.0.clone() },
+                initiative: (!entity.initiative.1).then(|| entity.initiative.0),
+                is_turn: offset == 0,
+                tag: entity.tag,
+            })
+            .collect()
+    }
+
+    /// "Goblin Ambush + The Nine"
+    fn origin(&self) -> Option<String> {
+        match (&self.loaded_encounter, &self.loaded_party) {
+            (Some(encounter), Some(party)) => Some(format!("{encounter} + {party}")),
+            (Some(name), None) | (None, Some(name)) => Some(name.clone()),
+            (None, None) => None,
+        }
+    }
+
+    fn forget_origins(&mut self) {
+        if !self.entities.iter().any(|e| e.origin == Some(Origin::Encounter)) {
+            self.loaded_encounter = None;
+            self.encounter_notes.clear();
+            self.lair_actions.clear();
+            self.lair_banner = false;
+        }
+        if !self.entities.iter().any(|e| e.origin == Some(Origin::Party)) {
+            self.loaded_party = None;
+        }
+    }
+
+    /// Adds `id` to whose turns were taken in `round`, see `round_history`
+    fn record_turn(&mut self, round: u32, id: EntityId) {
+        match self.round_history.back_mut() {
+            Some((last, ids)) if *last == round => ids.push(id),
+            _ => {
+                self.round_history.push_back((round, vec![id]));
+                if self.round_history.len() > ROUND_HISTORY_LEN {
+                    self.round_history.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Adds a `Snapshot` of right now to `snapshots`, labeled as the start of the round or just a turn
+    fn take_snapshot(&mut self, round_start: bool) {
+        let turn = match self.turn.checked_rem(self.entities.len()) {
+            Some(turn) => turn,
+            None => return,
+        };
+        let label = if round_start {
+            format!("Round {} start", self.round)
+        } else {
+            format!("Round {}, {}'s turn", self.round, self.entities[turn].name.0)
+        };
+        self.snapshots.push_back(Snapshot {
+            label,
+            round: self.round,
+            turn,
+            entities: self.entities.iter().map(Entity::snapshot).collect(),
+        });
+        if self.snapshots.len() > SNAPSHOT_LEN {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Takes back the last turn recorded, if it was `id`'s
+    fn unrecord_turn(&mut self, id: EntityId) {
+        if let Some((_, ids)) = self.round_history.back_mut() {
+            if ids.last() == Some(&id) {
+                ids.pop();
+            }
+            if ids.is_empty() {
+                self.round_history.pop_back();
+            }
+        }
+    }
+
+    /// A line for each round in `round_history`, newest first, with who's still to go this round.
+    /// Hidden names are censored unless `dm_view`, in case the players can see the screen.
+    fn round_history_lines(&self, dm_view: bool) -> Vec<String> {
+        let name = |id: EntityId| self.entities.iter()
+            .chain(self.sidelined.iter().map(|(entity, _)| entity))
+            .find(|entity| entity.id == id)
+            .map(|entity| if dm_view || !entity.name.1 {
+                entity.name.0.clone()
+            } else {
+                entity.censored_name.clone()
+            })
+            .or_else(|| dm_view.then(|| self.fallen.iter()
+                .find(|(fallen, _)| *fallen == id)
+                .map(|(_, combatant)| combatant.name.clone()))
+                .flatten())
+            .unwrap_or_else(|| String::from("(removed)"));
+
+        let acted = self.round_history.back()
+            .filter(|(round, _)| *round == self.round)
+            .map_or(&[][..], |(_, ids)| ids.as_slice());
+        let to_go = self.entities.iter()
+            .filter(|entity| !entity.staged && !acted.contains(&entity.id))
+            .map(|entity| name(entity.id))
+            .collect::<Vec<_>>();
+        let mut lines = vec![format!(
+            "Round {} still to go: {}",
+            self.round,
+            if to_go.is_empty() { String::from("nobody") } else { to_go.join(", ") },
+        )];
+        lines.extend(self.round_history.iter().rev().map(|(round, ids)| format!(
+            "Round {round}: {}",
+            ids.iter().map(|&id| name(id)).join(", "),
+        )));
+        lines
+    }
+
+    /// Moves on to the next turn that isn't skipped, doing the upkeep for the end of the outgoing
+    /// turn and the start of the incoming one
+    fn advance_turn(&mut self, commands: &mut Vec<Command<Message>>) {
+        let round = self.round;
+        // terminates because each skip clears a `surprised`
+        while let Some(next) = self.next_turn(true) {
+            if self.settings.announce {
+                self.announce_generation += 1;
+                let generation = self.announce_generation;
+                commands.push(Command::perform(
+                    async move {
+                        tokio::time::sleep(webhook::COALESCE).await;
+                        generation
+                    },
+                    Message::Announce,
+                ));
+            }
+            let curr = self.turn % self.entities.len();
+            let outgoing = &self.entities[curr];
+            let outgoing = (outgoing.id, outgoing.acted, outgoing.legendary_actions.map(|Hidden((_, left), _)| left));
+            self.turn_history.push(TurnUndo {
+                incoming: TurnState::save(&self.entities[next]),
+                outgoing,
+                cleared: Vec::new(),
+            });
+            let round = self.round;
+            self.record_turn(round, self.entities[curr].id);
+            let outgoing = &mut self.entities[curr];
+            outgoing.acted = true;
+            outgoing.last_turn = Some(Instant::now());
+            let reset = self.settings.legendary_reset;
+            if reset == LegendaryReset::EndOfTurn {
+                refresh_legendary_actions(outgoing);
+            }
+            if next <= curr {
+                self.round += 1;
+                let cleared = self.entities.iter_mut()
+                    .filter(|entity| entity.acted)
+                    .map(|entity| {
+                        entity.acted = false;
+                        entity.id
+                    })
+                    .collect();
+                if let Some(undo) = self.turn_history.last_mut() {
+                    undo.cleared = cleared;
+                }
+            }
+            self.turn = next;
+            // lair actions lose initiative ties, so they go off on the way from 20 to 19
+            let from = self.entities[curr].initiative.0;
+            let to = self.entities[next].initiative.0;
+            let new_round = next <= curr;
+            let lair_turn = if self.settings.initiative_caller {
+                (new_round || from >= LAIR_INITIATIVE) && to < LAIR_INITIATIVE
+            } else {
+                new_round
+            };
+            if lair_turn && !self.lair_actions.is_empty() {
+                self.lair_banner = true;
+            }
+            let entity = &mut self.entities[next];
+            begin_turn(entity, self.round, reset);
+            if self.round == 1 && entity.surprised {
+                entity.surprised = false;
+            } else {
+                break;
+            }
+        }
+        if self.round != round || self.settings.snapshot_turns {
+            self.take_snapshot(self.round != round);
+        }
+    }
+
+    /// The index of the next (or previous) entity that isn't staged or dead, wrapping around the end
+    /// of the order. `None` if there are no entities or they are all staged or dead.
+    fn next_turn(&self, forwards: bool) -> Option<usize> {
+        let len = self.entities.len();
+        let turn = self.turn.checked_rem(len)?;
+        (1..=len)
+            .map(|offset| if forwards {
+                (turn + offset) % len
+            } else {
+                (turn + len - offset) % len
+            })
+            .find(|&i| !self.entities[i].staged && !self.entities[i].dead)
+    }
+
+    /// Keeps the `saved` id unless a current entity already has it (say the same encounter was loaded
+    /// twice), in which case a new one is used.
+    fn claim_id(entities: &[Entity], saved: Option<EntityId>) -> EntityId {
+        match saved {
+            Some(id) if entities.iter().all(|e| e.id != id) => id.reserve(),
+            _ => EntityId::next(),
+        }
+    }
+
+    /// Takes `damage` off of the entity at `i`, then does whatever `Settings::at_zero` says if
+    /// it's a monster that hit 0 HP.
+    fn damage(&mut self, i: usize, damage: i64, commands: &mut Vec<Command<Message>>) {
+        // whoever's turn it is most likely did it
+        if let Some(turn) = self.turn.checked_rem(self.entities.len()) {
+            self.entities[turn].damage_dealt += damage.max(0) as u64;
+        }
+        let entity = &mut self.entities[i];
+        entity.change_hp(-damage);
+        // logged by which way it went, so the report agrees with `Entity::healing_received`
+        let (name, hp) = (entity.name.0.clone(), entity.hp.0);
+        self.combat_log.push(report::LogEntry {
+            round: self.round,
+            event: if damage < 0 {
+                report::LogEvent::Heal { name, amount: -damage, hp }
+            } else {
+                report::LogEvent::Damage { name, amount: damage, hp }
+            },
+        });
+        if damage > 0 && entity.concentrating.value {
+            let id = entity.id;
+            commands.push(async move {
+                Message::HighlightConcentration(id, Instant::now() + Duration::from_millis(1400))
+            }.into());
+        }
+        // only monsters, a PC at 0 is making death saves whether they were loaded or typed in
+        let monster = entity.disposition != Disposition::Ally;
+        if damage > 0 && monster && entity.hp.0 == 0 {
+            match self.settings.at_zero {
+                AtZero::KeepDead => entity.dead = true,
+                AtZero::Remove => self.remove_fallen(i, commands),
+                AtZero::Prompt => entity.zero_prompt = true,
+            }
+        }
+    }
+
+    /// Takes the monster at `i` out at 0 HP, logged so undoing it can take the log back out too
+    fn remove_fallen(&mut self, i: usize, commands: &mut Vec<Command<Message>>) {
+        let entity = self.remove_entity(i);
+        self.fallen.push((entity.id, entity.combatant()));
+        self.status = Some(format!("Removed {} at 0 HP", entity.name.0));
+        self.combat_log.push(report::LogEntry {
+            round: self.round,
+            event: report::LogEvent::Removed { name: entity.name.0.clone() },
+        });
+        self.offer_undo(Removal { entities: vec![(i, entity)], logged: true }, UNDO_REMOVE_WINDOW, commands);
+    }
+
+    /// Shows the Undo button next to the status for `window`, replacing any older removal
+    fn offer_undo(&mut self, removal: Removal, window: Duration, commands: &mut Vec<Command<Message>>) {
+        if let Some(id) = removal.entities.first().map(|(_, e)| e.id) {
+            commands.push(Command::perform(
+                async move {
+                    tokio::time::sleep(window).await;
+                    id
+                },
+                Message::ForgetRemoved,
+            ));
+        }
+        self.removed = Some(removal);
+    }
+
+    fn heal(&mut self, i: usize, heal: i64) {
+        let entity = &mut self.entities[i];
+        entity.change_hp(heal);
+        // see `damage`
+        let (name, hp) = (entity.name.0.clone(), entity.hp.0);
+        self.combat_log.push(report::LogEntry {
+            round: self.round,
+            event: if heal < 0 {
+                report::LogEvent::Damage { name, amount: -heal, hp }
+            } else {
+                report::LogEvent::Heal { name, amount: heal, hp }
+            },
+        });
+    }
+
+    /// Heals every ally to full, whether they were loaded with a party or typed in
+    fn heal_party(&mut self) {
+        let mut healed = 0;
+        for i in 0..self.entities.len() {
+            let entity = &self.entities[i];
+            if entity.disposition == Disposition::Ally && entity.hp.0 < entity.max_hp {
+                let missing = entity.max_hp - entity.hp.0;
+                self.heal(i, missing as i64);
+                healed += 1;
+            }
+        }
+        self.status = Some(format!("Healed {healed} to full"));
+    }
+
+    /// Where the new entity will go, from `insertion_index` so it can't disagree with where it
+    /// actually ends up. `None` until an initiative is typed.
+    fn init_hint(&self) -> Option<String> {
+        let init = self.new_entity.init.0.content.as_str();
+        if init.is_empty() {
+            return None;
+        }
+        if init.starts_with(['+', '-']) {
+            let modifier = init.parse::<i32>().unwrap_or(0);
+            let first = Self::insertion_index(&self.entities, (20 + modifier).max(0) as u32) + 1;
+            let last = Self::insertion_index(&self.entities, (1 + modifier).max(0) as u32) + 1;
+            return Some(if first == last {
+                format!("will act {} whatever the roll", ordinal(first))
+            } else {
+                format!("between {} and {} depending on roll", ordinal(first), ordinal(last))
+            });
+        }
+        let index = Self::insertion_index(&self.entities, init.parse().ok()?);
+        let describe = |entity: &Entity| format!("{} ({})", entity.name.0, entity.initiative.0);
+        let place = ordinal(index + 1);
+        Some(match (index.checked_sub(1).map(|i| &self.entities[i]), self.entities.get(index)) {
+            (Some(before), Some(after)) => format!("will act {place}, between {} and {}", describe(before), describe(after)),
+            (Some(before), None) => format!("will act {place}, after {}", describe(before)),
+            (None, Some(after)) => format!("will act {place}, before {}", describe(after)),
+            (None, None) => format!("will act {place}"),
+        })
+    }
+
+    /// The background while streaming, `None` when not streaming. A color that doesn't parse falls
+    /// back to the default rather than leaving the user stuck without controls or a key.
+    fn key_color(&self) -> Option<Color> {
+        self.settings.streaming.then(|| parse_hex_color(&self.settings.key_color)
+            .or_else(|| parse_hex_color(settings::DEFAULT_KEY_COLOR))
+            .unwrap_or(Color::from_rgb(0.0, 1.0, 0.0)))
+    }
+
+    /// Who "damage all" would hit right now
+    fn bulk_targets(&self) -> Vec<EntityId> {
+        let target = self.bulk.target;
+        let filter = self.disposition_filter;
+        let turn = self.turn.checked_rem(self.entities.len());
+        self.entities.iter()
+            .enumerate()
+            // whoever's turn it is is always shown, see `view`
+            .filter(|&(i, e)| target.matches(e.disposition, e.name.1, Some(i) == turn || filter.shows(e.disposition)))
+            .map(|(_, e)| e)
+            .map(|e| e.id)
+            .collect()
+    }
+
+    /// Whether the secret stats are shown, either from the DM view or a peek that hasn't run out
+    fn dm_view(&self) -> bool {
+        self.dm_view.value || self.peek_until.map_or(false, |until| Instant::now() < until)
+    }
+
+    fn peek(&mut self) {
+        let now = Instant::now();
+        let from = self.peek_until.filter(|&until| until > now).unwrap_or(now);
+        self.peek_until = Some(from + PEEK);
+    }
+
+    /// Everything on screen Tab can reach, in order: the new entity form, any save/load dialog, the
+    /// turn buttons, whoever's turn it is, the rest of the table, the options, then the bottom bar.
+    fn focus_order(&mut self) -> Vec<Stop> {
+        let dm_view = self.dm_view();
+        let disposition_filter = self.disposition_filter;
+        let side_panel = !self.settings_panel.open;
+        let mut stops = Vec::new();
+
+        if side_panel {
+            let NewEntity { name, init, hp, leg_acts, reactions, count, owner, icon_path, .. } = &mut self.new_entity;
+            stops.extend([
+                &mut name.0.state,
+                &mut init.0.state,
+                &mut hp.0.state,
+                &mut leg_acts.0.state,
+                &mut reactions.state,
+                &mut count.state,
+                &mut owner.state,
+                &mut icon_path.state,
+            ].map(Stop::Input));
+            stops.push(Stop::Control(Control::NewDisposition));
+            stops.push(Stop::Control(Control::SubmitNewEntity));
+        }
+
+        match &mut self.save_mode {
+            // lair actions are drawn above the name, see `SaveMode::view`
+            SaveMode::SaveEncounter(name, notes, lair, ..) => stops.extend([
+                &mut lair.state,
+                &mut name.state,
+                &mut notes.state,
+            ].map(Stop::Input)),
+            SaveMode::SaveParty(name, ..) => stops.push(Stop::Input(&mut name.state)),
+            SaveMode::LoadEncounter(.., adjust, _) => {
+                stops.push(Stop::Input(&mut adjust.hp.state));
+                stops.push(Stop::Input(&mut adjust.copies.state));
+            }
+            SaveMode::LoadParty(_, _, _, rows) => {
+                // hp second, since it's usually left blank
+                let (inits, hps): (Vec<_>, Vec<_>) = rows.iter_mut()
+                    .map(|(_, init, _, _, hp)| (Stop::Input(&mut init.state), Stop::Input(&mut hp.state)))
+                    .unzip();
+                stops.extend(inits);
+                stops.extend(hps);
+            }
+            SaveMode::AssembleParty(name, ..) => stops.push(Stop::Input(&mut name.state)),
+            SaveMode::EnterInitiatives(_, _, rows) => stops.extend(rows.iter_mut()
+                .map(|(.., text_input)| Stop::Input(&mut text_input.state))),
+            SaveMode::DeleteEncounter(confirm) | SaveMode::DeleteParty(confirm) => stops.push(Stop::Input(&mut confirm.text.state)),
+            SaveMode::None => {}
+        }
+
+        if side_panel {
+            stops.push(Stop::Control(Control::NextTurn));
+            stops.push(Stop::Control(Control::PrevTurn));
+            stops.push(Stop::Control(Control::Filter));
+        }
+
+        fn row_inputs(entity: &mut Entity) -> impl Iterator<Item = Stop<'_>> {
+            let Entity { new_condition, damage, heal, attack, ac, .. } = entity;
+            let mut inputs = vec![&mut new_condition.state, &mut damage.state, &mut heal.state];
+            if ac.is_some() {
+                inputs.push(&mut attack.state);
+            }
+            inputs.into_iter().map(Stop::Input)
+        }
+
+        // the table starts at whoever's turn it is, see `view`
+        let turn = self.turn.checked_rem(self.entities.len()).unwrap_or(0);
+        let (end, start) = self.entities.split_at_mut(turn);
+        if let Some((current, rest)) = start.split_first_mut() {
+            // whoever's turn it is is always shown, see `view`
+            let legendary = current.legendary_actions.is_some();
+            if dm_view {
+                stops.extend(row_inputs(current));
+            }
+            stops.push(Stop::Control(Control::Row(RowControl::Remove)));
+            if dm_view {
+                stops.push(Stop::Control(Control::Row(RowControl::Pin)));
+                stops.push(Stop::Control(Control::Row(RowControl::Sideline)));
+            }
+            stops.push(Stop::Control(Control::Row(RowControl::Reaction)));
+            stops.push(Stop::Control(Control::Row(RowControl::Concentrate)));
+            if legendary {
+                stops.push(Stop::Control(Control::Row(RowControl::LegendaryMinus)));
+                stops.push(Stop::Control(Control::Row(RowControl::LegendaryPlus)));
+            }
+            if dm_view {
+                for entity in rest.iter_mut().chain(end.iter_mut())
+                    .filter(|entity| disposition_filter.shows(entity.disposition)) {
+                    stops.extend(row_inputs(entity));
+                }
+            }
+        }
+
+        if side_panel {
+            if self.bulk.open {
+                stops.push(Stop::Input(&mut self.bulk.amount.state));
+            }
+            if dm_view && self.reminders.open {
+                stops.push(Stop::Input(&mut self.reminders.text.state));
+            }
+            stops.extend([
+                &mut self.preset_search.state,
+                &mut self.tag_legend.state,
+                &mut self.webhook_url.state,
+                &mut self.hook_command.state,
+                &mut self.summary_url.state,
+                &mut self.overlay_path.state,
+                &mut self.overlay_template.state,
+                &mut self.overlay_next_path.state,
+                &mut self.key_color.state,
+                &mut self.report_dir.state,
+                &mut self.report_file_name.state,
+            ].map(Stop::Input));
+        } else {
+            stops.extend([
+                &mut self.settings_panel.suffix_colors.state,
+                &mut self.settings_panel.quick_conditions.state,
+                &mut self.settings_panel.path.state,
+            ].map(Stop::Input));
+        }
+
+        if self.scratchpad.open {
+            stops.push(Stop::Input(&mut self.scratchpad.text.state));
+        }
+        stops.push(Stop::Input(&mut self.server_port.state));
+        stops
+    }
+
+    /// Moves the keyboard focus to the next (or previous) stop in `focus_order`
+    fn focus_next(&mut self, forwards: bool) {
+        let focus = self.keyboard_focus.take();
+        let mut stops = self.focus_order();
+        let len = stops.len();
+        // with nothing focused, Tab starts at the first field and Shift+Tab at the last
+        let next = match stops.iter().position(|stop| stop.is_focused(focus)) {
+            Some(i) => {
+                if let Stop::Input(state) = &mut stops[i] {
+                    state.unfocus();
+                }
+                if forwards { (i + 1) % len } else { (i + len - 1) % len }
+            }
+            None => if forwards { 0 } else { len.wrapping_sub(1) },
+        };
+        let control = match stops.get_mut(next) {
+            Some(Stop::Input(state)) => {
+                state.focus();
+                None
+            }
+            Some(Stop::Control(control)) => Some(*control),
+            None => None,
+        };
+        self.keyboard_focus = control;
+    }
+
+    /// What Enter or Space does when it isn't going to a text input: the focused control's message,
+    /// or else answering a tie (which is done here, so there's nothing more to run)
+    fn activate(&mut self) -> Option<Message> {
+        if self.typing() {
+            return None;
+        }
+        if let Some(message) = self.keyboard_focus.and_then(|control| self.control_message(control)) {
+            Some(message)
+        } else {
+            if !self.ties.is_empty() {
+                // keeps them after, how they were already put in
+                self.place_tie(false);
+            }
+            None
+        }
+    }
+
+    /// Damages (or heals, if `by` is positive) whoever's turn it is by `by`
+    fn nudge_hp(&mut self, by: i64, commands: &mut Vec<Command<Message>>) {
+        // typing a `-` into the damage box (or the notes, or a reminder) shouldn't also hurt
+        // whoever's turn it is
+        let typing = self.typing();
+        if let (false, Some(turn)) = (typing, self.turn.checked_rem(self.entities.len())) {
+            if by < 0 {
+                self.damage(turn, -by, commands);
+            } else {
+                self.heal(turn, by);
+            }
+        }
+    }
+
+    /// if any text input has focus, so keys should go to it
+    fn typing(&mut self) -> bool {
+        self.focus_order().iter().any(|stop| matches!(stop, Stop::Input(state) if state.is_focused()))
+    }
+
+    /// What pressing Enter or Space on `control` does, `None` if it's disabled right now
+    fn control_message(&self, control: Control) -> Option<Message> {
+        // `turn` can be one past the end, see `view`
+        let turn = self.turn.checked_rem(self.entities.len()).unwrap_or(0);
+        let current = self.entities.get(turn);
+        Some(match control {
+            Control::SubmitNewEntity => Message::NewEntitySubmit,
+            Control::NewDisposition => Message::NewDisposition(self.new_entity.disposition.next()),
+            Control::NextTurn => Message::NextTurn,
+            Control::PrevTurn => Message::PrevTurn,
+            Control::Filter => {
+                let filters = disposition::Filter::ALL;
+                let i = filters.iter().position(|&filter| filter == self.disposition_filter).unwrap_or(0);
+                Message::FilterDisposition(filters[(i + 1) % filters.len()])
+            }
+            Control::Row(control) => {
+                let current = current?;
+                match control {
+                    RowControl::Remove => Message::DeleteEntity(turn),
+                    RowControl::Pin => Message::TogglePin(turn),
+                    RowControl::Sideline => Message::Sideline(turn),
+                    RowControl::Reaction => Message::Reaction(turn),
+                    RowControl::Concentrate => Message::Concentrate(turn),
+                    RowControl::LegendaryMinus => match &current.legendary_actions {
+                        Some(Hidden((_, left), _)) if *left != 0 => Message::LegActionMinus(turn),
+                        _ => return None,
+                    },
+                    RowControl::LegendaryPlus => match &current.legendary_actions {
+                        Some(Hidden((total, left), _)) if left != total => Message::LegActionPlus(turn),
+                        _ => return None,
+                    },
+                }
+            }
+        })
+    }
+
+    /// Takes the entity at `i` out of the order, keeping `turn` on whoever's turn it was (or the next
+    /// one if it was `i`'s).
+    fn remove_entity(&mut self, i: usize) -> Entity {
+        let entity = self.entities.remove(i);
+        if i < self.turn {
+            self.turn -= 1;
+        }
+        self.refresh_adjacency();
+        entity
+    }
+
+    /// Entities can only be moved past neighbors with the same initiative. Call this whenever the
+    /// order or initiatives change.
+    fn refresh_adjacency(&mut self) {
+        self.regroup();
+        let ties = self.entities.windows(2)
+            .map(|pair| pair[0].initiative.0 == pair[1].initiative.0)
+            .collect_vec();
+        self.up_down = (0..self.entities.len())
+            .map(|i| [i != 0 && ties[i - 1], ties.get(i).copied().unwrap_or(false)])
+            .collect();
+    }
+
+    /// Pulls each held group back together right after its first member, so they take their turns
+    /// one after another, and breaks up any group that's down to one member.
+    fn regroup(&mut self) {
+        let len = self.entities.len();
+        let current = self.turn.checked_rem(len).map(|turn| self.entities[turn].id);
+        for i in 0..len {
+            let group = self.entities[i].group;
+            if group.is_some() && self.entities.iter().filter(|e| e.group == group).count() < 2 {
+                self.entities[i].group = None;
+            }
+        }
+        let mut i = 0;
+        while i < len {
+            let group = match self.entities[i].group {
+                Some(group) => group,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+            let mut end = i + 1;
+            for j in end..len {
+                if self.entities[j].group == Some(group) {
+                    let member = self.entities.remove(j);
+                    self.entities.insert(end, member);
+                    end += 1;
+                }
+            }
+            i = end;
+        }
+        if let Some(current) = current {
+            self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+        }
+    }
+
+    /// Where an entity with `initiative` goes, after everyone with the same or higher initiative. So
+    /// ties stay in the order they were added, the first one added goes first (they can still be
+    /// moved past each other by hand, see `refresh_adjacency`).
+    fn insertion_index(entities: &[Entity], initiative: u32) -> usize {
+        insertion_index_by(entities, initiative, |e| e.initiative.0)
+    }
+
+    /// What loading the encounter being previewed will do to the fight, `None` if there's no fight
+    /// yet or nothing's being loaded
+    fn load_preview(&self) -> Option<Vec<String>> {
+        let (rows, copies) = match &self.save_mode {
+            SaveMode::LoadEncounter(_, _, _, rows, _, _, adjust, _) if !self.entities.is_empty() => (rows, adjust.copies()),
+            _ => return None,
+        };
+        // `None` is someone already in the fight, otherwise the row it came from
+        let mut order = self.entities.iter()
+            .map(|e| (e.initiative.0, None))
+            .collect_vec();
+        // the same order `Message::LoadEncounter` adds them in
+        let mut sorted = rows.iter().enumerate().collect_vec();
+        sorted.sort_by_key(|(_, enemy)| (Reverse(enemy.initiative.0), enemy.order));
+        for (row, enemy) in sorted {
+            for copy in 0..=copies {
+                let initiative = enemy.copy_initiative(copy);
+                let index = insertion_index_by(&order, initiative, |&(initiative, _)| initiative);
+                order.insert(index, (initiative, Some(row)));
+            }
+        }
+
+        let added = rows.len() * (copies + 1);
+        let mut lines = vec![format!(
+            "+{added} new {}",
+            if added == 1 { "creature" } else { "creatures" },
+        )];
+        lines.extend(rows.iter().enumerate().map(|(row, enemy)| {
+            let places = order.iter()
+                .enumerate()
+                .filter(|(_, (_, from))| *from == Some(row))
+                .map(|(i, &(initiative, _))| (ordinal(i + 1), initiative))
+                .collect_vec();
+            // a template's copies rolled their own, so each place gets its initiative
+            if places.iter().all(|&(_, initiative)| initiative == enemy.initiative.0) {
+                format!("{} ({}) goes {}", enemy.name.0, enemy.initiative.0, places.iter().map(|(place, _)| place).join(", "))
+            } else {
+                format!("{} goes {}", enemy.name.0, places.iter().map(|(place, initiative)| format!("{place} ({initiative})")).join(", "))
+            }
+        }));
+        Some(lines)
+    }
+
+    /// Puts `entity` into the order at its initiative, without changing whose turn it is
+    pub fn add_entity(&mut self, entity: Entity) {
+        Self::insert_entity(&mut self.entities, &mut self.turn, entity);
+        self.refresh_adjacency();
+    }
+
+    /// Asks about each initiative that some of `added` tied with someone who was already there, once
+    /// per initiative no matter how many of them rolled it
+    fn ask_about_ties(&mut self, added: &[EntityId]) {
+        let resolved = self.resolve_ties(added);
+        let (new, existing): (Vec<&Entity>, Vec<&Entity>) = self.entities.iter()
+            .partition(|e| added.contains(&e.id));
+        let prompts = new.iter()
+            .map(|e| e.initiative.0)
+            .unique()
+            .filter(|initiative| !resolved.contains(initiative))
+            .filter_map(|initiative| {
+                let tied = existing.iter()
+                    .filter(|e| e.initiative.0 == initiative)
+                    .map(|e| e.name.0.as_str())
+                    .collect_vec();
+                if tied.is_empty() {
+                    return None;
+                }
+                let (ids, names): (Vec<_>, Vec<_>) = new.iter()
+                    .filter(|e| e.initiative.0 == initiative)
+                    .map(|e| (e.id, e.name.0.as_str()))
+                    .unzip();
+                Some(tie::TiePrompt::new(initiative, ids, &names, &tied))
+            })
+            .collect_vec();
+        self.ties.extend(prompts);
+    }
+
+    /// Settles the ties between players and monsters that `added` are in by `Settings::tie_rule`,
+    /// returning the initiatives it settled so they aren't asked about too
+    fn resolve_ties(&mut self, added: &[EntityId]) -> Vec<u32> {
+        let rule = self.settings.tie_rule;
+        if rule == TieRule::Manual {
+            return Vec::new();
+        }
+        let initiatives = self.entities.iter()
+            .filter(|e| added.contains(&e.id))
+            .map(|e| e.initiative.0)
+            .unique()
+            .collect_vec();
+        let current = self.turn.checked_rem(self.entities.len()).map(|turn| self.entities[turn].id);
+        let mut rng = rand::thread_rng();
+        let mut resolved = Vec::new();
+        let mut rolls = Vec::new();
+        for initiative in initiatives {
+            // ties are next to each other, since the order is sorted by initiative
+            let start = match self.entities.iter().position(|e| e.initiative.0 == initiative) {
+                Some(start) => start,
+                None => continue,
+            };
+            let end = self.entities[start..].iter()
+                .position(|e| e.initiative.0 != initiative)
+                .map_or(self.entities.len(), |len| start + len);
+            let tied = &mut self.entities[start..end];
+            let mixed = tied.iter().any(|e| e.disposition == Disposition::Ally)
+                && tied.iter().any(|e| e.disposition == Disposition::Enemy);
+            if !mixed {
+                continue;
+            }
+            // all stable, so each side keeps its own order
+            match rule {
+                TieRule::Manual => {}
+                TieRule::PlayersFirst => tied.sort_by_key(|e| match e.disposition {
+                    Disposition::Ally => 0,
+                    Disposition::Enemy => 2,
+                    Disposition::Neutral => 1,
+                }),
+                TieRule::MonstersFirst => tied.sort_by_key(|e| match e.disposition {
+                    Disposition::Enemy => 0,
+                    Disposition::Ally => 2,
+                    Disposition::Neutral => 1,
+                }),
+                TieRule::RollOff => {
+                    let mut rolled = tied.iter()
+                        .map(|e| (rng.gen_range(1..=20), e.id, e.name.0.clone()))
+                        .collect_vec();
+                    rolled.sort_by_key(|(roll, ..)| Reverse(*roll));
+                    tied.sort_by_key(|e| rolled.iter().position(|(_, id, _)| *id == e.id));
+                    rolls.extend(rolled.into_iter().map(|(roll, _, name)| format!("{name} {roll}")));
+                }
+            }
+            resolved.push(initiative);
+        }
+        if !rolls.is_empty() {
+            self.status = Some(format!("Roll-off: {}", rolls.join(", ")));
+        }
+        if let Some(current) = current {
+            self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+        }
+        self.refresh_adjacency();
+        resolved
+    }
+
+    /// Brings the `c`th of the companions of the entity at `owner` into the fight, rolling its HP
+    fn add_companion(&mut self, owner: usize, c: usize) {
+        let owner_entity = &self.entities[owner];
+        let Companion { name, hp, shares_turn, initiative_bonus } = owner_entity.companions[c].clone();
+        let (owner_id, owner_name, owner_initiative) = (owner_entity.id, owner_entity.name.0.clone(), owner_entity.initiative.0);
+        let (hp, max_hp) = hp.parse::<CurrentMaxHp>()
+            .ok()
+            .and_then(CurrentMaxHp::into_numbers)
+            .unwrap_or((0, 0));
+        let initiative = if shares_turn { owner_initiative } else { roll_initiative(initiative_bonus) };
+        let name = self.dedupe_name(name);
+        self.status = Some(format!("{name} joined the fight"));
+        let mut entity = Entity::new(name.into(), hp.into(), initiative.into());
+        entity.max_hp = max_hp;
+        entity.disposition = Disposition::Ally;
+        entity.owner = Some(owner_name);
+        entity.companion_of = Some((owner_id, c));
+        if shares_turn {
+            let owner = &mut self.entities[owner];
+            let group = *owner.group.get_or_insert(owner_id);
+            entity.group = Some(group);
+        }
+        self.add_entity(entity);
+    }
+
+    /// Trades the initiatives of the entities at `a` and `b`, then puts both back in the order where
+    /// their new initiatives go. Whoever's turn it is keeps it.
+    fn swap_initiative(&mut self, a: usize, b: usize) {
+        let current = self.turn.checked_rem(self.entities.len()).map(|turn| self.entities[turn].id);
+        let (first, second) = if a < b { (b, a) } else { (a, b) };
+        // the later one first so the other's index doesn't move
+        let mut first = self.entities.remove(first);
+        let mut second = self.entities.remove(second);
+        std::mem::swap(&mut first.initiative.0, &mut second.initiative.0);
+        self.combat_log.push(report::LogEntry {
+            round: self.round,
+            event: report::LogEvent::SwappedInitiative {
+                first: first.name.0.clone(),
+                first_initiative: first.initiative.0,
+                second: second.name.0.clone(),
+                second_initiative: second.initiative.0,
+            },
+        });
+        self.status = Some(format!("{} and {} swapped initiative", first.name.0, second.name.0));
+        for entity in [first, second] {
+            let index = Self::insertion_index(&self.entities, entity.initiative.0);
+            self.entities.insert(index, entity);
+        }
+        if let Some(current) = current {
+            self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+        }
+        self.refresh_adjacency();
+    }
+
+    /// Answers the first `TiePrompt`, moving its entities in front of the others at that initiative
+    /// if `before`
+    fn place_tie(&mut self, before: bool) {
+        let prompt = match self.ties.pop_front() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        if !before {
+            return;
+        }
+        let current = self.turn.checked_rem(self.entities.len()).map(|turn| self.entities[turn].id);
+        let (moved, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entities)
+            .into_iter()
+            .partition(|e| prompt.ids.contains(&e.id));
+        self.entities = rest;
+        let index = self.entities.iter()
+            .position(|e| e.initiative.0 == prompt.initiative)
+            .unwrap_or_else(|| Self::insertion_index(&self.entities, prompt.initiative));
+        self.entities.splice(index..index, moved);
+        if let Some(current) = current {
+            self.turn = self.entities.iter().position(|e| e.id == current).unwrap_or(self.turn);
+        }
+        self.refresh_adjacency();
+    }
+
+    /// The entities picked in a `SaveMode::SaveEncounter`, as they're saved
+    fn enemies(entities: &[Entity], rows: &[SaveRow]) -> Vec<Enemy> {
+        entities.iter()
+            .filter(|entity| rows.iter().any(|row| row.id == entity.id && row.included))
+            .enumerate()
+            .map(|(order, Entity { id, name, hp, max_hp, hp_formula, ac, icon, disposition, owner, initiative, initiative_bonus, legendary_actions, reactions, .. })| Enemy {
+                id: Some(*id),
+                name: name.clone(),
+                hp: *hp,
+                max_hp: Some(*max_hp),
+                ac: *ac,
+                hp_formula: hp_formula.clone(),
+                rolled_hp: None,
+                icon: icon.clone(),
+                disposition: *disposition,
+                owner: owner.clone(),
+                legendary_actions: legendary_actions.map(|Hidden((las, _), hidden)| Hidden(las, hidden)),
+                reactions: Some(reactions.1).filter(|&max| max != 1),
+                initiative: *initiative,
+                initiative_bonus: *initiative_bonus,
+                order,
+                copy_initiatives: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// The players picked in a `SaveMode::SaveParty`, as they're saved
+    fn pcs(entities: &[Entity], rows: &[SaveRow]) -> Vec<Pc> {
+        entities.iter()
+            .filter(|entity| rows.iter().any(|row| row.id == entity.id && row.included))
+            .map(|Entity { id, name, hp, max_hp, owner, companions, .. }| Pc {
+                id: Some(*id),
+                name: name.0.clone(),
+                hp: hp.0,
+                max_hp: Some(*max_hp),
+                owner: owner.clone(),
+                companions: companion_definitions(entities, *id, &name.0, companions),
+            })
+            .collect()
+    }
+
+    /// `proposed` as is, or numbered if it's taken and `Settings::suffix_duplicates` is on. A taken
+    /// name that's kept gets a warning in the status bar instead.
+    fn dedupe_name(&mut self, proposed: String) -> String {
+        if !self.entities.iter().any(|e| e.name.0 == proposed) {
+            proposed
+        } else if self.settings.suffix_duplicates {
+            unique_name(&self.entities, &proposed, &self.settings)
+        } else {
+            self.status = Some(duplicate_warning(&proposed));
+            proposed
+        }
+    }
+
+    /// Puts `batch` into the order with one stable sort of just the batch, then each goes after
+    /// everyone it ties with (like `insertion_index`). So ties within the batch keep its order, and
+    /// everyone already there stays as they were, moved by hand or not, whoever's turn it is included.
+    fn insert_batch(entities: &mut Vec<Entity>, turn: &mut usize, mut batch: Vec<Entity>) {
+        batch.sort_by_key(|e| Reverse(e.initiative.0));
+        for entity in batch {
+            Self::insert_entity(entities, turn, entity);
+        }
+    }
+
+    /// Inserts `entity` after every entity whose initiative is greater than *or equal to* its own,
+    /// returning where it went.
+    ///
+    /// Ties therefore keep insertion order: an entity added later acts after those already at its
+    /// initiative. The one exception is DEX: one with an `initiative_bonus` goes ahead of those it
+    /// ties with that have a lower bonus, stopping at anyone without one. Loading inserts enemies in
+    /// their saved order with `insert_in_order`, so a save/load round trip reproduces the order on
+    /// screen, including any manual tie-breaking done with the arrows.
+    fn insert_entity(entities: &mut Vec<Entity>, turn: &mut usize, entity: Entity) -> usize {
+        let mut index = Self::insertion_index(entities, entity.initiative.0);
+        if let Some(bonus) = entity.initiative_bonus {
+            while index > 0
+                && entities[index - 1].initiative.0 == entity.initiative.0
+                && entities[index - 1].initiative_bonus.map_or(false, |other| other < bonus) {
+                index -= 1;
+            }
+        }
+        entities.insert(index, entity);
+        if *turn >= index {
+            *turn += 1;
+        }
+        index
+    }
+
+    /// `insert_entity` without the DEX tie-break, for entities that are being put in one at a time
+    /// in the order they should end up in
+    fn insert_in_order(entities: &mut Vec<Entity>, turn: &mut usize, mut entity: Entity) {
+        let bonus = entity.initiative_bonus.take();
+        let index = Self::insert_entity(entities, turn, entity);
+        entities[index].initiative_bonus = bonus;
+    }
 }
 
-fn main() {
-    if let Some("TARGET") = std::env::args().nth(1).as_deref() {
-        println!("{}", self_update::get_target());
-        return;
+/// The index in `order` to put something with `initiative`, see `InitiativeManager::insertion_index`
+fn insertion_index_by<T>(order: &[T], initiative: u32, initiative_of: impl Fn(&T) -> u32) -> usize {
+    order.iter()
+        .position(|t| initiative_of(t) < initiative)
+        .unwrap_or(order.len())
+}
+
+/// What's saved as the companions of the player `id` (named `name`), their `companions` plus anything
+/// else in the fight that they own, so a familiar added by hand with them as its owner is kept
+fn companion_definitions(entities: &[Entity], id: EntityId, name: &str, companions: &[Companion]) -> Vec<Companion> {
+    let owner_group = entities.iter().find(|e| e.id == id).and_then(|e| e.group);
+    let mut definitions = companions.to_vec();
+    let owned = entities.iter()
+        .filter(|e| e.companion_of.is_none() && e.owner.as_deref() == Some(name));
+    for entity in owned {
+        if definitions.iter().all(|c| c.name != entity.name.0) {
+            definitions.push(Companion {
+                name: entity.name.0.clone(),
+                hp: entity.hp_formula.clone().unwrap_or_else(|| entity.max_hp.to_string()),
+                shares_turn: entity.group.is_some() && entity.group == owner_group,
+                initiative_bonus: 0,
+            });
+        }
+    }
+    definitions
+}
+
+/// `proposed`, with a suffix if one of `existing` already has that name
+fn unique_name(existing: &[Entity], proposed: &str, settings: &Settings) -> String {
+    let taken = existing.iter()
+        .map(|e| e.name.0.clone())
+        .collect();
+    settings.suffixed_name(&taken, proposed)
+}
+
+fn duplicate_warning(name: &str) -> String {
+    format!("There's more than one {name}, careful who gets hit")
+}
+
+/// What `begin_turn` changes about an entity, so that `PrevTurn` can put it back
+struct TurnState {
+    id: EntityId,
+    reactions_left: u32,
+    legendary_actions: Option<u32>,
+    conditions: Vec<Condition>,
+    recharges: Vec<bool>,
+    acted: bool,
+    surprised: bool,
+}
+
+impl TurnState {
+    fn save(entity: &Entity) -> Self {
+        Self {
+            id: entity.id,
+            reactions_left: entity.reactions.0,
+            legendary_actions: entity.legendary_actions.map(|Hidden((_, left), _)| left),
+            conditions: entity.conditions.clone(),
+            recharges: entity.recharges.iter().map(|r| r.ready).collect(),
+            acted: entity.acted,
+            surprised: entity.surprised,
+        }
+    }
+
+    fn restore(self, entity: &mut Entity) {
+        entity.reactions.0 = self.reactions_left;
+        if let (Some(Hidden((_, left), _)), Some(saved)) = (&mut entity.legendary_actions, self.legendary_actions) {
+            *left = saved;
+        }
+        entity.conditions = self.conditions;
+        for (recharge, ready) in entity.recharges.iter_mut().zip(self.recharges) {
+            recharge.ready = ready;
+        }
+        entity.acted = self.acted;
+        entity.surprised = self.surprised;
+    }
+}
+
+/// What the DM picked for a monster that hit 0 HP with `AtZero::Prompt`
+#[derive(Debug, Copy, Clone)]
+pub enum ZeroChoice {
+    Remove,
+    KeepDead,
+    /// it was a miscount, they're hanging on
+    AtOne,
+}
+
+/// Whoever was just taken off the board and where they were, see `Message::UndoDelete`
+struct Removal {
+    /// in the order they were removed, summons after their owner
+    entities: Vec<(usize, Entity)>,
+    /// `remove_fallen` logs a `LogEvent::Removed`, which undoing takes back out
+    logged: bool,
+}
+
+/// The whole fight at the start of a round (or a turn), see `InitiativeManager::snapshots`
+struct Snapshot {
+    /// "Round 4 start"
+    label: String,
+    round: u32,
+    turn: usize,
+    entities: Vec<Entity>,
+}
+
+struct TurnUndo {
+    incoming: TurnState,
+    /// and whether they had acted before it, and their legendary actions left in case they came
+    /// back at the end of the turn
+    outgoing: (EntityId, bool, Option<u32>),
+    /// everyone who'd acted, if this turn started a new round and cleared them
+    cleared: Vec<EntityId>,
+}
+
+/// Upkeep for the start of `entity`'s turn: their reactions (and legendary actions, if that's when
+/// they come back) come back, timed conditions count down, and recharge abilities get rolled for.
+fn begin_turn(entity: &mut Entity, round: u32, legendary_reset: LegendaryReset) {
+    entity.reactions.0 = entity.reactions.1;
+    if legendary_reset == LegendaryReset::StartOfTurn {
+        refresh_legendary_actions(entity);
+    }
+    condition::tick(&mut entity.conditions, &mut entity.recharges, round);
+    entity.acted = false;
+}
+
+fn refresh_legendary_actions(entity: &mut Entity) {
+    if let Some(Hidden((tot, left), _)) = &mut entity.legendary_actions {
+        *left = *tot;
+    }
+}
+
+/// The marker at the start of a row, with what it means (if the DM said) on hover. `on_press` is
+/// `None` in the players' view, where it can only be looked at.
+fn tag_button<'a>(
+    state: &'a mut button::State,
+    tag: Option<TagColor>,
+    legend: &HashMap<TagColor, String>,
+    on_press: Option<Message>,
+    style: style::InitiativeTableStyle,
+) -> Element<'a, Message> {
+    let (icon, color, tooltip) = match tag {
+        Some(tag) => (
+            Icon::DiamondFill,
+            tag.color(style.style()),
+            legend.get(&tag).cloned().unwrap_or_else(|| format!("{tag} tag")),
+        ),
+        None => (Icon::Diamond, Color::from_rgb(0.5, 0.5, 0.5), String::from("Click to tag")),
+    };
+    Button::new(state, Text::new(icon).font(ICON_FONT).size(10).color(color))
+        .style(style)
+        .padding(0)
+        .tap_if_some(on_press, Button::on_press)
+        .tooltip(tooltip, Position::Top)
+        .size(10)
+        .into()
+}
+
+/// ex. `2d8 + 2`, `None` if it isn't a formula
+fn roll_hp(formula: &str) -> Option<u32> {
+    formula.parse::<Hp>().ok().and_then(Hp::into_number)
+}
+
+/// the top of the initiative sliders when loading a party, anything higher can still be typed
+const MAX_SLIDER_INITIATIVE: u32 = 30;
+
+/// window height in px below which the controls next to the table are packed tighter
+const SHORT_WINDOW: u32 = 800;
+
+/// how often the "since last turn" times are redrawn, they only show minutes
+const SINCE_TICK: Duration = Duration::from_secs(15);
+
+/// how long `Message::Peek` shows the secret stats for
+const PEEK: Duration = Duration::from_secs(5);
+/// how often to check if a peek is over
+const PEEK_TICK: Duration = Duration::from_millis(250);
+
+/// `just now`, `2m ago`, `1h 5m ago`
+fn time_since(elapsed: Duration) -> String {
+    let minutes = elapsed.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => String::from("just now"),
+        (0, m) => format!("{m}m ago"),
+        (h, m) => format!("{h}h {m}m ago"),
     }
+}
+
+/// `1st`, `2nd`, `3rd`, `4th`, ..., `11th`, ..., `21st`
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// d20 + `modifier`, but at least 0
+fn roll_initiative(modifier: i32) -> u32 {
+    let roll = rand::thread_rng().gen_range(1..=20);
+    std::cmp::max(0, roll + modifier) as u32
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mirror = match args.next().as_deref() {
+        Some("TARGET") => {
+            println!("{}", self_update::get_target());
+            return;
+        }
+        // no window, see `cli::run`
+        Some(command @ ("export" | "validate" | "roll")) => std::process::exit(cli::run(command, args)),
+        // ex. `initiative_manager mirror 192.168.1.20:8081`
+        Some("mirror") => Some(args.next().unwrap_or_else(|| format!("127.0.0.1:{}", sync::DEFAULT_PORT))),
+        _ => None,
+    };
 
     let mut size = iced::window::Settings::default().size;
     size.1 = (size.1 as f64 * 0.9) as _;
-    <InitiativeManager as iced::Application>::run(Settings {
+    let settings = Settings::load();
+    let decorations = !(settings.streaming && settings.streaming_borderless);
+    <InitiativeManager as iced::Application>::run(iced::Settings {
         antialiasing: true,
         default_font: Some(include_bytes!("../resources/arial.ttf")),
         window: iced::window::Settings {
             size,
             min_size: None,
             icon: None,
+            decorations,
+            // so a key color with alpha can actually see through
+            transparent: settings.streaming,
             ..Default::default()
         },
-        flags: size,
+        flags: (size.0, size.1, mirror),
+        // `Message::Exit` saves first
+        exit_on_close_request: false,
         ..Default::default()
     }).unwrap();
 }
@@ -1391,12 +5781,14 @@ pub enum UpdateState {
     Downloading(f32),
     UpToDate,
     Downloaded,
+    /// the download was stopped, this stays until the next launch so it doesn't start again
+    Cancelled,
     Errored(String),
 }
 
 impl UpdateState {
     #[must_use]
-    pub fn view(&self, style: SettingsBarStyle) -> Element<crate::Message> {
+    pub fn view<'a>(&self, cancel: &'a mut button::State, style: SettingsBarStyle) -> Element<'a, crate::Message> {
         const VER: &str = cargo_crate_version!();
         match self {
             &Self::Downloading(pct) => {
@@ -1408,6 +5800,11 @@ impl UpdateState {
                         .style(style)
                         .height(Length::Units(12)) // bottom bar is 20 pts
                         .width(Length::Units(100)))
+                    .push_space(5)
+                    .push(Button::new(cancel, Text::new("Cancel").size(10))
+                        .style(style)
+                        .padding(1)
+                        .on_press(crate::Message::Update(update::Message::Cancel)))
                     .into()
             }
             view_as_text => match view_as_text {
@@ -1415,9 +5812,496 @@ impl UpdateState {
                 Self::Ready => Text::new("Preparing to download..."),
                 Self::Downloaded => Text::new("Downloaded new version! Restart program to get new features!"),
                 Self::UpToDate => Text::new(format!("Up to date, v{}", VER)),
+                Self::Cancelled => Text::new(format!("Update cancelled. Running v{}", VER)),
                 Self::Errored(e) => Text::new(format!("Error downloading new version: {}. Running v{}", e, VER)),
                 Self::Downloading(_) => unreachable!(),
             }.size(10).into()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> InitiativeManager {
+        InitiativeManager::with_settings(Settings::default(), Default::default(), (800, 600, None))
+    }
+
+    fn entity(name: &str, initiative: u32) -> Entity {
+        Entity::new(String::from(name).into(), 10.into(), initiative.into())
+    }
+
+    fn names(manager: &InitiativeManager) -> Vec<&str> {
+        manager.entities.iter().map(|e| e.name.0.as_str()).collect()
+    }
+
+    fn current(manager: &InitiativeManager) -> &str {
+        &manager.entities[manager.turn % manager.entities.len()].name.0
+    }
+
+    #[test]
+    fn add_entity_sorts_by_initiative() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin", 12));
+        manager.add_entity(entity("Wizard", 18));
+        manager.add_entity(entity("Fighter", 15));
+        assert_eq!(names(&manager), ["Wizard", "Fighter", "Goblin"]);
+    }
+
+    #[test]
+    fn equal_initiatives_keep_the_order_they_were_added_in() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin 1", 14));
+        manager.add_entity(entity("Goblin 2", 14));
+        manager.add_entity(entity("Goblin 3", 14));
+        assert_eq!(names(&manager), ["Goblin 1", "Goblin 2", "Goblin 3"]);
+    }
+
+    #[test]
+    fn add_entity_keeps_whose_turn_it_is() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin", 12));
+        manager.add_entity(entity("Wizard", 18));
+        manager.add_entity(entity("Fighter", 15));
+        // before the first turn, it's the top of the order
+        assert_eq!(current(&manager), "Wizard");
+
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(current(&manager), "Fighter");
+        manager.add_entity(entity("Rogue", 20));
+        assert_eq!(current(&manager), "Fighter");
+        assert_eq!(manager.turn, 2);
+        manager.add_entity(entity("Kobold", 3));
+        assert_eq!(current(&manager), "Fighter");
+        assert_eq!(names(&manager), ["Rogue", "Wizard", "Fighter", "Goblin", "Kobold"]);
+    }
+
+    #[test]
+    fn advance_turn_goes_down_the_order_and_wraps_into_the_next_round() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin", 12));
+        manager.add_entity(entity("Wizard", 18));
+        manager.add_entity(entity("Fighter", 15));
+
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!((current(&manager), manager.round), ("Fighter", 1));
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!((current(&manager), manager.round), ("Goblin", 1));
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!((current(&manager), manager.round), ("Wizard", 2));
+        assert_eq!(manager.turn, 0);
+    }
+
+    #[test]
+    fn insert_batch_keeps_tied_players_in_order_and_leaves_everyone_else() {
+        let mut manager = manager();
+        manager.add_entity(entity("Wizard", 20));
+        manager.add_entity(entity("Goblin", 15));
+        manager.add_entity(entity("Orc", 15));
+        // moved past the goblin by hand
+        manager.entities.swap(1, 2);
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(current(&manager), "Orc");
+
+        let batch = vec![entity("Cleric", 15), entity("Bard", 18), entity("Paladin", 15)];
+        InitiativeManager::insert_batch(&mut manager.entities, &mut manager.turn, batch);
+        assert_eq!(names(&manager), ["Wizard", "Bard", "Orc", "Goblin", "Cleric", "Paladin"]);
+        assert_eq!(current(&manager), "Orc");
+    }
+
+    #[test]
+    fn begin_turn_does_the_upkeep() {
+        let mut goblin = entity("Goblin", 12);
+        goblin.reactions = (0, 1);
+        goblin.legendary_actions = Some(Hidden((3, 0), false));
+        goblin.conditions.push(Condition::parse("Poisoned 1", 1).unwrap());
+        goblin.conditions.push(Condition::parse("Prone", 1).unwrap());
+        goblin.acted = true;
+
+        begin_turn(&mut goblin, 2, LegendaryReset::StartOfTurn);
+        assert_eq!(goblin.reactions, (1, 1));
+        assert_eq!(goblin.legendary_actions.map(|Hidden(la, _)| la), Some((3, 3)));
+        assert_eq!(goblin.conditions.iter().map(|c| c.name.as_str()).collect_vec(), ["Prone"]);
+        assert!(!goblin.acted);
+    }
+
+    #[test]
+    fn begin_turn_leaves_legendary_actions_for_the_end_of_turn() {
+        let mut dragon = entity("Dragon", 20);
+        dragon.legendary_actions = Some(Hidden((3, 1), false));
+        begin_turn(&mut dragon, 2, LegendaryReset::EndOfTurn);
+        assert_eq!(dragon.legendary_actions.map(|Hidden(la, _)| la), Some((3, 1)));
+    }
+
+    #[test]
+    fn load_preview_has_a_row_in_every_column_for_every_enemy() {
+        let mut dragon = entity("Dragon", 20);
+        dragon.legendary_actions = Some(Hidden((3, 3), false));
+        let goblin = entity("Goblin", 12);
+        // roman numerals have no zero
+        let mut lich = entity("Lich", 15);
+        lich.legendary_actions = Some(Hidden((0, 0), true));
+        let entities = [dragon, goblin, lich];
+        let rows = entities.iter()
+            .map(|e| SaveRow { id: e.id, name: e.name.0.clone(), hp: e.hp.0, initiative: e.initiative.0, included: true })
+            .collect_vec();
+        let enemies = InitiativeManager::enemies(&entities, &rows);
+
+        for display in LegendaryDisplay::ALL {
+            let columns = SaveMode::preview_columns(&enemies, &LoadAdjustments::default(), display, Style::default());
+            // each has its heading too
+            assert_eq!(columns.map(|column| column.len()), [4; 4]);
+        }
+    }
+
+    #[test]
+    fn load_preview_places_a_templates_copies_at_their_own_rolls() {
+        let mut manager = manager();
+        manager.add_entity(entity("Wizard", 20));
+        let mut goblin: Enemy = serde_json::from_value(serde_json::json!({
+            "name": ["Goblin", false], "hp": [7, false], "legendary_actions": null, "initiative": [10, false],
+        })).unwrap();
+        goblin.copy_initiatives = vec![25];
+        let mut rows = vec![goblin];
+        // more copies roll for the new ones and keep the first's
+        LoadAdjustments::roll_copies(&mut rows, 2);
+        assert_eq!(rows[0].copy_initiatives.len(), 2);
+        assert_eq!(rows[0].copy_initiative(1), 25);
+        rows[0].copy_initiatives.truncate(1);
+
+        let adjust = LoadAdjustments {
+            copies: TextInputState { content: String::from("1"), ..Default::default() },
+            template: true,
+            ..Default::default()
+        };
+        manager.save_mode = SaveMode::LoadEncounter(String::from("Ambush"), Default::default(), Default::default(), rows, true, Default::default(), adjust, String::new());
+        assert_eq!(manager.load_preview().unwrap(), ["+2 new creatures", "Goblin goes 1st (25), 3rd (10)"]);
+    }
+
+    #[test]
+    fn tab_with_nothing_focused_starts_at_either_end() {
+        let mut manager = manager();
+        manager.focus_next(true);
+        assert!(manager.new_entity.name.0.state.is_focused());
+        manager.focus_next(true);
+        assert!(!manager.new_entity.name.0.state.is_focused());
+        assert!(manager.new_entity.init.0.state.is_focused());
+
+        let mut manager = self::manager();
+        manager.focus_next(false);
+        assert!(manager.server_port.state.is_focused());
+        assert!(!manager.new_entity.name.0.state.is_focused());
+    }
+
+    #[test]
+    fn activate_does_nothing_while_typing_in_the_encounter_notes() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin", 10));
+        manager.keyboard_focus = Some(Control::NextTurn);
+        assert!(matches!(manager.activate(), Some(Message::NextTurn)));
+
+        manager.save_mode = SaveMode::SaveEncounter(
+            TextInputState::default(),
+            TextInputState::focused(),
+            TextInputState::default(),
+            false,
+            Default::default(),
+            Default::default(),
+            Vec::new(),
+        );
+        assert!(manager.activate().is_none());
+        assert_eq!(manager.turn, 0);
+    }
+
+    #[test]
+    fn nudge_hp_does_nothing_while_typing_a_reminder() {
+        let mut manager = manager();
+        manager.add_entity(entity("Goblin", 10));
+        manager.nudge_hp(-1, &mut Vec::new());
+        assert_eq!(manager.entities[0].hp.0, 9);
+
+        manager.reminders.open = true;
+        manager.reminders.text = TextInputState::focused();
+        manager.nudge_hp(-1, &mut Vec::new());
+        manager.nudge_hp(1, &mut Vec::new());
+        assert_eq!(manager.entities[0].hp.0, 9);
+    }
+
+    fn legendary_fight(reset: LegendaryReset) -> InitiativeManager {
+        let mut manager = manager();
+        manager.settings.legendary_reset = reset;
+        let mut dragon = entity("Dragon", 20);
+        dragon.legendary_actions = Some(Hidden((3, 3), false));
+        manager.add_entity(dragon);
+        manager.add_entity(entity("Fighter", 15));
+        manager.add_entity(entity("Goblin", 10));
+        manager
+    }
+
+    fn legendary_left(manager: &InitiativeManager) -> u32 {
+        manager.entities[0].legendary_actions.unwrap().0.1
+    }
+
+    fn spend_legendary(manager: &mut InitiativeManager, actions: u32) {
+        if let Some(Hidden((_, left), _)) = &mut manager.entities[0].legendary_actions {
+            *left -= actions;
+        }
+    }
+
+    #[test]
+    fn legendary_actions_come_back_at_the_start_of_their_turn() {
+        let mut manager = legendary_fight(LegendaryReset::StartOfTurn);
+        // the dragon's turn
+        spend_legendary(&mut manager, 1);
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(legendary_left(&manager), 2);
+        spend_legendary(&mut manager, 1);
+        manager.advance_turn(&mut Vec::new());
+        spend_legendary(&mut manager, 1);
+        assert_eq!(legendary_left(&manager), 0);
+        // round 2, the dragon's turn again
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!((current(&manager), manager.round), ("Dragon", 2));
+        assert_eq!(legendary_left(&manager), 3);
+    }
+
+    #[test]
+    fn legendary_actions_come_back_at_the_end_of_their_turn() {
+        let mut manager = legendary_fight(LegendaryReset::EndOfTurn);
+        // the dragon's turn
+        spend_legendary(&mut manager, 1);
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(legendary_left(&manager), 3);
+        spend_legendary(&mut manager, 2);
+        manager.advance_turn(&mut Vec::new());
+        spend_legendary(&mut manager, 1);
+        // round 2, the dragon's turn again, still spent
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!((current(&manager), manager.round), ("Dragon", 2));
+        assert_eq!(legendary_left(&manager), 0);
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(legendary_left(&manager), 3);
+    }
+
+    /// A player and a monster tied at 15 (the monster added second unless `monster_first`), settled by `rule`
+    fn settle_tie(rule: TieRule, monster_first: bool) -> (InitiativeManager, Vec<u32>) {
+        let mut manager = manager();
+        manager.settings.tie_rule = rule;
+        manager.add_entity(entity("Wizard", 20));
+        let mut fighter = entity("Fighter", 15);
+        fighter.disposition = Disposition::Ally;
+        let mut goblin = entity("Goblin", 15);
+        goblin.disposition = Disposition::Enemy;
+        let added = if monster_first { (goblin, fighter) } else { (fighter, goblin) };
+        manager.add_entity(added.0);
+        let id = added.1.id;
+        manager.add_entity(added.1);
+        let resolved = manager.resolve_ties(&[id]);
+        (manager, resolved)
+    }
+
+    #[test]
+    fn manual_ties_are_left_to_be_asked_about() {
+        let (manager, resolved) = settle_tie(TieRule::Manual, true);
+        assert!(resolved.is_empty());
+        assert_eq!(names(&manager), ["Wizard", "Goblin", "Fighter"]);
+    }
+
+    #[test]
+    fn players_first_puts_the_player_ahead() {
+        for monster_first in [true, false] {
+            let (manager, resolved) = settle_tie(TieRule::PlayersFirst, monster_first);
+            assert_eq!(resolved, [15]);
+            assert_eq!(names(&manager), ["Wizard", "Fighter", "Goblin"]);
+        }
+    }
+
+    #[test]
+    fn monsters_first_puts_the_monster_ahead() {
+        for monster_first in [true, false] {
+            let (manager, resolved) = settle_tie(TieRule::MonstersFirst, monster_first);
+            assert_eq!(resolved, [15]);
+            assert_eq!(names(&manager), ["Wizard", "Goblin", "Fighter"]);
+        }
+    }
+
+    #[test]
+    fn roll_off_settles_it_and_says_what_was_rolled() {
+        let (manager, resolved) = settle_tie(TieRule::RollOff, false);
+        assert_eq!(resolved, [15]);
+        assert_eq!(manager.entities[0].name.0, "Wizard");
+        let mut tied = names(&manager)[1..].to_vec();
+        tied.sort_unstable();
+        assert_eq!(tied, ["Fighter", "Goblin"]);
+        assert!(manager.status.as_deref().map_or(false, |status| status.starts_with("Roll-off: ")));
+    }
+
+    #[test]
+    fn settling_a_tie_keeps_whose_turn_it_is() {
+        let (mut manager, _) = settle_tie(TieRule::Manual, true);
+        manager.advance_turn(&mut Vec::new());
+        assert_eq!(current(&manager), "Goblin");
+        manager.settings.tie_rule = TieRule::PlayersFirst;
+        let id = manager.entities[2].id;
+        manager.resolve_ties(&[id]);
+        assert_eq!(names(&manager), ["Wizard", "Fighter", "Goblin"]);
+        assert_eq!(current(&manager), "Goblin");
+    }
+
+    /// `up_down` worked out from scratch
+    fn assert_adjacency(manager: &InitiativeManager) {
+        let entities = &manager.entities;
+        let fresh = (0..entities.len())
+            .map(|i| {
+                let tied = |j: usize| entities.get(j).map_or(false, |e: &Entity| e.initiative.0 == entities[i].initiative.0);
+                [i.checked_sub(1).map_or(false, tied), tied(i + 1)]
+            })
+            .collect_vec();
+        assert_eq!(manager.up_down, fresh, "for {:?}", names(manager));
+    }
+
+    #[test]
+    fn adjacency_is_kept_up_to_date() {
+        let mut manager = manager();
+        assert_adjacency(&manager);
+        for (name, initiative) in [("Wizard", 18), ("Goblin 1", 12), ("Goblin 2", 12), ("Fighter", 15), ("Goblin 3", 12)] {
+            manager.add_entity(entity(name, initiative));
+            assert_adjacency(&manager);
+        }
+        manager.swap_initiative(0, 2);
+        assert_adjacency(&manager);
+        manager.remove_entity(1);
+        assert_adjacency(&manager);
+
+        let rogue = entity("Rogue", 12);
+        let id = rogue.id;
+        manager.add_entity(rogue);
+        manager.ask_about_ties(&[id]);
+        manager.place_tie(true);
+        assert_adjacency(&manager);
+
+        manager.entities.iter_mut().for_each(|e| e.disposition = Disposition::Enemy);
+        manager.settings.tie_rule = TieRule::PlayersFirst;
+        let mut paladin = entity("Paladin", 12);
+        paladin.disposition = Disposition::Ally;
+        let id = paladin.id;
+        manager.add_entity(paladin);
+        manager.resolve_ties(&[id]);
+        assert_adjacency(&manager);
+
+        while !manager.entities.is_empty() {
+            manager.remove_entity(0);
+            assert_adjacency(&manager);
+        }
+    }
+
+    #[test]
+    fn heal_party_heals_every_ally() {
+        let mut manager = manager();
+        let mut loaded = entity("Fighter", 15);
+        loaded.origin = Some(Origin::Party);
+        loaded.disposition = Disposition::Ally;
+        // typed in by hand
+        let mut typed = entity("Cleric", 12);
+        typed.disposition = Disposition::Ally;
+        let goblin = entity("Goblin", 10);
+        for mut entity in [loaded, typed, goblin] {
+            entity.hp.0 = 3;
+            manager.add_entity(entity);
+        }
+        manager.heal_party();
+        assert_eq!(manager.entities.iter().map(|e| e.hp.0).collect_vec(), [10, 10, 3]);
+        assert_eq!(manager.status.as_deref(), Some("Healed 2 to full"));
+    }
+
+    #[test]
+    fn an_ally_typed_in_by_hand_is_left_to_make_death_saves() {
+        let mut manager = manager();
+        let mut cleric = entity("Cleric", 12);
+        cleric.disposition = Disposition::Ally;
+        manager.add_entity(cleric);
+        manager.add_entity(entity("Goblin", 10));
+        for at_zero in [AtZero::KeepDead, AtZero::Remove, AtZero::Prompt] {
+            manager.settings.at_zero = at_zero;
+            manager.damage(0, 10, &mut Vec::new());
+            let cleric = &manager.entities[0];
+            assert_eq!((cleric.name.0.as_str(), cleric.hp.0), ("Cleric", 0));
+            assert!(!cleric.dead && !cleric.zero_prompt);
+            manager.entities[0].hp.0 = 10;
+        }
+        manager.damage(1, 10, &mut Vec::new());
+        assert!(manager.entities[1].zero_prompt);
+    }
+
+    #[test]
+    fn negative_amounts_are_logged_the_way_hp_went() {
+        let mut manager = manager();
+        let mut goblin = entity("Goblin", 10);
+        goblin.hp.0 = 2;
+        manager.add_entity(goblin);
+        manager.damage(0, -5, &mut Vec::new());
+        manager.heal(0, -3);
+        let totals = report::totals(&manager.combat_log);
+        assert_eq!((totals[0].damage_taken, totals[0].healing), (3, 5));
+        let goblin = &manager.entities[0];
+        assert_eq!((goblin.damage_taken, goblin.healing_received), (3, 5));
+    }
+
+    fn with_bonus(name: &str, initiative: u32, bonus: Option<i32>) -> Entity {
+        let mut entity = entity(name, initiative);
+        entity.initiative_bonus = bonus;
+        entity.disposition = Disposition::Enemy;
+        entity
+    }
+
+    #[test]
+    fn ties_go_by_initiative_bonus_then_insertion_order() {
+        let mut manager = manager();
+        manager.add_entity(with_bonus("Goblin", 15, Some(1)));
+        manager.add_entity(with_bonus("Scout", 15, Some(3)));
+        manager.add_entity(with_bonus("Bandit", 15, Some(2)));
+        assert_eq!(names(&manager), ["Scout", "Bandit", "Goblin"]);
+        // no bonus, so it just goes last
+        manager.add_entity(with_bonus("Zombie", 15, None));
+        // and nobody can get past it
+        manager.add_entity(with_bonus("Assassin", 15, Some(5)));
+        manager.add_entity(with_bonus("Orc", 15, Some(1)));
+        assert_eq!(names(&manager), ["Scout", "Bandit", "Goblin", "Zombie", "Assassin", "Orc"]);
+    }
+
+    #[test]
+    fn save_then_load_keeps_the_order_on_screen() {
+        let mut manager = manager();
+        manager.add_entity(with_bonus("Wizard", 18, None));
+        manager.add_entity(with_bonus("Goblin", 15, Some(2)));
+        manager.add_entity(with_bonus("Orc", 15, Some(0)));
+        manager.add_entity(with_bonus("Kobold", 15, None));
+        manager.add_entity(with_bonus("Rat", 4, None));
+        // against the DEX tie-break, which loading mustn't undo
+        manager.entities.swap(1, 2);
+        let before = names(&manager).join(", ");
+
+        let rows = SaveRow::all(&manager.entities, true);
+        let json = serde_json::to_string(&EncounterFile {
+            version: ENCOUNTER_VERSION,
+            enemies: InitiativeManager::enemies(&manager.entities, &rows),
+            notes: String::new(),
+            template: false,
+            lair_actions: String::new(),
+        }).unwrap();
+        let mut enemies = EncounterFile::from_reader(json.as_bytes()).unwrap().enemies;
+        // the saved `order` is what counts, not where they are in the file
+        enemies.reverse();
+        sort_for_loading(&mut enemies);
+
+        let mut loaded = self::manager();
+        for enemy in enemies {
+            let mut entity = Entity::new(enemy.name, enemy.hp, enemy.initiative);
+            entity.initiative_bonus = enemy.initiative_bonus;
+            InitiativeManager::insert_in_order(&mut loaded.entities, &mut loaded.turn, entity);
+        }
+        assert_eq!(names(&loaded).join(", "), before);
+        assert_eq!(before, "Wizard, Orc, Goblin, Kobold, Rat");
+        assert_eq!(loaded.entities[2].initiative_bonus, Some(2));
+    }
+}
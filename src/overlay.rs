@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::utils::write_atomic;
+
+/// turn changes within this long of each other only write the last one
+pub const DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub const DEFAULT_TEMPLATE: &str = "Round {round} — {name}";
+
+/// How many upcoming combatants go in the second file
+const UP_NEXT: usize = 3;
+
+/// The text files an OBS text source can read, `next` is the optional "up next" file
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub path: PathBuf,
+    pub text: String,
+    pub next: Option<(PathBuf, String)>,
+}
+
+impl Overlay {
+    /// `names` is the player-safe order, starting from whoever's turn it is
+    pub fn new(path: &str, template: &str, next_path: &str, round: u32, names: &[String]) -> Result<Self, String> {
+        let path = validate(path)?;
+        let name = names.first().map_or("", String::as_str);
+        let text = render(template, round, name)?;
+        let next = if next_path.is_empty() {
+            None
+        } else {
+            let next = names.iter()
+                .skip(1)
+                .take(UP_NEXT)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some((validate(next_path)?, next))
+        };
+        Ok(Self { path, text, next })
+    }
+}
+
+/// Off the ui thread, since the file could be somewhere slow like a network drive
+pub async fn write(overlay: Overlay) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        write_atomic(&overlay.path, overlay.text)
+            .map_err(|e| format!("{}: {e}", overlay.path.display()))?;
+        if let Some((path, next)) = overlay.next {
+            write_atomic(&path, next)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        Ok(())
+    }).await.map_err(|e| e.to_string())?
+}
+
+fn validate(path: &str) -> Result<PathBuf, String> {
+    if path.trim().is_empty() {
+        return Err(String::from("no overlay file chosen"));
+    }
+    let path = PathBuf::from(path.trim());
+    if path.is_dir() {
+        return Err(format!("{} is a folder, not a file", path.display()));
+    }
+    match path.parent() {
+        Some(parent) if parent != Path::new("") && !parent.is_dir() => Err(format!("{} doesn't exist", parent.display())),
+        _ => Ok(path),
+    }
+}
+
+/// Fills in `{round}` and `{name}`, `{{` and `}}` are literal braces
+fn render(template: &str, round: u32, name: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(String::from("unclosed { in the overlay template")),
+                    }
+                }
+                match placeholder.as_str() {
+                    "round" => out.push_str(&round.to_string()),
+                    "name" => out.push_str(name),
+                    other => return Err(format!("unknown placeholder {{{other}}} in the overlay template")),
+                }
+            }
+            '}' => return Err(String::from("unmatched } in the overlay template")),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
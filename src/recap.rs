@@ -0,0 +1,61 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::SAVE_DIR;
+use crate::utils::write_atomic;
+
+/// How someone's fight went, by their real name since the recap is only for the DM
+#[derive(Debug, Clone)]
+pub struct Combatant {
+    pub name: String,
+    /// damage done during their turn, the best guess for who did it
+    pub damage_dealt: u64,
+    pub damage_taken: u64,
+    pub healing: u64,
+    /// at the end, or when they were removed
+    pub hp: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recap {
+    pub rounds: u32,
+    /// everyone who was on the board at the end, then anyone removed at 0 HP along the way
+    pub combatants: Vec<Combatant>,
+}
+
+impl Recap {
+    fn text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "# Combat Recap\n");
+        let _ = writeln!(text, "Rounds: {}\n", self.rounds);
+
+        let dead = self.combatants.iter()
+            .filter(|c| c.hp == 0)
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        if dead.is_empty() {
+            text.push_str("Nobody died.\n\n");
+        } else {
+            let _ = writeln!(text, "Died: {}\n", dead.join(", "));
+        }
+
+        text.push_str("| Name | Damage Dealt | Damage Taken | Healing |\n| --- | --- | --- | --- |\n");
+        for Combatant { name, damage_dealt, damage_taken, healing, .. } in &self.combatants {
+            let _ = writeln!(text, "| {} | {damage_dealt} | {damage_taken} | {healing} |", name.replace('|', "\\|"));
+        }
+        text
+    }
+}
+
+/// Writes `recap` to its own file in `SAVE_DIR/recaps`, returning its path
+pub async fn save(recap: Recap) -> Result<PathBuf, String> {
+    let name = chrono::Local::now().format("%Y-%m-%d %H-%M-%S.md").to_string();
+    tokio::task::spawn_blocking(move || {
+        let dir = SAVE_DIR.join("recaps");
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        let path = dir.join(name);
+        write_atomic(&path, recap.text()).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(path)
+    }).await.map_err(|e| e.to_string())?
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use iced::{Align, Button, button, Column, Element, Length, pick_list, PickList, Row, Text};
+
+use crate::EntityId;
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap, TextInputState};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Toggle,
+    Owner(Pick),
+    On(Pick),
+    Text(String),
+    Add,
+    /// just until the next turn
+    Dismiss(usize),
+    Delete(usize),
+}
+
+/// Whose turns a reminder comes up on
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Trigger {
+    /// everyone's but the owner's, ex. Spirit Guardians
+    Everyone,
+    Entity(EntityId),
+}
+
+/// An entity in one of the menus, `None` being "everyone" in the trigger menu
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Pick {
+    pub id: Option<EntityId>,
+    pub name: String,
+}
+
+impl Display for Pick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Something one entity's effect does on other entities' turns ("Spirit Guardians, WIS save"),
+/// shown under the active row when their turn comes
+#[derive(Debug)]
+pub struct Reminder {
+    /// whose effect it is, the reminder goes when they die or leave the fight
+    pub owner: EntityId,
+    pub trigger: Trigger,
+    pub text: String,
+    pub dismissed: bool,
+    dismiss: button::State,
+    delete: button::State,
+}
+
+impl Reminder {
+    pub fn due(&self, turn: EntityId) -> bool {
+        !self.dismissed && match self.trigger {
+            Trigger::Everyone => turn != self.owner,
+            Trigger::Entity(id) => turn == id,
+        }
+    }
+}
+
+/// The reminders menu under the bulk actions, the reminders themselves are shown with the table
+#[derive(Debug, Default)]
+pub struct Reminders {
+    pub open: bool,
+    toggle: button::State,
+    pub list: Vec<Reminder>,
+    pub owner: Option<Pick>,
+    owner_list: pick_list::State<Pick>,
+    pub on: Option<Pick>,
+    on_list: pick_list::State<Pick>,
+    pub text: TextInputState,
+    add: button::State,
+}
+
+impl Reminders {
+    pub fn everyone() -> Pick {
+        Pick { id: None, name: String::from("Everyone's turn") }
+    }
+
+    /// Adds what's been filled in, if it's all there
+    pub fn add(&mut self) -> bool {
+        let text = self.text.content.trim();
+        let owner = self.owner.as_ref().and_then(|pick| pick.id);
+        match (owner, &self.on) {
+            (Some(owner), Some(on)) if !text.is_empty() => {
+                self.list.push(Reminder {
+                    owner,
+                    trigger: on.id.map_or(Trigger::Everyone, Trigger::Entity),
+                    text: text.to_string(),
+                    dismissed: false,
+                    dismiss: Default::default(),
+                    delete: Default::default(),
+                });
+                self.text.content.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The menu, and the reminders due on `turn`'s turn to go under their row. `entities` are
+    /// everyone in the fight, `names` are everyone's names (sidelined too) as whoever's watching
+    /// should see them.
+    pub fn view(
+        &mut self,
+        style: Style,
+        entities: Vec<Pick>,
+        names: &HashMap<EntityId, String>,
+        turn: Option<EntityId>,
+        size: u16,
+    ) -> (Element<crate::Message>, Option<Element<crate::Message>>) {
+        let msg = crate::Message::Reminder;
+        let can_add = self.owner.is_some() && self.on.is_some() && !self.text.content.trim().is_empty();
+        let Self { open, toggle, list, owner, owner_list, on, on_list, text, add } = self;
+        let name = |id: EntityId| names.get(&id).map_or("?", String::as_str);
+
+        let toggle = Button::new(toggle, Text::new(if *open { "Hide reminders" } else { "Reminders" }).size(12))
+            .style(style)
+            .on_press(msg(Message::Toggle));
+        let mut menu = Column::new()
+            .align_items(Align::Center)
+            .push(toggle);
+        if *open {
+            let triggers = std::iter::once(Self::everyone())
+                .chain(entities.iter().cloned())
+                .collect();
+            menu = menu.push_space(6)
+                .push(Row::new()
+                    .align_items(Align::Center)
+                    .push(PickList::new(
+                        owner_list,
+                        entities,
+                        owner.clone().or_else(|| Some(Pick { id: None, name: String::from("Whose") })),
+                        move |pick| msg(Message::Owner(pick)),
+                    ).style(style)
+                        .text_size(14))
+                    .push_space(6)
+                    .push(Text::new("on").size(14))
+                    .push_space(6)
+                    .push(PickList::new(
+                        on_list,
+                        triggers,
+                        on.clone().or_else(|| Some(Pick { id: None, name: String::from("Whose turn") })),
+                        move |pick| msg(Message::On(pick)),
+                    ).style(style)
+                        .text_size(14)))
+                .push_space(6)
+                .push(Row::new()
+                    .align_items(Align::Center)
+                    .push(text.text_input("Spirit Guardians, WIS save", move |text| msg(Message::Text(text)))
+                        .style(style)
+                        .size(14)
+                        .width(Length::Units(180))
+                        .tap_if(can_add, |txt| txt.on_submit(msg(Message::Add))))
+                    .push_space(6)
+                    .push(Button::new(add, Text::new("Add").size(14))
+                        .style(style)
+                        .tap_if(can_add, |btn| btn.on_press(msg(Message::Add)))));
+        }
+
+        let mut due = Column::new();
+        let mut any_due = false;
+        for (i, reminder) in list.iter_mut().enumerate() {
+            let is_due = turn.map_or(false, |turn| reminder.due(turn));
+            let Reminder { owner, trigger, text, dismissed: _, dismiss, delete } = reminder;
+            if is_due {
+                any_due = true;
+                due = due.push(Row::new()
+                    .align_items(Align::Center)
+                    .push(Text::new(format!("⟳ {text} ({})", name(*owner))).size(size))
+                    .push_space(6)
+                    .push(Button::new(dismiss, Text::new("dismiss").size(size))
+                        .style(style)
+                        .padding(2)
+                        .on_press(msg(Message::Dismiss(i)))));
+            }
+            if *open {
+                let on = match *trigger {
+                    Trigger::Everyone => "everyone",
+                    Trigger::Entity(id) => name(id),
+                };
+                menu = menu.push_space(4)
+                    .push(Row::new()
+                        .align_items(Align::Center)
+                        .push(Text::new(format!("{}: {text} (on {on})", name(*owner))).size(12))
+                        .push_space(6)
+                        .push(Button::new(delete, Text::new("×").size(12))
+                            .style(style)
+                            .padding(2)
+                            .on_press(msg(Message::Delete(i)))));
+            }
+        }
+
+        (menu.into(), any_due.then(|| due.into()))
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::{self, Display, Write};
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::utils::write_atomic;
+
+pub const DEFAULT_FILE_NAME: &str = "{date} {encounter}.md";
+
+/// Something that happened to an entity, by its real name since the log is only for the DM
+#[derive(Debug, Clone)]
+pub enum LogEvent {
+    Damage { name: String, amount: i64, hp: u32 },
+    Heal { name: String, amount: i64, hp: u32 },
+    Removed { name: String },
+    /// with the initiatives they ended up with
+    SwappedInitiative { first: String, first_initiative: u32, second: String, second_initiative: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub round: u32,
+    pub event: LogEvent,
+}
+
+impl Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Damage { name, amount, hp } => write!(f, "{name} took {amount} damage ({hp} HP left)"),
+            Self::Heal { name, amount, hp } => write!(f, "{name} healed {amount} ({hp} HP)"),
+            Self::Removed { name } => write!(f, "{name} was removed"),
+            Self::SwappedInitiative { first, first_initiative, second, second_initiative } => write!(
+                f,
+                "{first} ({first_initiative}) and {second} ({second_initiative}) swapped initiative",
+            ),
+        }
+    }
+}
+
+/// An entity in the initiative table, hidden or not
+#[derive(Debug, Clone)]
+pub struct ReportRow {
+    pub name: String,
+    pub initiative: u32,
+    pub hp: u32,
+    pub max_hp: u32,
+    pub ac: Option<u32>,
+}
+
+/// Everything that goes in a session report, see `markdown`
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub encounter: String,
+    /// ex. `2022-03-14`
+    pub date: String,
+    pub round: u32,
+    pub rows: Vec<ReportRow>,
+    pub log: Vec<LogEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// needs the user's go ahead before it's replaced
+    Exists(PathBuf),
+    Other(String),
+}
+
+/// Writes `report` to `dir`, named by `file_name`. Refuses to replace a file unless it's `overwrite`,
+/// which is the path the user already agreed to replace.
+pub async fn write(report: Report, dir: String, file_name: String, overwrite: Option<PathBuf>) -> Result<PathBuf, Error> {
+    tokio::task::spawn_blocking(move || {
+        let dir = dir.trim();
+        if dir.is_empty() {
+            return Err(Error::Other(String::from("no report folder chosen")));
+        }
+        let dir = Path::new(dir);
+        if !dir.is_dir() {
+            return Err(Error::Other(format!("{} isn't a folder", dir.display())));
+        }
+        let path = dir.join(render_file_name(&file_name, &report.date, &report.encounter).map_err(Error::Other)?);
+        if path.exists() && overwrite.as_ref() != Some(&path) {
+            return Err(Error::Exists(path));
+        }
+        write_atomic(&path, markdown(&report))
+            .map_err(|e| Error::Other(format!("{}: {e}", path.display())))?;
+        Ok(path)
+    }).await.map_err(|e| Error::Other(e.to_string()))?
+}
+
+/// Fills in `{date}` and `{encounter}`, with anything that can't go in a file name replaced
+fn render_file_name(template: &str, date: &str, encounter: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}')
+            .ok_or_else(|| String::from("unclosed { in the report file name"))?;
+        match &rest[start + 1..start + end] {
+            "date" => out.push_str(date),
+            "encounter" => out.push_str(encounter),
+            other => return Err(format!("unknown placeholder {{{other}}} in the report file name")),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    let name = out.chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '-' } else { c })
+        .collect::<String>();
+    if name.trim().is_empty() {
+        Err(String::from("the report file name is empty"))
+    } else {
+        Ok(name.trim().to_string())
+    }
+}
+
+fn markdown(report: &Report) -> String {
+    let Report { encounter, date, round, rows, log } = report;
+    let mut md = String::new();
+    let _ = writeln!(md, "# {encounter}\n");
+    let _ = writeln!(md, "- Date: {date}");
+    let _ = writeln!(md, "- Rounds: {round}\n");
+
+    md.push_str("## Initiative\n\n| Name | Initiative | HP | AC |\n| --- | --- | --- | --- |\n");
+    for ReportRow { name, initiative, hp, max_hp, ac } in rows {
+        let ac = ac.map_or_else(String::new, |ac| ac.to_string());
+        let _ = writeln!(md, "| {} | {initiative} | {hp}/{max_hp} | {ac} |", escape(name));
+    }
+
+    md.push_str("\n## Combat Log\n");
+    if log.is_empty() {
+        md.push_str("\nNothing happened.\n");
+    }
+    for (round, entries) in &log.iter().group_by(|entry| entry.round) {
+        let _ = writeln!(md, "\n### Round {round}\n");
+        for entry in entries {
+            let _ = writeln!(md, "- {}", entry.event);
+        }
+    }
+
+    md.push_str("\n## Stats\n\n| Name | Damage Taken | Healing |\n| --- | --- | --- |\n");
+    for Totals { name, damage_taken, healing } in totals(log) {
+        let _ = writeln!(md, "| {} | {damage_taken} | {healing} |", escape(&name));
+    }
+    md
+}
+
+/// How much damage and healing an entity got over the whole log
+#[derive(Debug, Clone, Serialize)]
+pub struct Totals {
+    pub name: String,
+    pub damage_taken: i64,
+    pub healing: i64,
+}
+
+/// in the order each entity first shows up in `log`
+pub fn totals(log: &[LogEntry]) -> Vec<Totals> {
+    let mut totals: Vec<Totals> = Vec::new();
+    for entry in log {
+        let (name, damage_taken, healing) = match &entry.event {
+            LogEvent::Damage { name, amount, .. } => (name, *amount, 0),
+            LogEvent::Heal { name, amount, .. } => (name, 0, *amount),
+            LogEvent::Removed { .. } | LogEvent::SwappedInitiative { .. } => continue,
+        };
+        match totals.iter_mut().find(|totals| totals.name == *name) {
+            Some(totals) => {
+                totals.damage_taken += damage_taken;
+                totals.healing += healing;
+            }
+            None => totals.push(Totals { name: name.clone(), damage_taken, healing }),
+        }
+    }
+    totals
+}
+
+/// `|` would end the table cell
+fn escape(str: &str) -> String {
+    str.replace('|', "\\|")
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::PathBuf;
+
+use iced::{Align, Button, button, Container, Element, Length, Row, Text};
+use once_cell::sync::Lazy;
+
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap, TextInputState};
+
+/// Only ever the DM's, it's kept out of encounter files and everything the players see
+static SCRATCHPAD_FILE: Lazy<PathBuf> = Lazy::new(|| crate::SAVE_DIR.join("scratchpad.txt"));
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Toggle,
+    Edit(String),
+    /// asks first, see `Scratchpad::confirming`
+    Clear,
+    ConfirmClear,
+    Cancel,
+}
+
+/// Notes for the session ("the door is locked, DC 15"), shown above the bottom bar while it's open
+/// and saved on every change so they're still there next launch
+#[derive(Debug, Default)]
+pub struct Scratchpad {
+    pub open: bool,
+    pub text: TextInputState,
+    clear: button::State,
+    confirming: bool,
+    confirm: button::State,
+    cancel: button::State,
+}
+
+impl Scratchpad {
+    pub fn load() -> Self {
+        Self {
+            text: TextInputState {
+                content: fs::read_to_string(&*SCRATCHPAD_FILE).unwrap_or_default(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) {
+        // ignore error, same as settings
+        let _ = fs::write(&*SCRATCHPAD_FILE, &self.text.content);
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Toggle => {
+                self.open = !self.open;
+                self.confirming = false;
+            }
+            Message::Edit(text) => {
+                self.text.content = text;
+                self.save();
+            }
+            Message::Clear => self.confirming = true,
+            Message::ConfirmClear => {
+                self.text.content.clear();
+                self.confirming = false;
+                self.save();
+            }
+            Message::Cancel => self.confirming = false,
+        }
+    }
+
+    pub fn view(&mut self, style: Style) -> Element<crate::Message> {
+        let msg = crate::Message::Scratchpad;
+        let has_text = !self.text.content.is_empty();
+        let count = self.text.content.chars().count();
+        let Self { open: _, text, clear, confirming, confirm, cancel } = self;
+
+        let row = Row::new()
+            .align_items(Align::Center)
+            .push(text.text_input("Notes for the session", move |text| msg(Message::Edit(text)))
+                .style(style)
+                .size(14)
+                .width(Length::Fill))
+            .push_space(8)
+            .push(Text::new(format!("{count} {}", if count == 1 { "character" } else { "characters" })).size(12))
+            .push_space(8);
+        let row = if *confirming {
+            row.push(Text::new("Clear the notes?").size(12))
+                .push_space(4)
+                .push(Button::new(confirm, Text::new("Clear").size(12))
+                    .style(style)
+                    .on_press(msg(Message::ConfirmClear)))
+                .push_space(4)
+                .push(Button::new(cancel, Text::new("Cancel").size(12))
+                    .style(style)
+                    .on_press(msg(Message::Cancel)))
+        } else {
+            row.push(Button::new(clear, Text::new("Clear").size(12))
+                .style(style)
+                .tap_if(has_text, |btn| btn.on_press(msg(Message::Clear))))
+        };
+
+        Container::new(row)
+            .padding(4)
+            .width(Length::Fill)
+            .style(style)
+            .into()
+    }
+}
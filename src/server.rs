@@ -0,0 +1,238 @@
+use std::hash::{Hash, Hasher};
+
+use iced_futures::futures;
+use iced_native::subscription::Recipe;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+
+use crate::export::Row;
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// requests bigger than this are cut off, nothing the page sends comes close
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// Everything the players' page shows, pushed from `update` whenever it might have changed.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PlayerView {
+    pub round: u32,
+    pub rows: Vec<Row>,
+    /// the PCs in `SaveMode::LoadParty`, who can submit their own initiative
+    #[serde(default)]
+    pub awaiting_initiative: Vec<String>,
+}
+
+/// A player's initiative roll, posted as json to `/initiative`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Submission {
+    pub name: String,
+    pub roll: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Started(u16),
+    Failed(String),
+    Initiative(Submission),
+}
+
+/// Serves the player view on the LAN for as long as the subscription is alive, dropping it closes
+/// the listener.
+pub struct Server {
+    pub port: u16,
+    pub view: watch::Receiver<PlayerView>,
+}
+
+/// connections send submissions back to the stream through this
+type Channel = (mpsc::UnboundedSender<Submission>, mpsc::UnboundedReceiver<Submission>);
+
+enum State {
+    Starting(u16, watch::Receiver<PlayerView>),
+    Listening(TcpListener, watch::Receiver<PlayerView>, Channel),
+    Finished,
+}
+
+impl<H: Hasher, E> Recipe<H, E> for Server {
+    type Output = Event;
+
+    fn hash(&self, state: &mut H) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+        self.port.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: futures::stream::BoxStream<E>,
+    ) -> futures::stream::BoxStream<Self::Output> {
+        Box::pin(futures::stream::unfold(
+            State::Starting(self.port, self.view),
+            |state| async move {
+                match state {
+                    State::Starting(port, view) => match TcpListener::bind(("0.0.0.0", port)).await {
+                        Ok(listener) => Some((Event::Started(port), State::Listening(listener, view, mpsc::unbounded_channel()))),
+                        Err(e) => Some((Event::Failed(format!("Couldn't share the player view on port {port}: {e}")), State::Finished)),
+                    },
+                    State::Listening(listener, view, (tx, mut rx)) => loop {
+                        tokio::select! {
+                            accepted = listener.accept() => {
+                                // a bad connection shouldn't take the server down with it
+                                if let Ok((stream, _)) = accepted {
+                                    tokio::spawn(respond(stream, view.clone(), tx.clone()));
+                                }
+                            }
+                            // never `None`, `tx` is right here
+                            Some(submission) = rx.recv() => {
+                                return Some((Event::Initiative(submission), State::Listening(listener, view, (tx, rx))));
+                            }
+                        }
+                    },
+                    State::Finished => {
+                        // don't let the stream end, or iced would try to bind again every frame
+                        futures::future::pending::<()>().await;
+                        None
+                    }
+                }
+            },
+        ))
+    }
+}
+
+/// The head (request line and headers) and body of a request
+async fn read_request(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+            let content_length = head.lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(header, _)| header.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, len)| len.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            let body_start = end + 4;
+            if buf.len() - body_start >= content_length {
+                let body = buf[body_start..body_start + content_length].to_vec();
+                return Some((head, body));
+            }
+        }
+        if buf.len() > MAX_REQUEST {
+            return None;
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, view: watch::Receiver<PlayerView>, submissions: mpsc::UnboundedSender<Submission>) {
+    let (head, body) = match read_request(&mut stream).await {
+        Some(request) => request,
+        None => return,
+    };
+    // ex. "GET /state.json HTTP/1.1"
+    let mut request_line = head.split_whitespace();
+    let method = request_line.next().unwrap_or("GET");
+    let path = request_line.next().unwrap_or("/");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string()),
+        ("GET", "/state.json") => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&*view.borrow()).unwrap_or_default(),
+        ),
+        ("POST", "/initiative") => match serde_json::from_slice::<Submission>(&body) {
+            Ok(submission) if view.borrow().awaiting_initiative.contains(&submission.name) => {
+                let _ = submissions.send(submission);
+                ("200 OK", "text/plain", String::from("ok"))
+            }
+            Ok(submission) => ("400 Bad Request", "text/plain", format!("{} isn't waiting on initiative", submission.name)),
+            Err(_) => ("400 Bad Request", "text/plain", String::from("initiative has to be a whole number")),
+        },
+        _ => ("404 Not Found", "text/plain", String::from("not found")),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// polls `/state.json` every second, names are set with `textContent` so they don't need escaping.
+/// While a party is loading each player can also send their own initiative.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Initiative Order</title>
+<style>
+body { font-family: Arial, sans-serif; background: #222; color: #eee; }
+li { font-size: 1.5em; padding: 4px; }
+.turn { font-weight: bold; color: #fc6; }
+</style>
+</head>
+<body>
+<h1 id="round"></h1>
+<ol id="order"></ol>
+<form id="initiative" hidden>
+<select id="pc"></select>
+<input id="roll" type="number" min="0" placeholder="Initiative">
+<button type="submit">Submit</button>
+<span id="result"></span>
+</form>
+<script>
+let awaiting = "";
+document.getElementById("initiative").addEventListener("submit", async event => {
+    event.preventDefault();
+    const name = document.getElementById("pc").value;
+    const roll = Number(document.getElementById("roll").value);
+    const response = await fetch("/initiative", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ name, roll }),
+    });
+    document.getElementById("result").textContent = response.ok ? "Sent!" : await response.text();
+});
+async function refresh() {
+    try {
+        const state = await (await fetch("/state.json")).json();
+        // only rebuild the dropdown when the party changes, so it doesn't lose the selection
+        if (JSON.stringify(state.awaiting_initiative) !== awaiting) {
+            awaiting = JSON.stringify(state.awaiting_initiative);
+            document.getElementById("initiative").hidden = state.awaiting_initiative.length === 0;
+            document.getElementById("pc").replaceChildren(...state.awaiting_initiative.map(name => {
+                const option = document.createElement("option");
+                option.textContent = name;
+                return option;
+            }));
+        }
+        document.getElementById("round").textContent = "Round " + state.round;
+        const order = document.getElementById("order");
+        order.replaceChildren(...state.rows.map(row => {
+            const li = document.createElement("li");
+            li.textContent = row.name;
+            if (row.tag) {
+                const tag = document.createElement("span");
+                tag.textContent = "\u25C6 ";
+                tag.style.color = row.tag;
+                li.prepend(tag);
+            }
+            if (row.is_turn) li.className = "turn";
+            return li;
+        }));
+    } catch (e) {
+        document.getElementById("round").textContent = "Disconnected";
+    }
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
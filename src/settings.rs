@@ -0,0 +1,532 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{overlay, report, SAVE_DIR, server, sync};
+use crate::style::{Style, TagColor};
+use crate::utils::{self, write_atomic};
+
+/// how much of the window's width the initiative table gets, the controls get the rest
+pub const MIN_TABLE_RATIO: f32 = 0.35;
+pub const MAX_TABLE_RATIO: f32 = 0.8;
+const DEFAULT_TABLE_RATIO: f32 = 0.6;
+
+/// green screen green
+pub const DEFAULT_KEY_COLOR: &str = "#00FF00";
+
+const DEFAULT_SUFFIX_COLORS: [&str; 6] = ["red", "blue", "green", "yellow", "purple", "orange"];
+/// concentrating has its own column
+const DEFAULT_QUICK_CONDITIONS: [&str; 2] = ["Prone", "Grappled"];
+
+/// How legendary actions are shown, in the table and when loading an encounter
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum LegendaryDisplay {
+    /// ex. `II`
+    Roman,
+    /// ex. `2`
+    Number,
+    /// ex. `●●○`, or `2/7` for more than `MAX_PIPS`
+    Pips,
+}
+
+impl Default for LegendaryDisplay {
+    fn default() -> Self {
+        Self::Pips
+    }
+}
+
+impl LegendaryDisplay {
+    pub const ALL: [Self; 3] = [Self::Roman, Self::Number, Self::Pips];
+
+    /// any more than this are too many to count at a glance
+    pub const MAX_PIPS: u32 = 5;
+
+    /// `left` out of `total`. Roman numerals have no zero, so that's `0` either way.
+    pub fn text(self, left: u32, total: u32) -> String {
+        match self {
+            Self::Roman => roman::to(left as _).unwrap_or_else(|| left.to_string()),
+            Self::Number => left.to_string(),
+            Self::Pips if total > Self::MAX_PIPS => format!("{left}/{total}"),
+            Self::Pips => "●".repeat(left as usize) + &"○".repeat(total.saturating_sub(left) as usize),
+        }
+    }
+}
+
+/// What's kept from the players while the DM view is off, each on its own so names can be shown
+/// while HP is hidden or the other way around
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Censor {
+    /// hidden names are replaced by their censored version
+    pub names: bool,
+    /// hidden HP shows as `??`
+    pub hp: bool,
+    /// enemies' conditions and recharges aren't shown at all
+    pub conditions: bool,
+}
+
+impl Default for Censor {
+    fn default() -> Self {
+        Self {
+            names: true,
+            hp: true,
+            conditions: false,
+        }
+    }
+}
+
+/// When a monster gets its legendary actions back. The rules say the start of its turn, so it can
+/// use them all before then, but some tables refresh them at the end of its turn instead, which
+/// means any it used on its own turn are gone until the round comes back around.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum LegendaryReset {
+    StartOfTurn,
+    EndOfTurn,
+}
+
+impl Default for LegendaryReset {
+    fn default() -> Self {
+        Self::StartOfTurn
+    }
+}
+
+impl LegendaryReset {
+    pub const ALL: [Self; 2] = [Self::StartOfTurn, Self::EndOfTurn];
+}
+
+impl Display for LegendaryDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Roman => "Roman numerals",
+            Self::Number => "Numbers",
+            Self::Pips => "Pips",
+        })
+    }
+}
+
+impl Display for LegendaryReset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::StartOfTurn => "Start of its turn",
+            Self::EndOfTurn => "End of its turn",
+        })
+    }
+}
+
+/// How duplicate names are told apart, see `utils::suffixed_name`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum SuffixStyle {
+    /// `Goblin 2`
+    Number,
+    /// `Goblin B`
+    Letter,
+    /// `Goblin (red)`, from `Settings::suffix_colors`
+    Color,
+}
+
+impl Default for SuffixStyle {
+    fn default() -> Self {
+        Self::Number
+    }
+}
+
+impl SuffixStyle {
+    pub const ALL: [Self; 3] = [Self::Number, Self::Letter, Self::Color];
+}
+
+impl Display for SuffixStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Number => "Goblin 2",
+            Self::Letter => "Goblin B",
+            Self::Color => "Goblin (red)",
+        })
+    }
+}
+
+/// What happens to a monster that damage drops to 0 HP, PCs are always left to make death saves
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum AtZero {
+    /// kept in the table, but marked dead and skipped in the order
+    KeepDead,
+    /// taken out of the order, with a chance to undo
+    Remove,
+    /// asks on their row each time
+    Prompt,
+}
+
+impl Default for AtZero {
+    fn default() -> Self {
+        Self::KeepDead
+    }
+}
+
+impl AtZero {
+    pub const ALL: [Self; 3] = [Self::KeepDead, Self::Remove, Self::Prompt];
+}
+
+impl Display for AtZero {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::KeepDead => "Marked dead",
+            Self::Remove => "Removed",
+            Self::Prompt => "Asked about",
+        })
+    }
+}
+
+/// Who goes first when a player and a monster tie, the rules say the players decide
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum TieRule {
+    /// asked about each time, see `tie::TiePrompt`
+    Manual,
+    PlayersFirst,
+    MonstersFirst,
+    /// everyone tied rolls a d20
+    RollOff,
+}
+
+impl Default for TieRule {
+    fn default() -> Self {
+        Self::Manual
+    }
+}
+
+impl TieRule {
+    pub const ALL: [Self; 4] = [Self::Manual, Self::PlayersFirst, Self::MonstersFirst, Self::RollOff];
+}
+
+impl Display for TieRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Manual => "Asked about",
+            Self::PlayersFirst => "Players first",
+            Self::MonstersFirst => "Monsters first",
+            Self::RollOff => "Rolled off",
+        })
+    }
+}
+
+/// One change from the settings panel, see `Settings::set`
+#[derive(Debug, Copy, Clone)]
+pub enum Setting {
+    Style(Style),
+    Compact(bool),
+    LargeControls(bool),
+    Striped(bool),
+    InitiativeCaller(bool),
+    LegendaryDisplay(LegendaryDisplay),
+    LegendaryReset(LegendaryReset),
+    AtZero(AtZero),
+    TieRule(TieRule),
+    RemoveSummons(bool),
+    SuffixDuplicates(bool),
+    SuffixStyle(SuffixStyle),
+    FillSuffixGaps(bool),
+    StreamingBorderless(bool),
+    SnapshotTurns(bool),
+    CensorNames(bool),
+    CensorHp(bool),
+    CensorConditions(bool),
+}
+
+static SETTINGS_FILE: Lazy<PathBuf> = Lazy::new(|| SAVE_DIR.join("settings.json"));
+
+/// left out of exports unless the user asks for them, see `export`
+const SECRETS: [&str; 2] = ["webhook_url", "summary_url"];
+
+/// Anything missing from the file, like a setting added since it was saved, gets its value from
+/// `Settings::default`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub style: Style,
+    /// less padding & smaller text so more rows fit
+    pub compact: bool,
+    /// touchscreen sized inputs & buttons in the table, doesn't change the text like `compact` does
+    pub large_controls: bool,
+    /// alternate the background color of each row
+    pub striped: bool,
+    /// the current initiative count in big text above the table
+    pub initiative_caller: bool,
+    pub legendary_display: LegendaryDisplay,
+    pub legendary_reset: LegendaryReset,
+    pub censor: Censor,
+    /// fraction of the width for the table, between `MIN_TABLE_RATIO` and `MAX_TABLE_RATIO`
+    pub table_ratio: f32,
+    /// serve the player view over http, see `server::Server`
+    pub server: bool,
+    pub server_port: u16,
+    /// let other instances mirror this one, see `sync::Host`
+    pub host: bool,
+    pub sync_port: u16,
+    /// has a secret token in it, so this file is the only place it should ever be written
+    pub webhook_url: String,
+    /// post each turn to `webhook_url`
+    pub announce: bool,
+    /// write the current turn to `overlay_path` for OBS, see `overlay::Overlay`
+    pub overlay: bool,
+    pub overlay_path: String,
+    pub overlay_template: String,
+    /// the next few combatants, not written if empty
+    pub overlay_next_path: String,
+    pub at_zero: AtZero,
+    /// from before `at_zero`, when the only choice was to remove them or not, read once by `load`
+    #[serde(rename = "auto_remove", skip_serializing)]
+    legacy_auto_remove: bool,
+    /// only for players (allies) against monsters (enemies), other ties are always asked about
+    pub tie_rule: TieRule,
+    /// something to rewind to at every turn, not just the start of each round
+    pub snapshot_turns: bool,
+    /// deleting an entity also deletes everything they summoned
+    pub remove_summons: bool,
+    /// suffix a new entity whose name is already taken (see `suffix_style`), otherwise just warn about it
+    pub suffix_duplicates: bool,
+    pub suffix_style: SuffixStyle,
+    /// for `SuffixStyle::Color`, in order
+    pub suffix_colors: Vec<String>,
+    /// reuse the suffix of someone who's gone instead of counting on from the highest
+    pub fill_suffix_gaps: bool,
+    /// a button on each row for each of these, in the same `"Poisoned 3"` form as the condition box
+    pub quick_conditions: Vec<String>,
+    /// run `hook_command` on turn changes, off by default since it can run anything
+    pub hook: bool,
+    /// see `hook::run`
+    pub hook_command: String,
+    /// append a `history::Summary` to `combat_history.jsonl` when an encounter is cleared
+    pub history: bool,
+    /// where to post each `history::Summary`, not posted if empty
+    pub summary_url: String,
+    /// where session reports go, ex. an Obsidian vault
+    pub report_dir: String,
+    /// see `report::write`
+    pub report_file_name: String,
+    /// what each tag means tonight, shown when hovering over it
+    pub tag_legend: HashMap<TagColor, String>,
+    /// just the table over `key_color`, for capturing the window in OBS
+    pub streaming: bool,
+    /// `#RRGGBB` or `#RRGGBBAA`
+    pub key_color: String,
+    /// no title bar or border while streaming, only read when the app starts
+    pub streaming_borderless: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            style: Style::Dark,
+            compact: false,
+            large_controls: false,
+            striped: true,
+            initiative_caller: false,
+            legendary_display: LegendaryDisplay::Pips,
+            legendary_reset: LegendaryReset::StartOfTurn,
+            censor: Censor::default(),
+            table_ratio: DEFAULT_TABLE_RATIO,
+            server: false,
+            server_port: server::DEFAULT_PORT,
+            host: false,
+            sync_port: sync::DEFAULT_PORT,
+            webhook_url: String::new(),
+            announce: false,
+            overlay: false,
+            overlay_path: String::new(),
+            overlay_template: overlay::DEFAULT_TEMPLATE.to_string(),
+            overlay_next_path: String::new(),
+            at_zero: AtZero::KeepDead,
+            legacy_auto_remove: false,
+            tie_rule: TieRule::Manual,
+            snapshot_turns: false,
+            remove_summons: true,
+            suffix_duplicates: false,
+            suffix_style: SuffixStyle::Number,
+            suffix_colors: DEFAULT_SUFFIX_COLORS.iter().map(|&color| color.to_string()).collect(),
+            fill_suffix_gaps: false,
+            quick_conditions: DEFAULT_QUICK_CONDITIONS.iter().map(|&condition| condition.to_string()).collect(),
+            hook: false,
+            hook_command: String::new(),
+            history: false,
+            summary_url: String::new(),
+            report_dir: String::new(),
+            report_file_name: report::DEFAULT_FILE_NAME.to_string(),
+            tag_legend: HashMap::new(),
+            streaming: false,
+            key_color: DEFAULT_KEY_COLOR.to_string(),
+            streaming_borderless: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Disambiguates `name` from everything in `taken` the way these settings say to
+    pub fn suffixed_name(&self, taken: &HashSet<String>, name: &str) -> String {
+        utils::suffixed_name(taken, name, self.suffix_style, &self.suffix_colors, self.fill_suffix_gaps)
+    }
+
+    /// Changes one setting and saves them all
+    pub fn set(&mut self, setting: Setting) {
+        match setting {
+            Setting::Style(style) => self.style = style,
+            Setting::Compact(compact) => self.compact = compact,
+            Setting::LargeControls(large_controls) => self.large_controls = large_controls,
+            Setting::Striped(striped) => self.striped = striped,
+            Setting::InitiativeCaller(caller) => self.initiative_caller = caller,
+            Setting::LegendaryDisplay(display) => self.legendary_display = display,
+            Setting::LegendaryReset(reset) => self.legendary_reset = reset,
+            Setting::AtZero(at_zero) => self.at_zero = at_zero,
+            Setting::TieRule(rule) => self.tie_rule = rule,
+            Setting::RemoveSummons(remove_summons) => self.remove_summons = remove_summons,
+            Setting::SuffixDuplicates(suffix) => self.suffix_duplicates = suffix,
+            Setting::SuffixStyle(style) => self.suffix_style = style,
+            Setting::FillSuffixGaps(fill_gaps) => self.fill_suffix_gaps = fill_gaps,
+            Setting::StreamingBorderless(borderless) => self.streaming_borderless = borderless,
+            Setting::SnapshotTurns(turns) => self.snapshot_turns = turns,
+            Setting::CensorNames(names) => self.censor.names = names,
+            Setting::CensorHp(hp) => self.censor.hp = hp,
+            Setting::CensorConditions(conditions) => self.censor.conditions = conditions,
+        }
+        self.save();
+    }
+
+    pub fn load() -> Self {
+        fs::read(&*SETTINGS_FILE).map_or_else(|_| Self::default(), |bytes| Self::from_bytes(&bytes))
+    }
+
+    /// A file that's cut off or otherwise won't parse means starting over from the defaults
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut settings: Self = serde_json::from_slice(bytes).unwrap_or_default();
+        if std::mem::take(&mut settings.legacy_auto_remove) {
+            settings.at_zero = AtZero::Remove;
+        }
+        settings
+    }
+
+    /// Written to a temporary file first, so a crash part way through leaves the old settings alone
+    pub fn save(&self) {
+        // ignore error, the settings just won't be remembered next time
+        if let Ok(json) = serde_json::to_vec_pretty(self) {
+            let _ = write_atomic(&SETTINGS_FILE, json);
+        }
+    }
+}
+
+/// Writes `settings` to `path`, or to `settings.json` in it if it's a folder
+pub async fn export(settings: Settings, path: String, include_secrets: bool) -> Result<PathBuf, String> {
+    tokio::task::spawn_blocking(move || {
+        let path = settings_path(&path)?;
+        let mut json = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+        if !include_secrets {
+            if let Value::Object(map) = &mut json {
+                for key in SECRETS {
+                    map.remove(key);
+                }
+            }
+        }
+        let json = serde_json::to_vec_pretty(&json).map_err(|e| e.to_string())?;
+        write_atomic(&path, json).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(path)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// The settings read by `import`, with whatever in the file couldn't be used
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub settings: Settings,
+    /// keys this version doesn't know about, maybe from a newer one
+    pub unknown: Vec<String>,
+    /// known keys with a value that doesn't fit, these keep their `current` value
+    pub invalid: Vec<String>,
+}
+
+/// Reads exported settings from `path` (or `settings.json` in it) one key at a time on top of
+/// `current`, so one bad value doesn't throw out the rest. Keys not in the file, like secrets that
+/// weren't exported, stay as they are.
+pub async fn import(path: String, current: Settings) -> Result<Import, String> {
+    tokio::task::spawn_blocking(move || {
+        let path = settings_path(&path)?;
+        let bytes = fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let imported = match serde_json::from_slice(&bytes) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => return Err(format!("{} isn't a settings file", path.display())),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+
+        let mut settings = match serde_json::to_value(&current) {
+            Ok(Value::Object(map)) => map,
+            _ => return Err(String::from("couldn't read the current settings")),
+        };
+        let mut unknown = Vec::new();
+        let mut invalid = Vec::new();
+        for (key, value) in imported {
+            if !settings.contains_key(&key) {
+                unknown.push(key);
+                continue;
+            }
+            let old = settings.insert(key.clone(), value);
+            if try_settings(&settings).is_none() {
+                if let Some(old) = old {
+                    settings.insert(key.clone(), old);
+                }
+                invalid.push(key);
+            }
+        }
+        let settings = try_settings(&settings).unwrap_or(current);
+        Ok(Import { settings, unknown, invalid })
+    }).await.map_err(|e| e.to_string())?
+}
+
+fn try_settings(map: &Map<String, Value>) -> Option<Settings> {
+    serde_json::from_value(Value::Object(map.clone())).ok()
+}
+
+fn settings_path(path: &str) -> Result<PathBuf, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(String::from("no settings file chosen"));
+    }
+    let path = Path::new(path);
+    Ok(if path.is_dir() { path.join("settings.json") } else { path.to_path_buf() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_file_falls_back_to_defaults() {
+        let settings = Settings {
+            compact: true,
+            tie_rule: TieRule::RollOff,
+            ..Settings::default()
+        };
+        let json = serde_json::to_vec_pretty(&settings).unwrap();
+
+        let whole = Settings::from_bytes(&json);
+        assert!(whole.compact);
+        assert_eq!(whole.tie_rule, TieRule::RollOff);
+
+        for len in [0, 1, json.len() / 2, json.len() - 1] {
+            let cut = Settings::from_bytes(&json[..len]);
+            assert!(!cut.compact);
+            assert_eq!(cut.tie_rule, TieRule::Manual);
+        }
+    }
+
+    #[test]
+    fn missing_fields_get_their_defaults() {
+        let settings = Settings::from_bytes(br#"{ "compact": true }"#);
+        assert!(settings.compact);
+        assert_eq!(settings.suffix_colors, Settings::default().suffix_colors);
+    }
+
+    #[test]
+    fn legacy_auto_remove_is_read_as_at_zero() {
+        let settings = Settings::from_bytes(br#"{ "auto_remove": true }"#);
+        assert_eq!(settings.at_zero, AtZero::Remove);
+    }
+}
@@ -0,0 +1,194 @@
+use iced::{Align, Button, button, Checkbox, Column, Container, Element, Length, pick_list, PickList, Row, Text};
+
+use crate::settings::{AtZero, LegendaryDisplay, LegendaryReset, Setting, Settings, SuffixStyle, TieRule};
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap, TextInputState};
+
+/// Every option in one place, shown instead of the controls next to the table while it's open.
+/// Changes go through `crate::Message::SetSetting` so they're all saved the same way.
+#[derive(Debug, Default)]
+pub struct SettingsPanel {
+    pub open: bool,
+    close: button::State,
+    style_list: pick_list::State<Style>,
+    legendary_display_list: pick_list::State<LegendaryDisplay>,
+    legendary_reset_list: pick_list::State<LegendaryReset>,
+    at_zero_list: pick_list::State<AtZero>,
+    tie_rule_list: pick_list::State<TieRule>,
+    suffix_style_list: pick_list::State<SuffixStyle>,
+    /// `Settings::suffix_colors`, comma separated
+    pub suffix_colors: TextInputState,
+    /// `Settings::quick_conditions`, comma separated
+    pub quick_conditions: TextInputState,
+    /// where settings are exported to or imported from, not saved since it's different on each computer
+    pub path: TextInputState,
+    pub include_secrets: bool,
+    export: button::State,
+    import: button::State,
+}
+
+impl SettingsPanel {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            suffix_colors: TextInputState {
+                content: settings.suffix_colors.join(", "),
+                ..Default::default()
+            },
+            quick_conditions: TextInputState {
+                content: settings.quick_conditions.join(", "),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn view(&mut self, style: Style, settings: &Settings) -> Element<crate::Message> {
+        let Self {
+            open: _,
+            close,
+            style_list,
+            legendary_display_list,
+            legendary_reset_list,
+            at_zero_list,
+            tie_rule_list,
+            suffix_style_list,
+            suffix_colors,
+            quick_conditions,
+            path,
+            include_secrets,
+            export,
+            import,
+        } = self;
+
+        let heading = |text: &str| Text::new(text).size(18);
+        let checkbox = |checked, label: &str, setting: fn(bool) -> Setting| Checkbox::new(
+            checked,
+            label,
+            move |value| crate::Message::SetSetting(setting(value)),
+        ).style(style)
+            .size(16)
+            .text_size(14);
+
+        let theme = PickList::new(
+            style_list,
+            Style::ALL.to_vec(),
+            Some(settings.style),
+            |style| crate::Message::SetSetting(Setting::Style(style)),
+        ).style(style)
+            .text_size(14);
+        let legendary_display = PickList::new(
+            legendary_display_list,
+            LegendaryDisplay::ALL.to_vec(),
+            Some(settings.legendary_display),
+            |display| crate::Message::SetSetting(Setting::LegendaryDisplay(display)),
+        ).style(style)
+            .text_size(14);
+        let legendary_reset = PickList::new(
+            legendary_reset_list,
+            LegendaryReset::ALL.to_vec(),
+            Some(settings.legendary_reset),
+            |reset| crate::Message::SetSetting(Setting::LegendaryReset(reset)),
+        ).style(style)
+            .text_size(14);
+        let at_zero = PickList::new(
+            at_zero_list,
+            AtZero::ALL.to_vec(),
+            Some(settings.at_zero),
+            |at_zero| crate::Message::SetSetting(Setting::AtZero(at_zero)),
+        ).style(style)
+            .text_size(14);
+        let tie_rule = PickList::new(
+            tie_rule_list,
+            TieRule::ALL.to_vec(),
+            Some(settings.tie_rule),
+            |rule| crate::Message::SetSetting(Setting::TieRule(rule)),
+        ).style(style)
+            .text_size(14);
+        let suffix_style = PickList::new(
+            suffix_style_list,
+            SuffixStyle::ALL.to_vec(),
+            Some(settings.suffix_style),
+            |style| crate::Message::SetSetting(Setting::SuffixStyle(style)),
+        ).style(style)
+            .text_size(14);
+        let suffix_colors = suffix_colors.text_input("red, blue, green", crate::Message::SuffixColors)
+            .style(style)
+            .size(14);
+        let quick_conditions = quick_conditions.text_input("Prone, Grappled", crate::Message::QuickConditions)
+            .style(style)
+            .size(14);
+
+        let has_path = !path.content.trim().is_empty();
+        let transfer = Row::new()
+            .align_items(Align::Center)
+            .push(path.text_input("Settings file or folder", crate::Message::SettingsPath)
+                .style(style)
+                .size(14))
+            .push_space(8)
+            .push(Button::new(export, Text::new("Export").size(14))
+                .style(style)
+                .tap_if(has_path, |btn| btn.on_press(crate::Message::ExportSettings)))
+            .push_space(4)
+            .push(Button::new(import, Text::new("Import").size(14))
+                .style(style)
+                .tap_if(has_path, |btn| btn.on_press(crate::Message::ImportSettings)));
+
+        let column = Column::new()
+            .spacing(6)
+            .push(Row::new()
+                .align_items(Align::Center)
+                .push(Text::new("Settings").size(24))
+                .push_space(Length::Fill)
+                .push(Button::new(close, Text::new("Done").size(14))
+                    .style(style)
+                    .on_press(crate::Message::OpenSettings(false))))
+            .push(heading("Table"))
+            .push(labeled("Theme", theme))
+            .push(checkbox(settings.compact, "Compact rows", Setting::Compact))
+            .push(checkbox(settings.large_controls, "Large controls, for touchscreens", Setting::LargeControls))
+            .push(checkbox(settings.striped, "Stripe rows", Setting::Striped))
+            .push(checkbox(settings.initiative_caller, "Show the current initiative count above the table", Setting::InitiativeCaller))
+            .push(labeled("Legendary actions as", legendary_display))
+            .push_space(6)
+            .push(heading("With secret stats hidden"))
+            .push(checkbox(settings.censor.names, "Censor hidden names", Setting::CensorNames))
+            .push(checkbox(settings.censor.hp, "Censor hidden HP", Setting::CensorHp))
+            .push(checkbox(settings.censor.conditions, "Hide enemies' conditions", Setting::CensorConditions))
+            .push_space(6)
+            .push(heading("Combat"))
+            .push(labeled("Legendary actions come back at the", legendary_reset))
+            .push(labeled("Monsters at 0 HP are", at_zero))
+            .push(labeled("Players' ties with monsters are", tie_rule))
+            .push(checkbox(settings.snapshot_turns, "Rewind to any turn, not just the start of a round", Setting::SnapshotTurns))
+            .push(labeled("Quick conditions", quick_conditions))
+            .push(checkbox(settings.remove_summons, "Remove summons with their owner", Setting::RemoveSummons))
+            .push(checkbox(settings.suffix_duplicates, "Tell duplicate names apart (or just warn)", Setting::SuffixDuplicates))
+            .push(labeled("Duplicates are named like", suffix_style))
+            .tap_if(settings.suffix_style == SuffixStyle::Color, |col| col
+                .push(labeled("Colors", suffix_colors)))
+            .push(checkbox(settings.fill_suffix_gaps, "Reuse the suffixes of removed duplicates", Setting::FillSuffixGaps))
+            .push_space(6)
+            .push(heading("Streaming"))
+            .push(checkbox(settings.streaming_borderless, "No window border in streaming mode (next launch)", Setting::StreamingBorderless))
+            .push_space(6)
+            .push(heading("Move to another computer"))
+            .push(transfer)
+            .push(Checkbox::new(*include_secrets, "Include webhook urls", crate::Message::IncludeSecrets)
+                .style(style)
+                .size(16)
+                .text_size(14));
+
+        Container::new(column)
+            .padding(8)
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+fn labeled<'a>(label: &str, element: impl Into<Element<'a, crate::Message>>) -> Row<'a, crate::Message> {
+    Row::new()
+        .align_items(Align::Center)
+        .push(Text::new(label).size(14))
+        .push_space(8)
+        .push(element)
+}
@@ -0,0 +1,40 @@
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::icon::Glyph;
+
+/// A monster from the SRD that can be added without typing in its stats
+#[derive(Debug, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    /// parsed as `utils::Hp`, so it's rolled for each monster added
+    pub hp: String,
+    pub ac: u32,
+    /// added to the initiative roll
+    #[serde(default)]
+    pub initiative: i32,
+    #[serde(default)]
+    pub legendary_actions: u32,
+    #[serde(default)]
+    pub icon: Option<Glyph>,
+    /// name and the lowest d6 roll it recharges on
+    #[serde(default)]
+    pub recharges: Vec<(String, u32)>,
+}
+
+pub static PRESETS: Lazy<Vec<Preset>> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../resources/srd.json")).unwrap()
+});
+
+/// names of the presets containing `query`, ignoring case
+pub fn search(query: &str) -> Vec<String> {
+    let query = query.to_lowercase();
+    PRESETS.iter()
+        .filter(|preset| preset.name.to_lowercase().contains(&query))
+        .map(|preset| preset.name.clone())
+        .collect()
+}
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
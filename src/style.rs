@@ -1,8 +1,9 @@
 use std::fmt::{self, Display};
 use std::ops::Not;
 
-use iced::{button, checkbox, container, pick_list, scrollable, slider, text_input};
+use iced::{button, checkbox, Color, container, pick_list, scrollable, slider, text_input};
 use iced_aw::tabs;
+use serde::{Deserialize, Serialize};
 
 macro_rules! from {
     (
@@ -56,7 +57,7 @@ macro_rules! color {
     };
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Style {
     Light,
     Dark,
@@ -74,6 +75,13 @@ pub struct InitiativeTableStyle {
     alt: Option<bool>,
 }
 
+impl InitiativeTableStyle {
+    /// the theme this row is drawn in
+    pub fn style(self) -> Style {
+        self.style
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum InitiativeTableBorderStyle {
     Light,
@@ -81,6 +89,8 @@ pub enum InitiativeTableBorderStyle {
 }
 
 impl Style {
+    pub const ALL: [Self; 2] = [Self::Light, Self::Dark];
+
     pub fn settings_bar(self) -> SettingsBarStyle {
         match self {
             Self::Light => SettingsBarStyle::Light,
@@ -88,10 +98,11 @@ impl Style {
         }
     }
 
-    pub fn initiative_table(self, n: usize) -> InitiativeTableStyle {
+    /// `n == 0` is the entity whose turn it is, the rest alternate if `striped`
+    pub fn initiative_table(self, n: usize, striped: bool) -> InitiativeTableStyle {
         InitiativeTableStyle {
             style: self,
-            alt: (n != 0).then(|| n % 2 == 1),
+            alt: (n != 0).then(|| striped && n % 2 == 1),
         }
     }
 
@@ -129,6 +140,307 @@ impl Display for Style {
     }
 }
 
+/// A marker the DM can put on an entity to mean whatever they need that night, see
+/// `Settings::tag_legend`. The names double as css colors for the players' page.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
+    Pink,
+}
+
+impl TagColor {
+    pub const ALL: [Self; 8] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Teal,
+        Self::Blue,
+        Self::Purple,
+        Self::Pink,
+    ];
+
+    /// what clicking an entity's tag changes it to, going back to no tag after the last one
+    pub fn cycle(tag: Option<Self>) -> Option<Self> {
+        match tag {
+            None => Some(Self::ALL[0]),
+            Some(tag) => Self::ALL.iter()
+                .position(|&t| t == tag)
+                .and_then(|i| Self::ALL.get(i + 1))
+                .copied(),
+        }
+    }
+
+    /// brighter on the dark theme, deeper on the light one so they stand out either way
+    pub fn color(self, style: Style) -> Color {
+        match style {
+            Style::Dark => match self {
+                Self::Red => color!(rgb 0xE8 0x5A 0x5A),
+                Self::Orange => color!(rgb 0xF0 0xA0 0x40),
+                Self::Yellow => color!(rgb 0xF0 0xD8 0x50),
+                Self::Green => color!(rgb 0x60 0xC8 0x70),
+                Self::Teal => color!(rgb 0x40 0xC8 0xC0),
+                Self::Blue => color!(rgb 0x5A 0x9A 0xF0),
+                Self::Purple => color!(rgb 0xA8 0x78 0xE8),
+                Self::Pink => color!(rgb 0xF0 0x88 0xC8),
+            },
+            Style::Light => match self {
+                Self::Red => color!(rgb 0xC0 0x30 0x30),
+                Self::Orange => color!(rgb 0xD0 0x70 0x10),
+                Self::Yellow => color!(rgb 0xB8 0x98 0x00),
+                Self::Green => color!(rgb 0x2E 0x8B 0x40),
+                Self::Teal => color!(rgb 0x10 0x8A 0x88),
+                Self::Blue => color!(rgb 0x28 0x60 0xC8),
+                Self::Purple => color!(rgb 0x78 0x40 0xB8),
+                Self::Pink => color!(rgb 0xC8 0x40 0x90),
+            },
+        }
+    }
+}
+
+impl Display for TagColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Red => "Red",
+            Self::Orange => "Orange",
+            Self::Yellow => "Yellow",
+            Self::Green => "Green",
+            Self::Teal => "Teal",
+            Self::Blue => "Blue",
+            Self::Purple => "Purple",
+            Self::Pink => "Pink",
+        })
+    }
+}
+
+/// A flat background to chroma key the window out with, see `Settings::streaming`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyed {
+    pub style: Style,
+    pub color: Color,
+}
+
+impl container::StyleSheet for Keyed {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: self.color.into(),
+            ..Box::<dyn container::StyleSheet>::from(self.style).style()
+        }
+    }
+}
+
+impl From<Keyed> for Box<dyn container::StyleSheet> {
+    fn from(keyed: Keyed) -> Self {
+        Box::new(keyed)
+    }
+}
+
+/// The reaction toggle, green while it's free and red once it's been used
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Reaction {
+    pub style: InitiativeTableStyle,
+    pub available: bool,
+}
+
+impl Reaction {
+    const AVAILABLE: Color = color!(rgb 0x3C 0xB3 0x4A);
+    const USED: Color = color!(rgb 0xD9 0x3F 0x3F);
+
+    fn colored(&self, style: button::Style) -> button::Style {
+        button::Style {
+            text_color: if self.available { Self::AVAILABLE } else { Self::USED },
+            ..style
+        }
+    }
+}
+
+impl button::StyleSheet for Reaction {
+    fn active(&self) -> button::Style {
+        self.colored(Box::<dyn button::StyleSheet>::from(self.style).active())
+    }
+
+    fn hovered(&self) -> button::Style {
+        self.colored(Box::<dyn button::StyleSheet>::from(self.style).hovered())
+    }
+
+    fn pressed(&self) -> button::Style {
+        self.colored(Box::<dyn button::StyleSheet>::from(self.style).pressed())
+    }
+
+    fn disabled(&self) -> button::Style {
+        self.colored(Box::<dyn button::StyleSheet>::from(self.style).disabled())
+    }
+}
+
+impl From<Reaction> for Box<dyn button::StyleSheet> {
+    fn from(reaction: Reaction) -> Self {
+        Box::new(reaction)
+    }
+}
+
+/// Something that can be reached with Tab, drawn with a ring around it while it has keyboard
+/// focus, see `InitiativeManager::focus_order`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Focusable<S> {
+    pub style: S,
+    pub focused: bool,
+}
+
+impl<S> Focusable<S> {
+    pub fn new(style: S, focused: bool) -> Self {
+        Self { style, focused }
+    }
+}
+
+/// bright enough to see on both themes and on any row
+const FOCUS_RING: Color = color!(rgb 0x3C 0x9E 0xFF);
+const FOCUS_RING_WIDTH: f32 = 2.0;
+
+struct ButtonRing(Box<dyn button::StyleSheet>);
+
+impl ButtonRing {
+    fn ring(style: button::Style) -> button::Style {
+        button::Style {
+            border_width: FOCUS_RING_WIDTH,
+            border_color: FOCUS_RING,
+            ..style
+        }
+    }
+}
+
+impl button::StyleSheet for ButtonRing {
+    fn active(&self) -> button::Style {
+        Self::ring(self.0.active())
+    }
+
+    fn hovered(&self) -> button::Style {
+        Self::ring(self.0.hovered())
+    }
+
+    fn pressed(&self) -> button::Style {
+        Self::ring(self.0.pressed())
+    }
+
+    fn disabled(&self) -> button::Style {
+        Self::ring(self.0.disabled())
+    }
+}
+
+impl<S: Into<Box<dyn button::StyleSheet>>> From<Focusable<S>> for Box<dyn button::StyleSheet> {
+    fn from(Focusable { style, focused }: Focusable<S>) -> Self {
+        if focused {
+            Box::new(ButtonRing(style.into()))
+        } else {
+            style.into()
+        }
+    }
+}
+
+struct PickListRing(Box<dyn pick_list::StyleSheet>);
+
+impl pick_list::StyleSheet for PickListRing {
+    fn menu(&self) -> pick_list::Menu {
+        self.0.menu()
+    }
+
+    fn active(&self) -> pick_list::Style {
+        pick_list::Style {
+            border_width: FOCUS_RING_WIDTH,
+            border_color: FOCUS_RING,
+            ..self.0.active()
+        }
+    }
+
+    fn hovered(&self) -> pick_list::Style {
+        pick_list::Style {
+            border_width: FOCUS_RING_WIDTH,
+            border_color: FOCUS_RING,
+            ..self.0.hovered()
+        }
+    }
+}
+
+impl<S: Into<Box<dyn pick_list::StyleSheet>>> From<Focusable<S>> for Box<dyn pick_list::StyleSheet> {
+    fn from(Focusable { style, focused }: Focusable<S>) -> Self {
+        if focused {
+            Box::new(PickListRing(style.into()))
+        } else {
+            style.into()
+        }
+    }
+}
+
+/// A text input with a red border when what's typed in it can't be used, see `INVALID`
+#[derive(Debug, Copy, Clone)]
+pub struct Invalid<S> {
+    pub style: S,
+    pub invalid: bool,
+}
+
+impl<S> Invalid<S> {
+    pub fn new(style: S, invalid: bool) -> Self {
+        Self { style, invalid }
+    }
+}
+
+/// for the border of an `Invalid` input and the message under it, readable on both themes
+pub const INVALID: Color = color!(rgb 0xE0 0x4F 0x4F);
+
+struct InvalidBorder(Box<dyn text_input::StyleSheet>);
+
+impl InvalidBorder {
+    fn border(style: text_input::Style) -> text_input::Style {
+        text_input::Style {
+            border_width: 1.0,
+            border_color: INVALID,
+            ..style
+        }
+    }
+}
+
+impl text_input::StyleSheet for InvalidBorder {
+    fn active(&self) -> text_input::Style {
+        Self::border(self.0.active())
+    }
+
+    fn focused(&self) -> text_input::Style {
+        Self::border(self.0.focused())
+    }
+
+    fn placeholder_color(&self) -> Color {
+        self.0.placeholder_color()
+    }
+
+    fn value_color(&self) -> Color {
+        self.0.value_color()
+    }
+
+    fn selection_color(&self) -> Color {
+        self.0.selection_color()
+    }
+
+    fn hovered(&self) -> text_input::Style {
+        Self::border(self.0.hovered())
+    }
+}
+
+impl<S: Into<Box<dyn text_input::StyleSheet>>> From<Invalid<S>> for Box<dyn text_input::StyleSheet> {
+    fn from(Invalid { style, invalid }: Invalid<S>) -> Self {
+        if invalid {
+            Box::new(InvalidBorder(style.into()))
+        } else {
+            style.into()
+        }
+    }
+}
+
 from! { Style =>
     container: dark = Container;
     text_input: dark = TextInput;
@@ -0,0 +1,194 @@
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use iced::{Align, Color, Column, Container, Element, Length, Row, Text};
+use iced_futures::futures;
+use iced_native::subscription::Recipe;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::sync::watch;
+
+use crate::server::PlayerView;
+use crate::style::Style;
+use crate::utils::{SpacingExt, Tap};
+
+pub const DEFAULT_PORT: u16 = 8081;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Hosting(u16),
+    HostFailed(String),
+    Connected,
+    Disconnected(String),
+    View(PlayerView),
+}
+
+/// What a mirror shows, it only ever gets the player view so nothing secret reaches the TV
+#[derive(Debug, Default)]
+pub struct Mirror {
+    pub address: String,
+    pub view: PlayerView,
+    /// `None` while connected, otherwise why not
+    pub disconnected: Option<String>,
+}
+
+impl Mirror {
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            view: Default::default(),
+            disconnected: Some(String::from("connecting...")),
+        }
+    }
+
+    /// owns all of its text so `InitiativeManager::view` can return it early
+    pub fn view(&self, style: Style) -> Element<'static, crate::Message> {
+        const TURN_COLOR: Color = Color::from_rgb(1.0, 0.8, 0.4);
+
+        let status = match &self.disconnected {
+            Some(reason) => format!("Reconnecting to {} ({reason})", self.address),
+            None => format!("Mirroring {}", self.address),
+        };
+        let order = self.view.rows.iter()
+            .fold(Column::new().align_items(Align::Center).spacing(8), |col, row| col
+                .push(Row::new()
+                    .align_items(Align::Center)
+                    .spacing(10)
+                    .tap_if_some(row.tag, |r, tag| r
+                        .push(Text::new("◆").size(24).color(tag.color(style))))
+                    .push(Text::new(row.name.clone())
+                        .size(32)
+                        .tap_if(row.is_turn, |txt| txt.color(TURN_COLOR)))));
+        let content = Column::new()
+            .align_items(Align::Center)
+            .push_space(20)
+            .push(Text::new(format!("Round {}", self.view.round)).size(40))
+            .push_space(20)
+            .push(order)
+            .push_space(Length::Fill)
+            .push(Text::new(status).size(12));
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .style(style)
+            .into()
+    }
+}
+
+/// Sends each change to the player view to every connected mirror, one json object per line.
+pub struct Host {
+    pub port: u16,
+    pub view: watch::Receiver<PlayerView>,
+}
+
+enum HostState {
+    Starting(u16, watch::Receiver<PlayerView>),
+    Listening(TcpListener, watch::Receiver<PlayerView>),
+    Finished,
+}
+
+impl<H: Hasher, E> Recipe<H, E> for Host {
+    type Output = Event;
+
+    fn hash(&self, state: &mut H) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+        self.port.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: futures::stream::BoxStream<E>,
+    ) -> futures::stream::BoxStream<Self::Output> {
+        Box::pin(futures::stream::unfold(
+            HostState::Starting(self.port, self.view),
+            |state| async move {
+                match state {
+                    HostState::Starting(port, view) => match TcpListener::bind(("0.0.0.0", port)).await {
+                        Ok(listener) => Some((Event::Hosting(port), HostState::Listening(listener, view))),
+                        Err(e) => Some((Event::HostFailed(format!("Couldn't host on port {port}: {e}")), HostState::Finished)),
+                    },
+                    HostState::Listening(listener, view) => loop {
+                        // each mirror gets its own task, so one going away never bothers the host
+                        if let Ok((stream, _)) = listener.accept().await {
+                            tokio::spawn(feed(stream, view.clone()));
+                        }
+                    },
+                    HostState::Finished => {
+                        futures::future::pending::<()>().await;
+                        None
+                    }
+                }
+            },
+        ))
+    }
+}
+
+async fn feed(mut stream: TcpStream, mut view: watch::Receiver<PlayerView>) {
+    loop {
+        let mut line = serde_json::to_string(&*view.borrow()).unwrap_or_default();
+        line.push('\n');
+        if stream.write_all(line.as_bytes()).await.is_err() || view.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Follows a `Host`, reconnecting forever if the connection drops
+pub struct Connect {
+    pub address: String,
+}
+
+enum ConnectState {
+    /// the `bool` is whether to wait before trying, so a dead host isn't hammered
+    Connecting(String, bool),
+    Reading(String, Lines<BufReader<OwnedReadHalf>>),
+}
+
+impl<H: Hasher, E> Recipe<H, E> for Connect {
+    type Output = Event;
+
+    fn hash(&self, state: &mut H) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+        self.address.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: futures::stream::BoxStream<E>,
+    ) -> futures::stream::BoxStream<Self::Output> {
+        Box::pin(futures::stream::unfold(
+            ConnectState::Connecting(self.address, false),
+            |state| async move {
+                match state {
+                    ConnectState::Connecting(address, wait) => {
+                        if wait {
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                        }
+                        match TcpStream::connect(&address).await {
+                            Ok(stream) => {
+                                // the write half is dropped, mirrors never send anything
+                                let (read, _) = stream.into_split();
+                                Some((Event::Connected, ConnectState::Reading(address, BufReader::new(read).lines())))
+                            }
+                            Err(e) => Some((Event::Disconnected(e.to_string()), ConnectState::Connecting(address, true))),
+                        }
+                    }
+                    ConnectState::Reading(address, mut lines) => match lines.next_line().await {
+                        Ok(Some(line)) => match serde_json::from_str(&line) {
+                            Ok(view) => Some((Event::View(view), ConnectState::Reading(address, lines))),
+                            Err(e) => Some((Event::Disconnected(e.to_string()), ConnectState::Connecting(address, true))),
+                        },
+                        Ok(None) => Some((Event::Disconnected(String::from("host closed the connection")), ConnectState::Connecting(address, true))),
+                        Err(e) => Some((Event::Disconnected(e.to_string()), ConnectState::Connecting(address, true))),
+                    },
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,58 @@
+use iced::{Align, Button, button, Element, Row, Text};
+
+use crate::EntityId;
+use crate::style::Style;
+use crate::utils::SpacingExt;
+
+/// Some newly added entities rolled the same initiative as someone already in the fight. They're
+/// put after them to start with, this asks if they should go before instead.
+#[derive(Debug)]
+pub struct TiePrompt {
+    pub initiative: u32,
+    /// the new ones, in order
+    pub ids: Vec<EntityId>,
+    /// ex. "Goblin 3 ties with Goblin 2 (14)"
+    label: String,
+    before: button::State,
+    after: button::State,
+}
+
+impl TiePrompt {
+    pub fn new(initiative: u32, ids: Vec<EntityId>, new: &[&str], existing: &[&str]) -> Self {
+        Self {
+            initiative,
+            ids,
+            label: format!("{} {} with {} ({initiative})", new.join(", "), if new.len() == 1 { "ties" } else { "tie" }, existing.join(", ")),
+            before: Default::default(),
+            after: Default::default(),
+        }
+    }
+
+    pub fn view(&mut self, style: Style) -> Element<crate::Message> {
+        Row::new()
+            .align_items(Align::Center)
+            .push(Text::new(&self.label).size(14))
+            .push_space(6)
+            .push(Button::new(&mut self.before, Text::new("Before").size(14))
+                .style(style)
+                .on_press(crate::Message::PlaceTie(true)))
+            .push_space(4)
+            .push(Button::new(&mut self.after, Text::new("After (Enter)").size(14))
+                .style(style)
+                .on_press(crate::Message::PlaceTie(false)))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_agrees_with_how_many_tied() {
+        let one = TiePrompt::new(14, Vec::new(), &["Goblin 3"], &["Goblin 2"]);
+        assert_eq!(one.label, "Goblin 3 ties with Goblin 2 (14)");
+        let two = TiePrompt::new(9, Vec::new(), &["Orc 1", "Orc 2"], &["Aragorn", "Legolas"]);
+        assert_eq!(two.label, "Orc 1, Orc 2 tie with Aragorn, Legolas (9)");
+    }
+}
@@ -4,6 +4,7 @@ use std::fs::DirEntry;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use iced_futures::futures;
@@ -18,6 +19,8 @@ use crate::{InitiativeManager, UpdateState};
 pub enum Message {
     CheckForUpdate,
     Progress(Progress),
+    /// stops the download, dropping whatever of it was already downloaded
+    Cancel,
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +31,12 @@ pub enum Progress {
     Errored(String),
 }
 
+/// progress is only sent on after it's gone up this many percent, the bar can't show less anyway
+const PROGRESS_STEP: f32 = 0.5;
+
+/// a download that doesn't get any bytes for this long is given up on
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct Download {
     pub url: String,
 }
@@ -39,6 +48,8 @@ pub enum State {
         buf: Vec<u8>,
         total: u64,
         downloaded: u64,
+        /// the last percent sent as `Progress::Advanced`
+        reported: f32,
     },
     /// true if new version was downloaded
     Finished,
@@ -65,7 +76,11 @@ impl<H: Hasher, E> Recipe<H, E> for Download {
                         let response = client.get(url)
                             .header(header::USER_AGENT, HeaderValue::from_str("rust-reqwest/update").unwrap())
                             .header(header::ACCEPT, HeaderValue::from_str("application/octet-stream").unwrap())
-                            .send().await;
+                            .send();
+                        let response = match tokio::time::timeout(STALL_TIMEOUT, response).await {
+                            Ok(response) => response,
+                            Err(_) => return Some((Progress::Errored(stalled()), State::Finished)),
+                        };
                         match response {
                             Ok(resp) => {
                                 match resp.content_length() {
@@ -74,8 +89,14 @@ impl<H: Hasher, E> Recipe<H, E> for Download {
                                         buf: vec![],
                                         total,
                                         downloaded: 0,
+                                        reported: 0.0,
                                     })),
-                                    None => Some((Progress::Finished(Some(resp.bytes().await.unwrap().to_vec())), State::Finished)),
+                                    // no length to track progress against, so it all comes at once
+                                    None => match tokio::time::timeout(STALL_TIMEOUT, resp.bytes()).await {
+                                        Ok(Ok(bytes)) => Some((Progress::Finished(Some(bytes.to_vec())), State::Finished)),
+                                        Ok(Err(e)) => Some((Progress::Errored(e.to_string()), State::Finished)),
+                                        Err(_) => Some((Progress::Errored(stalled()), State::Finished)),
+                                    },
                                 }
                             }
                             Err(e) => Some((Progress::Errored(e.to_string()), State::Finished)),
@@ -86,23 +107,31 @@ impl<H: Hasher, E> Recipe<H, E> for Download {
                         mut buf,
                         total,
                         mut downloaded,
-                    } => {
-                        match response.chunk().await {
-                            Ok(Some(bytes)) => {
+                        reported,
+                    } => loop {
+                        // keep reading until there's enough progress to be worth redrawing for
+                        match tokio::time::timeout(STALL_TIMEOUT, response.chunk()).await {
+                            Ok(Ok(Some(bytes))) => {
                                 downloaded += bytes.len() as u64;
-                                let percent = downloaded as f32 / total as f32 * 100.0;
                                 buf.extend_from_slice(&bytes);
-                                Some((Progress::Advanced(percent), State::Downloading {
-                                    response,
-                                    buf,
-                                    total,
-                                    downloaded,
-                                }))
+                                let percent = (downloaded as f32 / total as f32 * 100.0).min(100.0);
+                                if percent - reported >= PROGRESS_STEP {
+                                    break Some((Progress::Advanced(percent), State::Downloading {
+                                        response,
+                                        buf,
+                                        total,
+                                        downloaded,
+                                        reported: percent,
+                                    }));
+                                }
                             }
-                            Ok(None) => Some((Progress::Finished(Some(buf)), State::Finished)),
-                            Err(e) => Some((Progress::Errored(e.to_string()), State::Finished)),
+                            // a finished download always goes straight to `Downloaded`, even if the
+                            // last bit of progress wasn't sent
+                            Ok(Ok(None)) => break Some((Progress::Finished(Some(buf)), State::Finished)),
+                            Ok(Err(e)) => break Some((Progress::Errored(e.to_string()), State::Finished)),
+                            Err(_) => break Some((Progress::Errored(stalled()), State::Finished)),
                         }
-                    }
+                    },
                     State::Finished => {
                         // ig?
 
@@ -122,6 +151,10 @@ impl<H: Hasher, E> Recipe<H, E> for Download {
     }
 }
 
+fn stalled() -> String {
+    format!("the download stalled, nothing came for {} seconds", STALL_TIMEOUT.as_secs())
+}
+
 pub fn handle(app: &mut InitiativeManager, message: Message) -> anyhow::Result<()> {
     match message {
         Message::CheckForUpdate => {
@@ -152,6 +185,15 @@ pub fn handle(app: &mut InitiativeManager, message: Message) -> anyhow::Result<(
             };
             Ok(())
         }
+        Message::Cancel => {
+            // leaving `Ready`/`Downloading` unsubscribes from `Download`, which drops its buffer
+            if let UpdateState::Ready | UpdateState::Downloading(_) = app.update_state {
+                app.update_state = UpdateState::Cancelled;
+            }
+            Ok(())
+        }
+        // a chunk can still arrive between cancelling and the subscription going away
+        Message::Progress(_) if matches!(app.update_state, UpdateState::Cancelled) => Ok(()),
         Message::Progress(progress) => {
             app.update_state = match progress {
                 Progress::Started => UpdateState::Downloading(0.0),
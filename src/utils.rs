@@ -1,4 +1,7 @@
+use std::collections::HashSet;
 use std::fmt::Display;
+use std::{fs, io};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use iced::{button, Button, Checkbox, Color, Column, Element, HorizontalAlignment, Length, Row, Rule, Scrollable, Space, Text, text_input, TextInput, Tooltip};
@@ -11,6 +14,7 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use crate::Message;
+use crate::settings::SuffixStyle;
 
 pub trait SpacingExt {
     fn push_space<L: Into<Length>>(self, length: L) -> Self;
@@ -48,6 +52,23 @@ impl<'a, Message: 'a> SpacingExt for Scrollable<'a, Message> {
     }
 }
 
+/// `#RRGGBB` or `#RRGGBBAA`, the `#` is optional
+pub fn parse_hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| hex.get(i..i + 2)
+        .and_then(|c| u8::from_str_radix(c, 16).ok())
+        .map(|c| c as f32 / 255.0);
+    Some(Color {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+        a: if hex.len() == 8 { channel(6)? } else { 1.0 },
+    })
+}
+
 pub trait ColorExt {
     fn r(self, r: f32) -> Self;
     fn g(self, g: f32) -> Self;
@@ -266,18 +287,115 @@ pub trait TooltipExt<'a, Message>: Into<Element<'a, Message>> {
 
 impl<'a, Message, E: Into<Element<'a, Message>>> TooltipExt<'a, Message> for E {}
 
+/// Writes to a temporary file next to `path` then renames it over `path`, so readers (or a crash
+/// halfway through) never see a partly written file.
+pub fn write_atomic<C: AsRef<[u8]>>(path: &Path, contents: C) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+/// Adds `line` to the end of `path` (making it if needed) with `write_atomic`, so the file either
+/// has the whole line or none of it.
+pub fn append_atomic(path: &Path, line: &str) -> io::Result<()> {
+    let mut contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    if !contents.is_empty() && !contents.ends_with(b"\n") {
+        contents.push(b'\n');
+    }
+    contents.extend_from_slice(line.as_bytes());
+    contents.push(b'\n');
+    write_atomic(path, contents)
+}
+
+/// `name`, or `name` with the first free suffix in `style` if it's already taken. Counts on
+/// from the highest suffix already taken (`Goblin 1` and `Goblin 3` give `Goblin 4`) unless
+/// `fill_gaps`, in which case the lowest free one is used (`Goblin 2`). The unsuffixed name counts as
+/// the first.
+pub fn suffixed_name(taken: &HashSet<String>, name: &str, style: SuffixStyle, colors: &[String], fill_gaps: bool) -> String {
+    if !taken.contains(name) {
+        return name.to_string();
+    }
+    let n = if fill_gaps {
+        (2..).find(|&n| !taken.contains(&with_suffix(name, style, colors, n))).unwrap()
+    } else {
+        let highest = taken.iter()
+            .filter_map(|taken| suffix_number(taken, name, style, colors))
+            .max()
+            .unwrap_or(1);
+        // `highest` only knows about suffixes in this style, this skips anything else in the way
+        (highest.max(1) + 1..).find(|&n| !taken.contains(&with_suffix(name, style, colors, n))).unwrap()
+    };
+    with_suffix(name, style, colors, n)
+}
+
+/// The `n`th of `name`, `n` starts at 2 since the first one has no suffix
+fn with_suffix(name: &str, style: SuffixStyle, colors: &[String], n: usize) -> String {
+    match style {
+        SuffixStyle::Number => format!("{name} {n}"),
+        SuffixStyle::Letter => format!("{name} {}", letters(n)),
+        SuffixStyle::Color if colors.is_empty() => with_suffix(name, SuffixStyle::Number, colors, n),
+        SuffixStyle::Color => {
+            // after every color's been used once they come around again numbered, `red 2`
+            let i = n - 2;
+            match i / colors.len() {
+                0 => format!("{name} ({})", colors[i]),
+                lap => format!("{name} ({} {})", colors[i % colors.len()], lap + 1),
+            }
+        }
+    }
+}
+
+/// `n` for a `name` suffixed by `with_suffix`, ex. `7` for `Goblin 7`
+fn suffix_number(suffixed: &str, name: &str, style: SuffixStyle, colors: &[String]) -> Option<usize> {
+    let suffix = suffixed.strip_prefix(name)?.strip_prefix(' ')?;
+    match style {
+        SuffixStyle::Number => suffix.parse().ok(),
+        SuffixStyle::Letter if !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_uppercase()) => suffix.bytes()
+            .try_fold(0usize, |n, b| n.checked_mul(26)?.checked_add((b - b'A') as usize + 1)),
+        SuffixStyle::Letter => None,
+        SuffixStyle::Color if colors.is_empty() => suffix_number(suffixed, name, SuffixStyle::Number, colors),
+        SuffixStyle::Color => {
+            let suffix = suffix.strip_prefix('(')?.strip_suffix(')')?;
+            let (color, lap) = suffix.rsplit_once(' ')
+                .and_then(|(color, lap)| Some((color, lap.parse::<usize>().ok()?)))
+                .unwrap_or((suffix, 1));
+            let i = colors.iter().position(|c| c == color)?;
+            Some(lap.checked_sub(1)? * colors.len() + i + 2)
+        }
+    }
+}
+
+/// `A`, `B`, ..., `Z`, `AA`, `AB`, ...
+fn letters(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap()
+}
+
+/// Scrambles each letter of `name`, keeping whether it was capitalized, so "Sir Reginald II" becomes
+/// something like "Qex Tubowapc JV". Everything that isn't a letter (spaces, digits, punctuation) is
+/// left where it is.
 pub fn censor_name(name: &str) -> String {
-    const CENSOR: [char; 26] = [
-        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm',
-        'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-    ];
     let mut rng = thread_rng();
-    Regex::new(r#"\s+"#).unwrap()
-        .split(name)
-        .map(|word| (0..word.len() + 1 - rng.gen_range(0..2))
-            .map(|_| CENSOR[rng.gen_range(0..26)])
-            .collect::<String>())
-        .join(" ")
+    name.chars()
+        .map(|c| if c.is_alphabetic() {
+            let letter = char::from(b'a' + rng.gen_range(0..26));
+            if c.is_uppercase() { letter.to_ascii_uppercase() } else { letter }
+        } else {
+            c
+        })
+        .collect()
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -338,6 +456,8 @@ impl Hp {
     }
 
     pub fn into_number(self) -> Option<u32> {
+        // nothing entered yet, see `CurrentMaxHp::from_str`
+        if self.0.is_empty() { return None; }
         let mut rng = rand::thread_rng();
         self.0.into_iter()
             .map(|hp| hp.into_number(&mut rng))
@@ -356,3 +476,182 @@ impl FromStr for Hp {
         Ok(Self(vec))
     }
 }
+
+/// Either `hp`, which sets current & max hp to the same total, or `current/max`.
+#[derive(Debug)]
+pub struct CurrentMaxHp {
+    current: Hp,
+    max: Option<Hp>,
+}
+
+impl CurrentMaxHp {
+    pub fn new(hp: u32) -> Self {
+        Self { current: Hp::new(hp), max: None }
+    }
+
+    /// (current, max), rolling each part independently
+    pub fn into_numbers(self) -> Option<(u32, u32)> {
+        let current = self.current.into_number()?;
+        let max = match self.max {
+            Some(max) => max.into_number()?,
+            None => current,
+        };
+        Some((current, max))
+    }
+}
+
+impl FromStr for CurrentMaxHp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut slash_split = s.split('/');
+        let current = slash_split.next()
+            .ok_or(())?
+            .trim();
+        let max = slash_split.next().map(str::trim);
+        if slash_split.count() != 0 {
+            return Err(());
+        }
+        // `/30` has no current hp
+        if current.is_empty() && max.is_some() {
+            return Err(());
+        }
+        let current = current.parse()?;
+        let max = match max {
+            None => None,
+            // still typing `22/`
+            Some("") => Some(Hp(Vec::new())),
+            Some(max) => Some(max.parse()?),
+        };
+        Ok(Self { current, max })
+    }
+}
+
+/// What goes in an entity's attack box: the attack's total, or `nat 20`/`nat 1` (`n20` works too)
+/// which hit or miss no matter the AC.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AttackRoll {
+    Total(i32),
+    Natural(u32),
+}
+
+impl AttackRoll {
+    /// `s` could still become an attack roll, ex. `nat ` or `-`
+    pub fn in_progress(s: &str) -> bool {
+        let rest = ["nat", "na", "n"].iter()
+            .find_map(|prefix| s.strip_prefix(prefix))
+            .map_or(s, str::trim_start);
+        let rest = rest.strip_prefix('-').unwrap_or(rest);
+        rest.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+impl FromStr for AttackRoll {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix("nat").or_else(|| s.strip_prefix('n')) {
+            Some(natural) => match natural.trim().parse() {
+                Ok(roll @ (1 | 20)) => Ok(Self::Natural(roll)),
+                _ => Err(()),
+            },
+            None => s.parse().map(Self::Total).map_err(|_| ()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taken(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|&name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn suffixed_name_counts_on_past_the_highest() {
+        let taken = taken(&["Goblin", "Goblin 1", "Goblin 3"]);
+        assert_eq!(suffixed_name(&taken, "Goblin", SuffixStyle::Number, &[], false), "Goblin 4");
+        assert_eq!(suffixed_name(&taken, "Goblin", SuffixStyle::Number, &[], true), "Goblin 2");
+        assert_eq!(suffixed_name(&taken, "Orc", SuffixStyle::Number, &[], false), "Orc");
+    }
+
+    #[test]
+    fn suffixed_name_with_letters() {
+        let gap = taken(&["Goblin", "Goblin C"]);
+        assert_eq!(suffixed_name(&gap, "Goblin", SuffixStyle::Letter, &[], false), "Goblin D");
+        assert_eq!(suffixed_name(&gap, "Goblin", SuffixStyle::Letter, &[], true), "Goblin B");
+        assert_eq!(letters(26), "Z");
+        assert_eq!(letters(27), "AA");
+    }
+
+    #[test]
+    fn suffixed_name_with_colors_comes_around_again_numbered() {
+        let colors = [String::from("red"), String::from("blue")];
+        assert_eq!(suffixed_name(&taken(&["Goblin"]), "Goblin", SuffixStyle::Color, &colors, false), "Goblin (red)");
+        let all = taken(&["Goblin", "Goblin (red)", "Goblin (blue)"]);
+        assert_eq!(suffixed_name(&all, "Goblin", SuffixStyle::Color, &colors, false), "Goblin (red 2)");
+        let gap = taken(&["Goblin", "Goblin (blue)"]);
+        assert_eq!(suffixed_name(&gap, "Goblin", SuffixStyle::Color, &colors, false), "Goblin (red 2)");
+        assert_eq!(suffixed_name(&gap, "Goblin", SuffixStyle::Color, &colors, true), "Goblin (red)");
+    }
+
+    #[test]
+    fn suffixed_name_only_counts_suffixes_in_its_style() {
+        // `Goblin 2` isn't a letter suffix, so it doesn't count towards the highest letter
+        let taken = taken(&["Goblin", "Goblin 2"]);
+        assert_eq!(suffixed_name(&taken, "Goblin", SuffixStyle::Number, &[], false), "Goblin 3");
+        assert_eq!(suffixed_name(&taken, "Goblin", SuffixStyle::Letter, &[], false), "Goblin B");
+    }
+
+    /// `censored` has letters where `name` has letters, in the same case, and everything else the same
+    fn same_shape(name: &str, censored: &str) -> bool {
+        name.chars().count() == censored.chars().count()
+            && name.chars().zip(censored.chars()).all(|(n, c)| if n.is_alphabetic() {
+                c.is_ascii_alphabetic() && n.is_uppercase() == c.is_uppercase()
+            } else {
+                n == c
+            })
+    }
+
+    #[test]
+    fn censor_name_keeps_the_shape_of_the_name() {
+        for name in ["Sir Reginald II", "Goblin 3", "O'Brien-Smith", "the Nameless, 2nd", "  spaced  out "] {
+            let censored = censor_name(name);
+            assert!(same_shape(name, &censored), "{name} censored as {censored}");
+        }
+        assert_eq!(censor_name(""), "");
+        assert_eq!(censor_name("42 - 7"), "42 - 7");
+    }
+
+    fn current_max(hp: &str) -> Option<(u32, u32)> {
+        hp.parse::<CurrentMaxHp>().ok()?.into_numbers()
+    }
+
+    #[test]
+    fn current_max_hp() {
+        assert_eq!(current_max("22/30"), Some((22, 30)));
+        assert_eq!(current_max(" 22 / 30 "), Some((22, 30)));
+        assert_eq!(current_max("30"), Some((30, 30)));
+        assert_eq!(current_max("5 + 5"), Some((10, 10)));
+    }
+
+    #[test]
+    fn current_max_hp_rolls_dice_for_current() {
+        for _ in 0..20 {
+            let (current, max) = current_max("2d6/30").unwrap();
+            assert!((2..=12).contains(&current), "2d6 rolled {current}");
+            assert_eq!(max, 30);
+        }
+    }
+
+    #[test]
+    fn current_max_hp_rejects_no_current() {
+        assert!("/30".parse::<CurrentMaxHp>().is_err());
+        assert!("1/2/3".parse::<CurrentMaxHp>().is_err());
+        // still being typed, so it's allowed in the box but isn't a number yet
+        assert!("22/".parse::<CurrentMaxHp>().is_ok());
+        assert_eq!(current_max("22/"), None);
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use reqwest::header::{self, HeaderValue};
+
+/// how long to wait for turns to stop changing before announcing, so clicking through several
+/// turns only posts the last one
+pub const COALESCE: Duration = Duration::from_millis(1500);
+
+/// Posts `content` (discord markdown) to a discord webhook.
+///
+/// The errors deliberately never include `url`, it has the webhook's token in it.
+pub async fn post(url: String, content: String) -> Result<(), String> {
+    let body = serde_json::json!({ "content": content }).to_string();
+    let response = reqwest::Client::new()
+        .post(url)
+        .header(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))
+        .body(body)
+        .send()
+        .await
+        .map_err(|_| String::from("couldn't reach Discord"))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Discord responded with {}", response.status()))
+    }
+}
+
+/// Waits longer after each failure in a row, so a bad url doesn't post (and error) every turn
+#[derive(Debug, Default)]
+pub struct Backoff {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Backoff {
+    const BASE: Duration = Duration::from_secs(30);
+    const MAX: Duration = Duration::from_secs(10 * 60);
+
+    pub fn ready(&self) -> bool {
+        self.retry_at.map_or(true, |retry_at| Instant::now() >= retry_at)
+    }
+
+    pub fn succeeded(&mut self) {
+        *self = Self::default();
+    }
+
+    /// `true` for the first failure in a row, which is the only one worth telling the user about
+    pub fn failed(&mut self) -> bool {
+        self.failures += 1;
+        let wait = Self::BASE * 2_u32.saturating_pow(self.failures - 1);
+        self.retry_at = Some(Instant::now() + wait.min(Self::MAX));
+        self.failures == 1
+    }
+}